@@ -1,21 +1,36 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use cratedocs_mcp::tools::DocRouter;
-use mcp_core::Content;
-use mcp_server::router::RouterService;
+use cratedocs_mcp::tools::{DocRouter, DocService};
+use cratedocs_mcp::transport::notifications::with_notifications;
+use cratedocs_mcp::transport::auth::{Auth, AuthConfig};
+use cratedocs_mcp::transport::http_sse_server::App;
+use cratedocs_mcp::transport::origin::OriginPolicy;
+use cratedocs_mcp::transport::tls::{Tls, RELOAD_INTERVAL};
+use cratedocs_mcp::transport::sessions::{SessionLimits, SessionRegistry};
+#[cfg(unix)]
+use cratedocs_mcp::transport::unix_socket::{bridge, default_socket_path, SocketListener, UnixSocketServer};
+use mcp_core::{Content, ResourceContents};
 use mcp_server::{ByteTransport, Router, Server};
 use serde_json::json;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 use tokio::io::{stdin, stdout};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{self, EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
-use cratedocs_mcp::tools::tldr;
+use cratedocs_mcp::tools::pipeline::Pipeline;
+use cratedocs_mcp::tools::tokens::{configure_tokenizer, TokenizerSource};
 
 #[derive(Parser)]
 #[command(author, version = "0.2.0", about, long_about = None)]
 #[command(propagate_version = true)]
 #[command(disable_version_flag = true)]
 struct Cli {
+    /// Tokenizer used to count tokens: a Hugging Face model name, a path to a local
+    /// tokenizer.json, or "heuristic" [default: $CRATEDOCS_TOKENIZER or bert-base-cased]
+    #[arg(long, global = true)]
+    tokenizer: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -32,11 +47,17 @@ enum Commands {
         /// Summarize output by stripping LICENSE and VERSION sections (TL;DR mode)
         #[arg(long)]
         tldr: bool,
-        /// Maximum number of tokens for output (token-aware truncation)
+        /// Comma-separated output filters and presets applied to every tool (e.g. "compact")
+        #[arg(long)]
+        filters: Option<String>,
+        /// Maximum number of tokens per page of tool output (longer output is paginated)
         #[arg(long)]
         max_tokens: Option<usize>,
+        /// Seconds between checks of subscribed crates for new releases
+        #[arg(long, default_value_t = 300)]
+        poll_interval: u64,
     },
-    /// Run the server with HTTP/SSE interface
+    /// Run the server with Streamable HTTP (/mcp), HTTP/SSE (/sse) and WebSocket (/ws) interfaces
     Http {
         /// Address to bind the HTTP server to
         #[arg(short, long, default_value = "127.0.0.1:8080")]
@@ -45,10 +66,111 @@ enum Commands {
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+        
+        /// Summarize output by stripping LICENSE and VERSION sections (TL;DR mode)
+        #[arg(long)]
+        tldr: bool,
+        
+        /// Comma-separated output filters and presets applied to every tool (e.g. "compact")
+        #[arg(long)]
+        filters: Option<String>,
+        
+        /// Maximum number of tokens per page of tool output (longer output is paginated)
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        
+        /// Seconds between checks of subscribed crates for new releases
+        #[arg(long, default_value_t = 300)]
+        poll_interval: u64,
+        
+        /// Maximum number of concurrent sessions across all transports
+        #[arg(long, default_value_t = 100)]
+        max_sessions: usize,
+        
+        /// Seconds without a request after which a session is closed
+        #[arg(long, default_value_t = 1800)]
+        session_idle_timeout: u64,
+        
+        /// Seconds between pings of WebSocket clients; a client that sends nothing, not
+        /// even a pong, between two pings is disconnected
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        ws_ping_interval: u64,
+        
+        /// JSON file of bearer tokens and their rate limits and tools; tokens in
+        /// $CRATEDOCS_TOKENS are added. Without either, requests are not authenticated
+        #[arg(long)]
+        auth_file: Option<PathBuf>,
+        
+        /// Serve /admin/sessions and /metrics without authentication. Without this flag they
+        /// are only served when authentication is enabled, and only to admin tokens
+        #[arg(long)]
+        admin_open: bool,
+        
+        /// PEM certificate chain; serves HTTPS together with --tls-key. Both files are
+        /// reloaded when they change
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        
+        /// PEM private key for --tls-cert
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        
+        /// Browser origin allowed to use the server, besides loopback ones (repeatable,
+        /// or comma-separated; "*" allows any). Requests from other origins are rejected
+        #[arg(long, value_delimiter = ',')]
+        allowed_origin: Vec<String>,
+        
+        /// Host name requests may be addressed to when bound to a loopback address, besides
+        /// loopback names and the hosts of allowed origins (repeatable, or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        allowed_host: Vec<String>,
+    },
+    /// Run a shared server on a Unix domain socket, one session per connection
+    #[cfg(unix)]
+    Unix {
+        /// Socket path [default: $XDG_RUNTIME_DIR/cratedocs.sock]. Only the current user
+        /// may connect to it
+        #[arg(long)]
+        path: Option<PathBuf>,
+        
+        /// Enable debug logging
+        #[arg(short, long)]
+        debug: bool,
+        
+        /// Summarize output by stripping LICENSE and VERSION sections (TL;DR mode)
+        #[arg(long)]
+        tldr: bool,
+        
+        /// Comma-separated output filters and presets applied to every tool (e.g. "compact")
+        #[arg(long)]
+        filters: Option<String>,
+        
+        /// Maximum number of tokens per page of tool output (longer output is paginated)
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        
+        /// Seconds between checks of subscribed crates for new releases
+        #[arg(long, default_value_t = 300)]
+        poll_interval: u64,
+        
+        /// Maximum number of concurrent sessions
+        #[arg(long, default_value_t = 100)]
+        max_sessions: usize,
+        
+        /// Seconds without a request after which a session is closed
+        #[arg(long, default_value_t = 1800)]
+        session_idle_timeout: u64,
+    },
+    /// Connect stdin and stdout to a server started with `cratedocs unix`
+    #[cfg(unix)]
+    Bridge {
+        /// Socket path [default: $XDG_RUNTIME_DIR/cratedocs.sock]
+        #[arg(long)]
+        path: Option<PathBuf>,
     },
     /// Test tools directly from the CLI
     Test {
-        /// The tool to test (lookup_crate, search_crates, lookup_item, list_crate_items)
+        /// The tool to test (lookup_crate, search_crates, lookup_item, list_crate_items, diff_crate_api, find_examples, view_source, search_in_crate, search_by_signature, find_impls, doc_outline)
         #[arg(long, default_value = "lookup_crate")]
         tool: String,
         
@@ -56,11 +178,11 @@ enum Commands {
         #[arg(long)]
         crate_name: Option<String>,
         
-        /// Item path for lookup_item (e.g., std::vec::Vec)
+        /// Item path for lookup_item, find_examples and view_source (e.g., std::vec::Vec)
         #[arg(long)]
         item_path: Option<String>,
         
-        /// Search query for search_crates
+        /// Search query for search_crates, search_in_crate and search_by_signature
         #[arg(long)]
         query: Option<String>,
         
@@ -68,7 +190,39 @@ enum Commands {
         #[arg(long)]
         version: Option<String>,
         
-        /// Result limit for search_crates
+        /// Documentation view for lookup_crate (readme, overview, metadata, all)
+        #[arg(long)]
+        view: Option<String>,
+        
+        /// Comma-separated section headings for lookup_crate and lookup_item (e.g., Examples,Panics)
+        #[arg(long)]
+        sections: Option<String>,
+        
+        /// Version being upgraded from, for diff_crate_api
+        #[arg(long)]
+        old_version: Option<String>,
+        
+        /// Version being upgraded to, for diff_crate_api
+        #[arg(long)]
+        new_version: Option<String>,
+        
+        /// Trait whose implementors find_impls lists (e.g., tower::Service)
+        #[arg(long)]
+        trait_name: Option<String>,
+        
+        /// Type whose trait impls find_impls lists (e.g., bytes::Bytes)
+        #[arg(long)]
+        type_name: Option<String>,
+        
+        /// Also scan direct dependencies in find_impls
+        #[arg(long)]
+        include_dependencies: bool,
+        
+        /// Also print the JSON record of lookup_crate, lookup_item, search_crates or list_crate_items
+        #[arg(long)]
+        structured: bool,
+        
+        /// Result limit for search_crates, search_in_crate and search_by_signature
         #[arg(long)]
         limit: Option<u32>,
         
@@ -96,10 +250,22 @@ enum Commands {
         #[arg(long)]
         tldr: bool,
     
-        /// Maximum number of tokens for output (token-aware truncation)
+        /// Comma-separated output filters and presets (e.g. "tldr,drop_badges" or "compact")
+        #[arg(long)]
+        filters: Option<String>,
+    
+        /// Maximum number of tokens per page of output (longer output is paginated)
         #[arg(long)]
         max_tokens: Option<usize>,
         
+        /// Page of the output to show (1-based)
+        #[arg(long)]
+        page: Option<usize>,
+        
+        /// Continuation cursor printed at the end of the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+        
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
@@ -109,20 +275,105 @@ enum Commands {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(spec) = &cli.tokenizer {
+        // Nothing has counted tokens yet, so the choice always takes effect.
+        let _ = configure_tokenizer(TokenizerSource::parse(spec));
+    }
 
     match cli.command {
         Commands::Version => {
             println!("{}", env!("CARGO_PKG_VERSION"));
             Ok(())
         },
-        Commands::Stdio { debug, tldr, max_tokens } => run_stdio_server(debug, tldr, max_tokens).await,
-        Commands::Http { address, debug } => run_http_server(address, debug).await,
+        Commands::Stdio { debug, tldr, filters, max_tokens, poll_interval } => {
+            let router = DocRouter::new_with_tldr_and_max_tokens(tldr, max_tokens)
+                .with_pipeline(parse_filters(filters.as_deref())?);
+            run_stdio_server(debug, router, Duration::from_secs(poll_interval)).await
+        }
+        Commands::Http {
+            address,
+            debug,
+            tldr,
+            filters,
+            max_tokens,
+            poll_interval,
+            max_sessions,
+            session_idle_timeout,
+            ws_ping_interval,
+            auth_file,
+            admin_open,
+            tls_cert,
+            tls_key,
+            allowed_origin,
+            allowed_host,
+        } => {
+            let auth = match auth_file {
+                Some(path) => AuthConfig::load(&path)?,
+                None => AuthConfig::default(),
+            }
+            .with_env_tokens();
+            // One router, and so one cache, for all sessions
+            let router = DocRouter::new_with_tldr_and_max_tokens(tldr, max_tokens)
+                .with_pipeline(parse_filters(filters.as_deref())?);
+            let app = App::new()
+                .with_doc_router(router)
+                .with_poll_interval(Duration::from_secs(poll_interval))
+                .with_session_limits(SessionLimits {
+                    max_sessions,
+                    idle_timeout: Duration::from_secs(session_idle_timeout),
+                })
+                .with_ping_interval(Duration::from_secs(ws_ping_interval))
+                .with_auth(Auth::new(auth))
+                .with_admin_open(admin_open)
+                .with_origins(OriginPolicy::new(&allowed_origin).with_allowed_hosts(&allowed_host));
+            let tls = match (tls_cert, tls_key) {
+                (Some(cert), Some(key)) => Some(Tls::load(cert, key)?),
+                _ => None,
+            };
+            run_http_server(address, debug, app, tls).await
+        }
+        #[cfg(unix)]
+        Commands::Unix {
+            path,
+            debug,
+            tldr,
+            filters,
+            max_tokens,
+            poll_interval,
+            max_sessions,
+            session_idle_timeout,
+        } => {
+            let router = DocRouter::new_with_tldr_and_max_tokens(tldr, max_tokens)
+                .with_pipeline(parse_filters(filters.as_deref())?);
+            let server = UnixSocketServer::new()
+                .with_router(router)
+                .with_poll_interval(Duration::from_secs(poll_interval))
+                .with_registry(SessionRegistry::new(SessionLimits {
+                    max_sessions,
+                    idle_timeout: Duration::from_secs(session_idle_timeout),
+                }));
+            run_unix_server(path.unwrap_or_else(default_socket_path), debug, server).await
+        }
+        #[cfg(unix)]
+        Commands::Bridge { path } => {
+            bridge(&path.unwrap_or_else(default_socket_path), stdin(), stdout()).await?;
+            // Reading stdin blocks a thread that would keep the runtime from shutting down
+            std::process::exit(0)
+        }
         Commands::Test {
             tool,
             crate_name,
             item_path,
             query,
             version,
+            view,
+            sections,
+            old_version,
+            new_version,
+            trait_name,
+            type_name,
+            include_dependencies,
+            structured,
             limit,
             item_type,
             visibility,
@@ -130,7 +381,10 @@ async fn main() -> Result<()> {
             format,
             output,
             tldr,
+            filters,
             max_tokens,
+            page,
+            cursor,
             debug
         } => run_test_tool(TestToolConfig {
             tool,
@@ -138,6 +392,14 @@ async fn main() -> Result<()> {
             item_path,
             query,
             version,
+            view,
+            sections,
+            old_version,
+            new_version,
+            trait_name,
+            type_name,
+            include_dependencies,
+            structured,
             limit,
             item_type,
             visibility,
@@ -145,13 +407,21 @@ async fn main() -> Result<()> {
             format,
             output,
             tldr,
+            filters,
             max_tokens,
+            page,
+            cursor,
             debug
         }).await,
     }
 }
 
-async fn run_stdio_server(debug: bool, tldr: bool, max_tokens: Option<usize>) -> Result<()> {
+/// The output pipeline selected by `--filters`; empty when the option is not given.
+fn parse_filters(filters: Option<&str>) -> Result<Pipeline> {
+    Pipeline::parse(filters.unwrap_or_default()).map_err(|e| anyhow::anyhow!(e))
+}
+
+async fn run_stdio_server(debug: bool, router: DocRouter, poll_interval: Duration) -> Result<()> {
     // Set up file appender for logging
     let file_appender = RollingFileAppender::new(Rotation::DAILY, "logs", "stdio-server.log");
 
@@ -169,21 +439,18 @@ async fn run_stdio_server(debug: bool, tldr: bool, max_tokens: Option<usize>) ->
 
     tracing::info!("Starting MCP documentation server in STDIN/STDOUT mode");
 
-    // Create an instance of our documentation router
-    // If tldr is needed globally, you may want to pass it to DocRouter or handle it in tool output
-    let router = RouterService(DocRouter::new_with_tldr_and_max_tokens(tldr, max_tokens));
+    // Wrap the documentation router, configured with the output defaults, in a service
+    let (router, notifications) = DocService::with_subscriptions(router, poll_interval);
 
-    // Create and run the server
+    // Create and run the server; resource update notifications share stdout with responses
     let server = Server::new(router);
-    let transport = ByteTransport::new(stdin(), stdout());
+    let transport = ByteTransport::new(stdin(), with_notifications(stdout(), notifications));
 
     tracing::info!("Documentation server initialized and ready to handle requests");
-    // Note: tldr is parsed and available, but not yet used in stdio mode.
-    // If you want to apply TLDR globally, you would need to modify DocRouter or Server to use it.
     Ok(server.run(transport).await?)
 }
 
-async fn run_http_server(address: String, debug: bool) -> Result<()> {
+async fn run_http_server(address: String, debug: bool, app: App, tls: Option<Tls>) -> Result<()> {
     // Setup tracing
     let level = if debug { "debug" } else { "info" };
     
@@ -198,18 +465,65 @@ async fn run_http_server(address: String, debug: bool) -> Result<()> {
     // Parse socket address
     let addr: SocketAddr = address.parse()?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
+    // Pages on other sites may reach a loopback address through DNS rebinding, under their own host name
+    let app = if addr.ip().is_loopback() {
+        let origins = app.origins.clone().with_host_validation(true);
+        app.with_origins(origins)
+    } else {
+        app
+    };
 
+    let (scheme, ws_scheme) = if tls.is_some() { ("https", "wss") } else { ("http", "ws") };
     tracing::debug!("Rust Documentation Server listening on {}", listener.local_addr()?);
-    tracing::info!("Access the Rust Documentation Server at http://{}/sse", addr);
+    tracing::info!("Access the Rust Documentation Server at {}://{}/mcp (Streamable HTTP), {}://{}/sse (HTTP/SSE) or {}://{}/ws (WebSocket)", scheme, addr, scheme, addr, ws_scheme, addr);
+    if !app.auth.is_enabled() && !addr.ip().is_loopback() {
+        tracing::warn!("Serving {} without authentication; set --auth-file or $CRATEDOCS_TOKENS", addr);
+    }
+    if app.admin_open && !app.auth.is_enabled() {
+        tracing::warn!("Anyone reaching {} may list and close sessions (--admin-open)", addr);
+    }
+    if app.origins.allows_any() {
+        tracing::warn!("Any browser origin may call the server (--allowed-origin '*')");
+    }
     
-    // Create app and run server
-    let app = cratedocs_mcp::transport::http_sse_server::App::new();
-    axum::serve(listener, app.router()).await?;
+    // Run the configured app
+    app.registry.spawn_reaper();
+    match tls {
+        Some(tls) => {
+            tls.spawn_reloader(RELOAD_INTERVAL);
+            axum::serve(tls.bind(listener)?, app.router()).await?;
+        }
+        None => axum::serve(listener, app.router()).await?,
+    }
     
     Ok(())
 }
 
-// --- TLDR Helper Function ---
+#[cfg(unix)]
+async fn run_unix_server(path: PathBuf, debug: bool, server: UnixSocketServer) -> Result<()> {
+    let level = if debug { "debug" } else { "info" };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| format!("{},{}", level, env!("CARGO_CRATE_NAME")).into()),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    let listener = SocketListener::bind(path).await?;
+    tracing::info!("Rust Documentation Server listening on {}", listener.path().display());
+    server.registry.spawn_reaper();
+
+    // Stop on Ctrl-C or SIGTERM, so that dropping the listener removes the socket file
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        _ = server.serve(&listener) => {}
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+    tracing::info!("Shutting down");
+    Ok(())
+}
 
 /// Configuration for the test tool
 struct TestToolConfig {
@@ -218,6 +532,14 @@ struct TestToolConfig {
     item_path: Option<String>,
     query: Option<String>,
     version: Option<String>,
+    view: Option<String>,
+    sections: Option<String>,
+    old_version: Option<String>,
+    new_version: Option<String>,
+    trait_name: Option<String>,
+    type_name: Option<String>,
+    include_dependencies: bool,
+    structured: bool,
     limit: Option<u32>,
     item_type: Option<String>,
     visibility: Option<String>,
@@ -225,7 +547,10 @@ struct TestToolConfig {
     format: Option<String>,
     output: Option<String>,
     tldr: bool,
+    filters: Option<String>,
     max_tokens: Option<usize>,
+    page: Option<usize>,
+    cursor: Option<String>,
     debug: bool,
 }
 
@@ -237,11 +562,22 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
         item_path,
         query,
         version,
+        view,
+        sections,
+        old_version,
+        new_version,
+        trait_name,
+        type_name,
+        include_dependencies,
+        structured,
         limit,
         format,
         output,
         tldr,
+        filters,
         max_tokens,
+        page,
+        cursor,
         debug,
         item_type,
         visibility,
@@ -253,22 +589,41 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
         println!("Usage examples:");
         println!("  cargo run --bin cratedocs -- test --tool lookup_crate --crate-name serde");
         println!("  cargo run --bin cratedocs -- test --tool lookup_crate --crate-name tokio --version 1.35.0");
+        println!("  cargo run --bin cratedocs -- test --tool lookup_crate --crate-name tokio --view readme");
+        println!("  cargo run --bin cratedocs -- test --tool doc_outline --crate-name tokio --item-path sync::mpsc::Sender");
+        println!("  cargo run --bin cratedocs -- test --tool lookup_item --crate-name tokio --item-path sync::mpsc::Sender --sections Examples");
         println!("  cargo run --bin cratedocs -- test --tool lookup_item --crate-name tokio --item-path sync::mpsc::Sender");
         println!("  cargo run --bin cratedocs -- test --tool lookup_item --crate-name serde --item-path Serialize --version 1.0.147");
         println!("  cargo run --bin cratedocs -- test --tool search_crates --query logger --limit 5");
         println!("  cargo run --bin cratedocs -- test --tool search_crates --query logger --format json");
         println!("  cargo run --bin cratedocs -- test --tool lookup_crate --crate-name tokio --output tokio-docs.md");
+        println!("  cargo run --bin cratedocs -- test --tool diff_crate_api --crate-name reqwest --old-version 0.11.27 --new-version 0.12.0");
+        println!("  cargo run --bin cratedocs -- test --tool find_examples --crate-name tokio --version 1.38.0 --item-path sync::mpsc::channel");
+        println!("  cargo run --bin cratedocs -- test --tool view_source --crate-name serde --version 1.0.203 --item-path de::Visitor");
+        println!("  cargo run --bin cratedocs -- test --tool search_in_crate --crate-name tokio --version 1.38.0 --query \"graceful shutdown\"");
+        println!("  cargo run --bin cratedocs -- test --tool search_by_signature --crate-name url --version 2.5.0 --query \"&str -> Result<Url, _>\"");
+        println!("  cargo run --bin cratedocs -- test --tool find_impls --crate-name tower --version 0.4.13 --trait-name Service");
+        println!("  cargo run --bin cratedocs -- test --tool find_impls --crate-name bytes --version 1.6.0 --type-name Bytes");
         println!("\nAvailable tools:");
         println!("  lookup_crate   - Look up documentation for a Rust crate");
         println!("  lookup_item    - Look up documentation for a specific item in a crate");
         println!("                   Format: 'module::path::ItemName' (e.g., 'sync::mpsc::Sender')");
         println!("                   The tool will try to detect if it's a struct, enum, trait, fn, or macro");
         println!("  search_crates  - Search for crates on crates.io");
+        println!("  diff_crate_api - Compare the public API of two crate versions");
+        println!("  find_examples  - Find doctests, examples/ and tests/ files and README snippets");
+        println!("  view_source    - Show the source of an item with file path and line numbers");
+        println!("  search_in_crate - Full-text search over a crate's item names, docs and README");
+        println!("  search_by_signature - Find functions by type signature (e.g., '&str -> Result<Url, _>')");
+        println!("  find_impls     - List implementors of a trait or traits implemented for a type");
+        println!("  doc_outline    - Show the section headings of a docs page with token sizes");
         println!("  help           - Show this help information");
         println!("\nOutput options:");
         println!("  --format       - Output format: markdown (default), text, json");
         println!("  --output       - Write output to a file instead of stdout");
         println!("  --tldr         - Summarize output by stripping LICENSE and VERSION sections");
+        println!("  --filters      - Output filters and presets, e.g. compact or tldr,drop_badges,dedupe_links");
+        println!("  --structured   - Also print the JSON record (lookup_crate, lookup_item, search_crates, list_crate_items)");
         return Ok(());
     }
     
@@ -281,16 +636,15 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
         .with_target(false)
         .init();
 
-    // Create router instance
-    let router = DocRouter::new();
+    // Create router instance; it filters the output and splits it into pages of max_tokens
+    let router = DocRouter::new_with_tldr_and_max_tokens(tldr, max_tokens)
+        .with_pipeline(parse_filters(filters.as_deref())?);
     
     tracing::info!("Testing tool: {}", tool);
     
-    // Get format option (default to markdown)
-    let format = format.unwrap_or_else(|| "markdown".to_string());
     
     // Prepare arguments based on the tool being tested
-    let arguments = match tool.as_str() {
+    let mut arguments = match tool.as_str() {
         "lookup_crate" => {
             let crate_name = crate_name.ok_or_else(|| 
                 anyhow::anyhow!("--crate-name is required for lookup_crate tool"))?;
@@ -298,6 +652,8 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
             json!({
                 "crate_name": crate_name,
                 "version": version,
+                "view": view,
+                "sections": sections,
             })
         },
         "lookup_item" => {
@@ -310,6 +666,7 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
                 "crate_name": crate_name,
                 "item_path": item_path,
                 "version": version,
+                "sections": sections,
             })
         },
         "search_crates" => {
@@ -336,8 +693,109 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
             });
             arguments
         },
+        "diff_crate_api" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for diff_crate_api tool"))?;
+            let old_version = old_version.ok_or_else(||
+                anyhow::anyhow!("--old-version is required for diff_crate_api tool"))?;
+            let new_version = new_version.ok_or_else(||
+                anyhow::anyhow!("--new-version is required for diff_crate_api tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "old_version": old_version,
+                "new_version": new_version,
+            })
+        },
+        "find_examples" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for find_examples tool"))?;
+            let version = version.ok_or_else(||
+                anyhow::anyhow!("--version is required for find_examples tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "version": version,
+                "item_path": item_path,
+            })
+        },
+        "view_source" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for view_source tool"))?;
+            let version = version.ok_or_else(||
+                anyhow::anyhow!("--version is required for view_source tool"))?;
+            let item_path = item_path.ok_or_else(||
+                anyhow::anyhow!("--item-path is required for view_source tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "version": version,
+                "item_path": item_path,
+            })
+        },
+        "search_in_crate" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for search_in_crate tool"))?;
+            let version = version.ok_or_else(||
+                anyhow::anyhow!("--version is required for search_in_crate tool"))?;
+            let query = query.ok_or_else(||
+                anyhow::anyhow!("--query is required for search_in_crate tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "version": version,
+                "query": query,
+                "limit": limit,
+            })
+        },
+        "search_by_signature" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for search_by_signature tool"))?;
+            let version = version.ok_or_else(||
+                anyhow::anyhow!("--version is required for search_by_signature tool"))?;
+            let query = query.ok_or_else(||
+                anyhow::anyhow!("--query is required for search_by_signature tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "version": version,
+                "query": query,
+                "limit": limit,
+            })
+        },
+        "find_impls" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for find_impls tool"))?;
+            let version = version.ok_or_else(||
+                anyhow::anyhow!("--version is required for find_impls tool"))?;
+            if trait_name.is_some() == type_name.is_some() {
+                return Err(anyhow::anyhow!("exactly one of --trait-name or --type-name is required for find_impls tool"));
+            }
+            
+            json!({
+                "crate_name": crate_name,
+                "version": version,
+                "trait_name": trait_name,
+                "type_name": type_name,
+                "include_dependencies": include_dependencies,
+            })
+        },
+        "doc_outline" => {
+            let crate_name = crate_name.ok_or_else(||
+                anyhow::anyhow!("--crate-name is required for doc_outline tool"))?;
+            
+            json!({
+                "crate_name": crate_name,
+                "item_path": item_path,
+                "version": version,
+            })
+        },
         _ => return Err(anyhow::anyhow!("Unknown tool: {}", tool)),
     };
+    arguments["page"] = json!(page);
+    arguments["cursor"] = json!(cursor);
+    arguments["format"] = json!(format);
+    arguments["structured"] = json!(structured);
     
     // Call the tool and get results
     tracing::debug!("Calling {} with arguments: {}", tool, arguments);
@@ -363,91 +821,8 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
     if !result.is_empty() {
         for content in result {
             if let Content::Text(text) = content {
-                let mut content_str = text.text;
-
-                // If max_tokens is set, truncate output to fit within the limit
-                if let Some(max_tokens) = max_tokens {
-                    match cratedocs_mcp::tools::count_tokens(&content_str) {
-                        Ok(token_count) if token_count > max_tokens => {
-                            // Truncate by character, then to previous word boundary, and append Mandarin to indicate truncation.
-                            let mut truncated = content_str.clone();
-                            while cratedocs_mcp::tools::count_tokens(&truncated).map_or(0, |c| c) > max_tokens && !truncated.is_empty() {
-                                truncated.pop();
-                            }
-                            if let Some(last_space) = truncated.rfind(' ') {
-                                truncated.truncate(last_space);
-                            }
-                            truncated.push_str(" 内容被截断");
-                            content_str = truncated;
-                        }
-                        _ => {}
-                    }
-                }
-
-                // TL;DR processing: strip LICENSE and VERSION(S) sections if --tldr is set
-                if tldr {
-                    content_str = tldr::apply_tldr(&content_str);
-                }
+                let formatted_output = text.text;
 
-                let formatted_output = match format.as_str() {
-                    "json" => {
-                        // For search_crates, which may return JSON content
-                        if tool == "search_crates" && content_str.trim().starts_with('{') {
-                            // If content is already valid JSON, pretty print it
-                            match serde_json::from_str::<serde_json::Value>(&content_str) {
-                                Ok(json_value) => serde_json::to_string_pretty(&json_value)
-                                    .unwrap_or_else(|_| content_str.clone()),
-                                Err(_) => {
-                                    // If it's not JSON, wrap it in a simple JSON object
-                                    json!({ "content": content_str }).to_string()
-                                }
-                            }
-                        } else {
-                            // For non-JSON content, wrap in a JSON object
-                            json!({ "content": content_str }).to_string()
-                        }
-                    },
-                    "text" => {
-                        // For JSON content, try to extract plain text
-                        if content_str.trim().starts_with('{') && tool == "search_crates" {
-                            match serde_json::from_str::<serde_json::Value>(&content_str) {
-                                Ok(json_value) => {
-                                    // Try to create a simple text representation of search results
-                                    if let Some(crates) = json_value.get("crates").and_then(|v| v.as_array()) {
-                                        let mut text_output = String::from("Search Results:\n\n");
-                                        for (i, crate_info) in crates.iter().enumerate() {
-                                            let name = crate_info.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown");
-                                            let description = crate_info.get("description").and_then(|v| v.as_str()).unwrap_or("No description");
-                                            let downloads = crate_info.get("downloads").and_then(|v| v.as_u64()).unwrap_or(0);
-                                            
-                                            text_output.push_str(&format!("{}. {} - {} (Downloads: {})\n",
-                                                i + 1, name, description, downloads));
-                                        }
-                                        text_output
-                                    } else {
-                                        content_str
-                                    }
-                                },
-                                Err(_) => content_str,
-                            }
-                        } else {
-                            // For markdown content, use a simple approach to convert to plain text
-                            // This is a very basic conversion - more sophisticated would need a proper markdown parser
-                            content_str
-                                .replace("# ", "")
-                                .replace("## ", "")
-                                .replace("### ", "")
-                                .replace("#### ", "")
-                                .replace("##### ", "")
-                                .replace("###### ", "")
-                                .replace("**", "")
-                                .replace("*", "")
-                                .replace("`", "")
-                        }
-                    },
-                    _ => content_str, // Default to original markdown for "markdown" or any other format
-                };
-                
                 // Output to file or stdout
                 match &output {
                     Some(file_path) => {
@@ -474,6 +849,12 @@ async fn run_test_tool(config: TestToolConfig) -> Result<()> {
                         println!("\n--- END RESULT ---");
                     }
                 }
+            } else if let Content::Resource(resource) = content {
+                if let ResourceContents::TextResourceContents { uri, text, .. } = resource.resource {
+                    println!("\n--- STRUCTURED RESULT ({}) ---\n", uri);
+                    println!("{}", text);
+                    println!("\n--- END STRUCTURED RESULT ---");
+                }
             } else {
                 println!("Received non-text content");
             }
@@ -0,0 +1,477 @@
+use crate::tools::item_list::{
+    download_and_cache_crate, hidden_impls, impl_type_name, module_exports, parse_crate_modules, public_type_paths,
+    release_spans,
+};
+use anyhow::{bail, Result};
+use quote::ToTokens;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use syn::{Attribute, ImplItem, Item, TraitItem, UseTree, Visibility};
+
+/// A single entry of a crate's public API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiItem {
+    pub kind: &'static str,
+    pub path: String,
+    pub signature: String,
+    /// Adding this item breaks downstream code, e.g. a variant of an exhaustive
+    /// enum or a trait method without a default body.
+    pub breaking_if_added: bool,
+}
+
+/// Public API of a crate keyed by `(path, kind)`.
+pub type PublicApi = BTreeMap<(String, &'static str), ApiItem>;
+
+/// Differences between two versions of a public API.
+#[derive(Debug, Default)]
+pub struct ApiDiff {
+    pub added: Vec<ApiItem>,
+    pub removed: Vec<ApiItem>,
+    pub changed: Vec<(ApiItem, ApiItem)>,
+    /// Added items that break existing users of the old version.
+    pub breaking_additions: Vec<ApiItem>,
+}
+
+impl ApiDiff {
+    pub fn breaking_count(&self) -> usize {
+        self.removed.len() + self.changed.len() + self.breaking_additions.len()
+    }
+
+    /// The smallest semver bump that covers this diff.
+    pub fn required_bump(&self, old_version: &str) -> &'static str {
+        let pre_1_0 = old_version.trim_start_matches('v').starts_with("0.");
+        if self.breaking_count() > 0 {
+            if pre_1_0 { "minor (0.x breaking)" } else { "major" }
+        } else if !self.added.is_empty() {
+            if pre_1_0 { "patch (0.x additive)" } else { "minor" }
+        } else {
+            "patch"
+        }
+    }
+
+    pub fn to_markdown(&self, crate_name: &str, old_version: &str, new_version: &str) -> String {
+        let mut output = format!(
+            "# API diff for `{}`: {} -> {}\n\n",
+            crate_name, old_version, new_version
+        );
+        output.push_str(&format!(
+            "{} added, {} removed, {} changed ({} potentially breaking). Required version bump: {}.\n\n",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.breaking_count(),
+            self.required_bump(old_version)
+        ));
+
+        if !self.removed.is_empty() {
+            output.push_str("## Removed\n");
+            for item in &self.removed {
+                output.push_str(&format!(
+                    "- **breaking** {} `{}`: `{}`\n",
+                    item.kind, item.path, item.signature
+                ));
+            }
+            output.push('\n');
+        }
+        if !self.changed.is_empty() {
+            output.push_str("## Changed\n");
+            for (old, new) in &self.changed {
+                output.push_str(&format!("- **breaking** {} `{}`\n", new.kind, new.path));
+                output.push_str(&format!("  - old: `{}`\n", old.signature));
+                output.push_str(&format!("  - new: `{}`\n", new.signature));
+            }
+            output.push('\n');
+        }
+        if !self.added.is_empty() {
+            output.push_str("## Added\n");
+            for item in &self.added {
+                let marker = if self.breaking_additions.contains(item) {
+                    "**breaking** "
+                } else {
+                    ""
+                };
+                output.push_str(&format!(
+                    "- {}{} `{}`: `{}`\n",
+                    marker, item.kind, item.path, item.signature
+                ));
+            }
+            output.push('\n');
+        }
+        if self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() {
+            output.push_str("No public API changes detected.\n");
+        }
+        output
+    }
+}
+
+/// Download two versions of a crate and report how their public API differs.
+pub async fn diff_crate_api(crate_name: &str, old_version: &str, new_version: &str) -> Result<String> {
+    let old_dir = download_and_cache_crate(crate_name, old_version).await?;
+    let new_dir = download_and_cache_crate(crate_name, new_version).await?;
+
+    let old_api = collect_public_api(Path::new(&old_dir));
+    let new_api = collect_public_api(Path::new(&new_dir));
+    if old_api.is_empty() && new_api.is_empty() {
+        bail!("no public API found in {} {} or {}", crate_name, old_version, new_version);
+    }
+
+    Ok(diff_public_api(&old_api, &new_api).to_markdown(crate_name, old_version, new_version))
+}
+
+/// Enumerate the public API of an unpacked crate source directory, with items of
+/// private modules at the paths `pub use` re-exports them at.
+pub fn collect_public_api(crate_dir: &Path) -> PublicApi {
+    let mut api = PublicApi::new();
+    let modules = parse_crate_modules(crate_dir);
+    let exports = module_exports(&modules);
+    for (module, module_exports) in modules.iter().zip(&exports) {
+        for export in module_exports {
+            let items = module.ast.items.iter().filter(|item| export.includes_item(item));
+            collect_items(items, &export.path, &mut api);
+        }
+    }
+    // Impls extend the API of a public type wherever they are written
+    let type_paths = public_type_paths(&modules, &exports);
+    for i in hidden_impls(&modules, &exports).into_iter().filter(|i| !is_doc_hidden(&i.attrs)) {
+        if let Some(type_path) = impl_type_name(i).and_then(|name| type_paths.get(&name)) {
+            collect_impl(i, Some(type_path), &mut api);
+        }
+    }
+    drop(modules);
+    release_spans();
+    api
+}
+
+/// Compare two public APIs item by item.
+pub fn diff_public_api(old: &PublicApi, new: &PublicApi) -> ApiDiff {
+    let mut diff = ApiDiff::default();
+    let old_paths: HashSet<&str> = old.keys().map(|(path, _)| path.as_str()).collect();
+
+    for (key, old_item) in old {
+        match new.get(key) {
+            None => diff.removed.push(old_item.clone()),
+            Some(new_item) if new_item.signature != old_item.signature => {
+                diff.changed.push((old_item.clone(), new_item.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, new_item) in new {
+        if old.contains_key(key) {
+            continue;
+        }
+        // Only additions to a pre-existing parent can break old users.
+        let parent_existed = new_item
+            .path
+            .rsplit_once("::")
+            .is_some_and(|(parent, _)| old_paths.contains(parent));
+        if new_item.breaking_if_added && parent_existed {
+            diff.breaking_additions.push(new_item.clone());
+        }
+        diff.added.push(new_item.clone());
+    }
+    diff
+}
+
+fn collect_items<'a>(items: impl IntoIterator<Item = &'a Item>, module_path: &[String], api: &mut PublicApi) {
+    for item in items {
+        match item {
+            Item::Fn(f) if is_public(&f.vis, &f.attrs) => {
+                push(api, "fn", join(module_path, &f.sig.ident), render(&f.sig), false);
+            }
+            Item::Struct(s) if is_public(&s.vis, &s.attrs) => {
+                let path = join(module_path, &s.ident);
+                let header = format!(
+                    "struct {}{}{}",
+                    s.ident,
+                    render(&s.generics),
+                    where_clause(&s.generics)
+                );
+                push(api, "struct", path.clone(), header, false);
+
+                // Adding a field to a struct that can be built with a literal breaks callers.
+                let literal_constructible =
+                    !has_attr(&s.attrs, "non_exhaustive") && s.fields.iter().all(|f| is_pub(&f.vis));
+                for (index, field) in s.fields.iter().enumerate() {
+                    if !is_public(&field.vis, &field.attrs) {
+                        continue;
+                    }
+                    let name = field
+                        .ident
+                        .as_ref()
+                        .map(|ident| ident.to_string())
+                        .unwrap_or_else(|| index.to_string());
+                    push(
+                        api,
+                        "field",
+                        format!("{}::{}", path, name),
+                        render(&field.ty),
+                        literal_constructible,
+                    );
+                }
+            }
+            Item::Enum(e) if is_public(&e.vis, &e.attrs) => {
+                let path = join(module_path, &e.ident);
+                let header = format!(
+                    "enum {}{}{}",
+                    e.ident,
+                    render(&e.generics),
+                    where_clause(&e.generics)
+                );
+                push(api, "enum", path.clone(), header, false);
+
+                let exhaustive = !has_attr(&e.attrs, "non_exhaustive");
+                for variant in &e.variants {
+                    if is_doc_hidden(&variant.attrs) {
+                        continue;
+                    }
+                    let mut variant = variant.clone();
+                    variant.attrs.clear();
+                    push(
+                        api,
+                        "variant",
+                        format!("{}::{}", path, variant.ident),
+                        render(&variant),
+                        exhaustive,
+                    );
+                }
+            }
+            Item::Union(u) if is_public(&u.vis, &u.attrs) => {
+                let header = format!("union {}{}", u.ident, render(&u.generics));
+                push(api, "union", join(module_path, &u.ident), header, false);
+            }
+            Item::Trait(t) if is_public(&t.vis, &t.attrs) => {
+                let path = join(module_path, &t.ident);
+                let mut header = format!(
+                    "{}trait {}{}",
+                    if t.unsafety.is_some() { "unsafe " } else { "" },
+                    t.ident,
+                    render(&t.generics)
+                );
+                if !t.supertraits.is_empty() {
+                    header.push_str(&format!(": {}", render(&t.supertraits)));
+                }
+                header.push_str(&where_clause(&t.generics));
+                push(api, "trait", path.clone(), header, false);
+
+                for trait_item in &t.items {
+                    match trait_item {
+                        TraitItem::Fn(f) if !is_doc_hidden(&f.attrs) => push(
+                            api,
+                            "method",
+                            format!("{}::{}", path, f.sig.ident),
+                            render(&f.sig),
+                            f.default.is_none(),
+                        ),
+                        TraitItem::Type(ty) if !is_doc_hidden(&ty.attrs) => push(
+                            api,
+                            "assoc type",
+                            format!("{}::{}", path, ty.ident),
+                            format!("type {}{}", ty.ident, bounds(&ty.bounds)),
+                            ty.default.is_none(),
+                        ),
+                        TraitItem::Const(c) if !is_doc_hidden(&c.attrs) => push(
+                            api,
+                            "assoc const",
+                            format!("{}::{}", path, c.ident),
+                            format!("const {}: {}", c.ident, render(&c.ty)),
+                            c.default.is_none(),
+                        ),
+                        _ => {}
+                    }
+                }
+            }
+            Item::Type(t) if is_public(&t.vis, &t.attrs) => {
+                let mut alias = t.clone();
+                alias.attrs.clear();
+                push(api, "type", join(module_path, &t.ident), render(&alias), false);
+            }
+            Item::Const(c) if is_public(&c.vis, &c.attrs) => {
+                let signature = format!("const {}: {}", c.ident, render(&c.ty));
+                push(api, "const", join(module_path, &c.ident), signature, false);
+            }
+            Item::Static(s) if is_public(&s.vis, &s.attrs) => {
+                let mutability = if matches!(s.mutability, syn::StaticMutability::Mut(_)) {
+                    "mut "
+                } else {
+                    ""
+                };
+                let signature = format!("static {}{}: {}", mutability, s.ident, render(&s.ty));
+                push(api, "static", join(module_path, &s.ident), signature, false);
+            }
+            Item::Macro(m) if has_attr(&m.attrs, "macro_export") && !is_doc_hidden(&m.attrs) => {
+                // Exported macros always live at the crate root.
+                if let Some(ident) = &m.ident {
+                    push(api, "macro", ident.to_string(), format!("macro_rules! {}", ident), false);
+                }
+            }
+            Item::Use(u) if is_public(&u.vis, &u.attrs) => {
+                let mut leaves = Vec::new();
+                use_leaves(&u.tree, String::new(), &mut leaves);
+                for (name, source) in leaves {
+                    push(api, "re-export", join(module_path, &name), format!("use {}", source), false);
+                }
+            }
+            Item::Mod(m) if is_public(&m.vis, &m.attrs) => {
+                if let Some((_, inline_items)) = &m.content {
+                    let mut child_path = module_path.to_vec();
+                    child_path.push(m.ident.to_string());
+                    collect_items(inline_items, &child_path, api);
+                }
+            }
+            Item::Impl(i) if !is_doc_hidden(&i.attrs) => {
+                let type_path = impl_type_name(i).map(|name| join(module_path, &name));
+                collect_impl(i, type_path.as_deref(), api);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Record a trait impl, or the public methods and constants of an inherent impl of the
+/// type at `type_path`.
+fn collect_impl(i: &syn::ItemImpl, type_path: Option<&str>, api: &mut PublicApi) {
+    let self_ty = render(&i.self_ty);
+    match &i.trait_ {
+        Some((negative, trait_path, _)) => {
+            // Trait impls are part of the API no matter where they are written.
+            let trait_name = format!("{}{}", if negative.is_some() { "!" } else { "" }, render(trait_path));
+            let header = format!(
+                "impl{} {} for {}{}",
+                render(&i.generics),
+                trait_name,
+                self_ty,
+                where_clause(&i.generics)
+            );
+            push(api, "impl", format!("impl {} for {}", trait_name, self_ty), header, false);
+        }
+        None => {
+            let Some(type_path) = type_path else { return };
+            for impl_item in &i.items {
+                match impl_item {
+                    ImplItem::Fn(f) if is_public(&f.vis, &f.attrs) => push(
+                        api,
+                        "method",
+                        format!("{}::{}", type_path, f.sig.ident),
+                        render(&f.sig),
+                        false,
+                    ),
+                    ImplItem::Const(c) if is_public(&c.vis, &c.attrs) => push(
+                        api,
+                        "assoc const",
+                        format!("{}::{}", type_path, c.ident),
+                        format!("const {}: {}", c.ident, render(&c.ty)),
+                        false,
+                    ),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Flatten a `use` tree into `(exported name, source path)` pairs.
+fn use_leaves(tree: &UseTree, prefix: String, out: &mut Vec<(String, String)>) {
+    match tree {
+        UseTree::Path(p) => use_leaves(&p.tree, format!("{}{}::", prefix, p.ident), out),
+        UseTree::Name(n) => out.push((n.ident.to_string(), format!("{}{}", prefix, n.ident))),
+        UseTree::Rename(r) => out.push((r.rename.to_string(), format!("{}{}", prefix, r.ident))),
+        UseTree::Glob(_) => out.push(("*".to_string(), format!("{}*", prefix))),
+        UseTree::Group(g) => {
+            for tree in &g.items {
+                use_leaves(tree, prefix.clone(), out);
+            }
+        }
+    }
+}
+
+fn push(api: &mut PublicApi, kind: &'static str, path: String, signature: String, breaking_if_added: bool) {
+    api.insert(
+        (path.clone(), kind),
+        ApiItem {
+            kind,
+            path,
+            signature,
+            breaking_if_added,
+        },
+    );
+}
+
+fn join(module_path: &[String], name: &impl ToString) -> String {
+    let mut parts = module_path.to_vec();
+    parts.push(name.to_string());
+    parts.join("::")
+}
+
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+fn is_public(vis: &Visibility, attrs: &[Attribute]) -> bool {
+    is_pub(vis) && !is_doc_hidden(attrs)
+}
+
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+/// `#[doc(hidden)]` items are not covered by semver guarantees.
+fn is_doc_hidden(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("doc")
+            && matches!(&attr.meta, syn::Meta::List(list) if list.tokens.to_string().contains("hidden"))
+    })
+}
+
+fn where_clause(generics: &syn::Generics) -> String {
+    generics
+        .where_clause
+        .as_ref()
+        .map(|w| format!(" {}", render(w)))
+        .unwrap_or_default()
+}
+
+fn bounds<T: ToTokens>(bounds: &syn::punctuated::Punctuated<T, syn::Token![+]>) -> String {
+    if bounds.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", render(bounds))
+    }
+}
+
+/// Render a syntax node as compact, human-readable Rust.
+pub(crate) fn render<T: ToTokens>(node: &T) -> String {
+    tidy_tokens(&node.to_token_stream().to_string())
+}
+
+/// Undo the token spacing produced by `TokenStream::to_string`.
+pub(crate) fn tidy_tokens(tokens: &str) -> String {
+    let mut s = tokens.to_string();
+    for (from, to) in [
+        (" :: ", "::"),
+        (":: ", "::"),
+        (" ::", "::"),
+        (" < ", "<"),
+        ("< ", "<"),
+        (" <", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" ;", ";"),
+        (" : ", ": "),
+        ("& ", "&"),
+        ("( ", "("),
+        (" )", ")"),
+        ("[ ", "["),
+        (" ]", "]"),
+        ("? ", "?"),
+        (" (", "("),
+        ("->(", "-> ("),
+        (",(", ", ("),
+        (":(", ": ("),
+        ("=(", "= ("),
+        ("! ", "!"),
+    ] {
+        s = s.replace(from, to);
+    }
+    s
+}
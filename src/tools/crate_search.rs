@@ -0,0 +1,297 @@
+use crate::tools::item_list::{
+    doc_comment, download_and_cache_crate, hidden_impls, impl_type_name, module_exports, parse_crate_modules,
+    public_type_paths, release_spans, visit_impl, visit_items, VisitedItem,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Default number of results returned by `search_in_crate`.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 document length normalization.
+const B: f64 = 0.75;
+/// How many times an item's name terms are counted relative to its docs.
+const NAME_BOOST: usize = 3;
+/// Longest snippet shown under a result, in characters.
+const SNIPPET_CHARS: usize = 200;
+
+const STOP_WORDS: [&str; 24] = [
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "is", "it", "of",
+    "on", "or", "that", "the", "this", "to", "was", "will", "with",
+];
+
+/// Indexes are built once per unpacked crate and kept for the life of the process.
+static INDEXES: OnceLock<Mutex<HashMap<PathBuf, Arc<CrateIndex>>>> = OnceLock::new();
+
+/// Something that can be found by `search_in_crate`: a public item or a README section.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Item path relative to the crate root, or "README: <heading>".
+    pub path: String,
+    pub kind: &'static str,
+    pub text: String,
+    terms: HashMap<String, usize>,
+    length: usize,
+}
+
+/// A scored search hit.
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    pub document: &'a Document,
+    pub score: f64,
+    pub snippet: String,
+}
+
+/// Inverted BM25 index over a crate's public items and README.
+#[derive(Debug, Default)]
+pub struct CrateIndex {
+    documents: Vec<Document>,
+    /// Term to the indices of the documents containing it.
+    postings: HashMap<String, Vec<usize>>,
+    average_length: f64,
+}
+
+/// Download a crate and return the items whose names or docs best match `query`.
+pub async fn search_in_crate(
+    crate_name: &str,
+    version: &str,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<String> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let index = cached_index(Path::new(&crate_path));
+    let hits = index.search(query, limit.unwrap_or(DEFAULT_LIMIT));
+
+    let mut output = format!("# Search results for \"{}\" in {} {}\n\n", query, crate_name, version);
+    if hits.is_empty() {
+        output.push_str("No matching items found.\n");
+        return Ok(output);
+    }
+    for (rank, hit) in hits.iter().enumerate() {
+        output.push_str(&format!(
+            "{}. `{}` ({}, score {:.2})\n",
+            rank + 1,
+            hit.document.path,
+            hit.document.kind,
+            hit.score
+        ));
+        if !hit.snippet.is_empty() {
+            output.push_str(&format!("   > {}\n", hit.snippet));
+        }
+    }
+    Ok(output)
+}
+
+/// Return the index for `crate_dir`, building it on first use.
+fn cached_index(crate_dir: &Path) -> Arc<CrateIndex> {
+    let indexes = INDEXES.get_or_init(Default::default);
+    if let Some(index) = indexes.lock().unwrap().get(crate_dir) {
+        return index.clone();
+    }
+    // Build outside the lock; a concurrent build of the same crate is harmless.
+    let index = Arc::new(CrateIndex::build(crate_dir));
+    indexes
+        .lock()
+        .unwrap()
+        .insert(crate_dir.to_path_buf(), index.clone());
+    index
+}
+
+impl CrateIndex {
+    /// Index the public items reachable from the crate root plus the README's sections.
+    pub fn build(crate_dir: &Path) -> Self {
+        let mut index = CrateIndex::default();
+
+        let modules = parse_crate_modules(crate_dir);
+        let exports = module_exports(&modules);
+        for (module, module_exports) in modules.iter().zip(&exports) {
+            if module.module_path.is_empty() {
+                let doc = doc_comment(&module.ast.attrs);
+                if !doc.is_empty() {
+                    index.add("crate".to_string(), "crate", "", doc);
+                }
+            }
+            for export in module_exports {
+                visit_items(&module.ast.items, &export.path, &mut |item| {
+                    // Only the items a `pub use` names, for modules re-exported item by item
+                    let name = item.path.split("::").nth(export.path.len()).unwrap_or_default();
+                    if export.includes(name) {
+                        index.add_item(item);
+                    }
+                });
+            }
+        }
+        // Methods of public types, wherever their impls are written
+        let type_paths = public_type_paths(&modules, &exports);
+        for i in hidden_impls(&modules, &exports) {
+            if let Some(type_path) = impl_type_name(i).and_then(|name| type_paths.get(&name)) {
+                visit_impl(i, type_path, &mut |item| index.add_item(item));
+            }
+        }
+        drop(modules);
+        release_spans();
+
+        for readme in ["README.md", "readme.md", "README"] {
+            let Ok(content) = fs::read_to_string(crate_dir.join(readme)) else { continue };
+            for (heading, body) in readme_sections(&content) {
+                index.add(format!("README: {}", heading), "readme", &heading, body);
+            }
+            break;
+        }
+
+        let total: usize = index.documents.iter().map(|d| d.length).sum();
+        index.average_length = total as f64 / index.documents.len().max(1) as f64;
+        index
+    }
+
+    /// Index a public item by its name and doc comment.
+    fn add_item(&mut self, item: VisitedItem) {
+        if item.public {
+            let name = item.path.rsplit("::").next().unwrap_or_default().to_string();
+            self.add(item.path, item.kind, &name, doc_comment(item.attrs));
+        }
+    }
+
+    fn add(&mut self, path: String, kind: &'static str, name: &str, text: String) {
+        let mut terms: HashMap<String, usize> = HashMap::new();
+        for term in tokenize(name) {
+            *terms.entry(term).or_default() += NAME_BOOST;
+        }
+        for term in tokenize(&text) {
+            *terms.entry(term).or_default() += 1;
+        }
+        let length = terms.values().sum();
+        let id = self.documents.len();
+        for term in terms.keys() {
+            self.postings.entry(term.clone()).or_default().push(id);
+        }
+        self.documents.push(Document { path, kind, text, terms, length });
+    }
+
+    /// Number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Rank documents against `query` with BM25 and return the best `limit`.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit<'_>> {
+        let mut query_terms = tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+
+        let count = self.documents.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in &query_terms {
+            let Some(postings) = self.postings.get(term) else { continue };
+            let idf = ((count - postings.len() as f64 + 0.5) / (postings.len() as f64 + 0.5) + 1.0).ln();
+            for &id in postings {
+                let document = &self.documents[id];
+                let tf = document.terms[term] as f64;
+                let norm = K1 * (1.0 - B + B * document.length as f64 / self.average_length.max(1.0));
+                *scores.entry(id).or_default() += idf * tf * (K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(id, score)| {
+                let document = &self.documents[id];
+                SearchHit {
+                    document,
+                    score,
+                    snippet: snippet(&document.text, &query_terms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split text into lowercase, stemmed terms. `camelCase` and `snake_case` names are split.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut previous_lower = false;
+        for c in word.chars() {
+            if c.is_uppercase() && previous_lower {
+                terms.push(std::mem::take(&mut current));
+            }
+            previous_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.extend(c.to_lowercase());
+        }
+        terms.push(current);
+    }
+    terms
+        .into_iter()
+        .filter(|term| term.len() > 1 && !STOP_WORDS.contains(&term.as_str()))
+        .map(|term| stem(&term))
+        .collect()
+}
+
+/// Strip common English suffixes so "close", "closed", "closing" and "closes" share a term.
+fn stem(term: &str) -> String {
+    let mut stem = term;
+    for suffix in ["ing", "ed", "s"] {
+        if let Some(stripped) = term.strip_suffix(suffix) {
+            if stripped.len() >= 4 || (suffix != "ing" && stripped.len() >= 3) {
+                if suffix == "s" && stripped.ends_with('s') {
+                    break;
+                }
+                stem = stripped;
+                break;
+            }
+        }
+    }
+    stem.strip_suffix('e').filter(|s| s.len() >= 3).unwrap_or(stem).to_string()
+}
+
+/// Split a README into `(heading, body)` pairs; text before the first heading is "Introduction".
+fn readme_sections(markdown: &str) -> Vec<(String, String)> {
+    let mut sections = vec![("Introduction".to_string(), String::new())];
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        match line.strip_prefix('#') {
+            Some(heading) if !in_code => {
+                sections.push((heading.trim_start_matches('#').trim().to_string(), String::new()));
+            }
+            _ => {
+                let body = &mut sections.last_mut().unwrap().1;
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+    sections.retain(|(_, body)| !body.trim().is_empty());
+    sections
+}
+
+/// The first doc line mentioning a query term, or the first line, cut to `SNIPPET_CHARS`.
+fn snippet(text: &str, query_terms: &[String]) -> String {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("```"));
+    let first = lines.clone().next().unwrap_or_default();
+    let line = lines
+        .find(|line| tokenize(line).iter().any(|term| query_terms.contains(term)))
+        .unwrap_or(first);
+    match line.char_indices().nth(SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
+    }
+}
@@ -1,6 +1,20 @@
+use crate::tools::api_diff;
+use crate::tools::crate_search;
+use crate::tools::examples;
+use crate::tools::format::OutputFormat;
+use crate::tools::impls;
 use crate::tools::item_list;
-use crate::tools::tldr;
-use std::{future::Future, pin::Pin, sync::Arc};
+use crate::tools::metrics::{metrics, observed};
+use crate::tools::outline;
+use crate::tools::paginate;
+use crate::tools::pipeline::{self, Pipeline};
+use crate::tools::prompts;
+use crate::tools::rustdoc::{rustdoc_item_record, rustdoc_to_markdown};
+use crate::tools::signature_search;
+use crate::tools::structured::{self, CrateMetadata, ItemRecord};
+use crate::tools::view_source;
+use crate::tools::docs::resources::{DocResource, RecentResources};
+use std::{future::Future, pin::Pin, sync::Arc, time::Instant};
 
 use mcp_core::{
     handler::{PromptError, ResourceError},
@@ -35,21 +49,79 @@ impl DocCache {
 
     pub async fn get(&self, key: &str) -> Option<String> {
         let cache = self.cache.lock().await;
-        cache.get(key).cloned()
+        let value = cache.get(key).cloned();
+        metrics().record_cache(value.is_some());
+        value
     }
 
     pub async fn set(&self, key: String, value: String) {
         let mut cache = self.cache.lock().await;
         cache.insert(key, value);
     }
+
+    /// Forget everything cached for a crate without an explicit version, e.g. after a release.
+    pub async fn invalidate_latest(&self, crate_name: &str) {
+        let mut cache = self.cache.lock().await;
+        cache.retain(|key, _| {
+            let Some(rest) = key.strip_prefix(crate_name) else { return true };
+            match rest.chars().next() {
+                None | Some('#') => false,
+                // `crate:{version}...` starts with a digit; `crate:{item}` and `crate:latest#...` don't
+                Some(':') => rest[1..].starts_with(|c: char| c.is_ascii_digit()),
+                Some(_) => true,
+            }
+        });
+    }
+}
+
+/// Which part of a crate's documentation `lookup_crate` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrateView {
+    /// The README rendered by crates.io.
+    Readme,
+    /// The crate root rustdoc page.
+    #[default]
+    Overview,
+    /// The docs.rs landing page with versions, links and feature flags.
+    Metadata,
+    /// Overview, README and metadata, in that order.
+    All,
+}
+
+impl CrateView {
+    pub fn parse(view: &str) -> Option<Self> {
+        match view {
+            "readme" => Some(Self::Readme),
+            "overview" => Some(Self::Overview),
+            "metadata" => Some(Self::Metadata),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Readme => "readme",
+            Self::Overview => "overview",
+            Self::Metadata => "metadata",
+            Self::All => "all",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct DocRouter {
     pub client: Client,
     pub cache: DocCache,
+    /// Apply the `tldr` preset before the other filters; a call's `tldr` or `filters` overrides it.
     pub tldr: bool,
+    /// Filters applied to every tool's output unless a call passes its own `filters`.
+    pub pipeline: Pipeline,
     pub max_tokens: Option<usize>,
+    /// Output format unless a call passes its own `format`.
+    pub format: OutputFormat,
+    /// Documentation looked up recently, listed as resources.
+    pub recent: RecentResources,
 }
 
 impl Default for DocRouter {
@@ -64,44 +136,163 @@ impl DocRouter {
             client: Client::new(),
             cache: DocCache::new(),
             tldr,
+            pipeline: Pipeline::none(),
             max_tokens,
+            format: OutputFormat::default(),
+            recent: RecentResources::default(),
         }
     }
+    /// Replace the default output pipeline, e.g. with one parsed from `--filters`.
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+    /// Replace the default output format.
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
     pub fn new_with_tldr(tldr: bool) -> Self {
         Self::new_with_tldr_and_max_tokens(tldr, None)
     }
     pub fn new() -> Self {
         Self::new_with_tldr_and_max_tokens(false, None)
     }
+    /// A router for one more client: same configuration, cache and HTTP client,
+    /// but its own list of recently viewed resources.
+    pub fn for_session(&self) -> Self {
+        Self {
+            recent: RecentResources::default(),
+            ..self.clone()
+        }
+    }
 
-    // Fetch crate documentation from docs.rs
-    async fn lookup_crate(&self, crate_name: String, version: Option<String>) -> Result<String, ToolError> {
+    // Fetch crate documentation from docs.rs and crates.io
+    async fn lookup_crate(&self, crate_name: String, version: Option<String>, view: CrateView) -> Result<String, ToolError> {
         // Check cache first
-        let cache_key = if let Some(ver) = &version {
+        let mut cache_key = if let Some(ver) = &version {
             format!("{}:{}", crate_name, ver)
         } else {
             crate_name.clone()
         };
+        if view != CrateView::Overview {
+            cache_key = format!("{}#{}", cache_key, view.as_str());
+        }
 
         if let Some(doc) = self.cache.get(&cache_key).await {
             return Ok(doc);
         }
 
-        // Construct the docs.rs URL for the crate
-        let url = if let Some(ver) = version {
-            format!("https://docs.rs/crate/{}/{}/", crate_name, ver)
-        } else {
-            format!("https://docs.rs/crate/{}/", crate_name)
+        let markdown_body = match view {
+            CrateView::Overview => self.crate_overview(&crate_name, version.as_deref()).await?,
+            CrateView::Readme => self.crate_readme(&crate_name, version.as_deref()).await?,
+            CrateView::Metadata => self.crate_metadata(&crate_name, version.as_deref()).await?,
+            CrateView::All => {
+                // A missing README should not hide the rest of the documentation
+                let readme = self
+                    .crate_readme(&crate_name, version.as_deref())
+                    .await
+                    .unwrap_or_else(|e| format!("_No README available: {}_\n", e));
+                format!(
+                    "# Overview\n\n{}\n# README\n\n{}\n# Metadata\n\n{}",
+                    self.crate_overview(&crate_name, version.as_deref()).await?,
+                    readme,
+                    self.crate_metadata(&crate_name, version.as_deref()).await?
+                )
+            }
         };
 
-        // Fetch the documentation page
-        let response = self.client.get(&url)
+        // Cache the markdown result
+        self.cache.set(cache_key, markdown_body.clone()).await;
+        
+        Ok(markdown_body)
+    }
+
+    // The crate root rustdoc page: `//!` docs, modules and top-level items
+    async fn crate_overview(&self, crate_name: &str, version: Option<&str>) -> Result<String, ToolError> {
+        let url = format!(
+            "https://docs.rs/{}/{}/{}/",
+            crate_name,
+            version.unwrap_or("latest"),
+            crate_name.replace('-', "_")
+        );
+        let html_body = self.fetch_html(&url).await?;
+        Ok(rustdoc_to_markdown(&html_body))
+    }
+
+    // The docs.rs landing page: versions, links, owners, dependencies and feature flags
+    async fn crate_metadata(&self, crate_name: &str, version: Option<&str>) -> Result<String, ToolError> {
+        let url = match version {
+            Some(ver) => format!("https://docs.rs/crate/{}/{}/", crate_name, ver),
+            None => format!("https://docs.rs/crate/{}/", crate_name),
+        };
+        let html_body = self.fetch_html(&url).await?;
+        Ok(rustdoc_to_markdown(&html_body))
+    }
+
+    // The README as rendered by crates.io
+    async fn crate_readme(&self, crate_name: &str, version: Option<&str>) -> Result<String, ToolError> {
+        let version = match version {
+            Some(ver) => ver.to_string(),
+            None => self.latest_version(crate_name).await?,
+        };
+        let url = format!("https://crates.io/api/v1/crates/{}/{}/readme", crate_name, version);
+        let html_body = self.fetch_html(&url).await?;
+        Ok(rustdoc_to_markdown(&html_body))
+    }
+
+    // crates.io metadata of a crate version, for structured output
+    async fn crate_metadata_record(&self, crate_name: &str, version: Option<&str>) -> Result<CrateMetadata, ToolError> {
+        let cache_key = format!("{}:{}#metadata-record", crate_name, version.unwrap_or("latest"));
+        if let Some(record) = self.cache.get(&cache_key).await {
+            if let Ok(record) = serde_json::from_str(&record) {
+                return Ok(record);
+            }
+        }
+
+        let parse = |body: String| {
+            serde_json::from_str::<Value>(&body).map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to parse crates.io response: {}", e))
+            })
+        };
+        let crate_info = parse(self.fetch_html(&format!("https://crates.io/api/v1/crates/{}", crate_name)).await?)?;
+        let version = match version {
+            Some(ver) => ver.to_string(),
+            None => self.latest_version(crate_name).await?,
+        };
+        let version_info = parse(
+            self.fetch_html(&format!("https://crates.io/api/v1/crates/{}/{}", crate_name, version)).await?,
+        )?;
+        let record = structured::crate_metadata(&crate_info, &version_info);
+        if let Ok(json) = serde_json::to_string(&record) {
+            self.cache.set(cache_key, json).await;
+        }
+        Ok(record)
+    }
+
+    // Latest stable version of a crate according to crates.io
+    async fn latest_version(&self, crate_name: &str) -> Result<String, ToolError> {
+        let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+        let body = self.fetch_html(&url).await?;
+        let info: Value = serde_json::from_str(&body).map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to parse crates.io response: {}", e))
+        })?;
+        ["max_stable_version", "max_version"]
+            .iter()
+            .find_map(|key| info["crate"][key].as_str())
+            .map(|ver| ver.to_string())
+            .ok_or_else(|| ToolError::ExecutionError(format!("No published version found for {}", crate_name)))
+    }
+
+    // Fetch a page, failing on transport errors and non-success statuses
+    async fn fetch_html(&self, url: &str) -> Result<String, ToolError> {
+        let response = self.client.get(url)
             .header("User-Agent", "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)")
             .send()
-            .await
-            .map_err(|e| {
-                ToolError::ExecutionError(format!("Failed to fetch documentation: {}", e))
-            })?;
+            .await;
+        let response = observed(url, response).map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to fetch documentation: {}", e))
+        })?;
 
         if !response.status().is_success() {
             return Err(ToolError::ExecutionError(format!(
@@ -110,17 +301,9 @@ impl DocRouter {
             )));
         }
 
-        let html_body = response.text().await.map_err(|e| {
+        response.text().await.map_err(|e| {
             ToolError::ExecutionError(format!("Failed to read response body: {}", e))
-        })?;
-        
-        // Convert HTML to markdown
-        let markdown_body = parse_html(&html_body);
-
-        // Cache the markdown result
-        self.cache.set(cache_key, markdown_body.clone()).await;
-        
-        Ok(markdown_body)
+        })
     }
 
     // Search crates.io for crates matching a query
@@ -132,10 +315,10 @@ impl DocRouter {
         let response = self.client.get(&url)
             .header("User-Agent", "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)")
             .send()
-            .await
-            .map_err(|e| {
-                ToolError::ExecutionError(format!("Failed to search crates.io: {}", e))
-            })?;
+            .await;
+        let response = observed(&url, response).map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to search crates.io: {}", e))
+        })?;
 
         if !response.status().is_success() {
             return Err(ToolError::ExecutionError(format!(
@@ -159,24 +342,49 @@ impl DocRouter {
     }
 
     // Get documentation for a specific item in a crate
-    async fn lookup_item(&self, crate_name: String, mut item_path: String, version: Option<String>) -> Result<String, ToolError> {
-        // Strip crate name prefix from the item path if it exists
-        let crate_prefix = format!("{}::", crate_name);
-        if item_path.starts_with(&crate_prefix) {
-            item_path = item_path[crate_prefix.len()..].to_string();
+    async fn lookup_item(&self, crate_name: String, item_path: String, version: Option<String>) -> Result<String, ToolError> {
+        let item_path = strip_crate_prefix(&crate_name, item_path);
+        let cache_key = item_cache_key(&crate_name, &item_path, version.as_deref());
+        if let Some(doc) = self.cache.get(&cache_key).await {
+            return Ok(doc);
         }
 
-        // Check cache first
-        let cache_key = if let Some(ver) = &version {
-            format!("{}:{}:{}", crate_name, ver, item_path)
-        } else {
-            format!("{}:{}", crate_name, item_path)
-        };
+        let html_body = self.fetch_item_html(&crate_name, &item_path, version.as_deref()).await?;
+        self.cache_item_record(&cache_key, &crate_name, &item_path, &html_body).await;
 
-        if let Some(doc) = self.cache.get(&cache_key).await {
-            return Ok(doc);
+        // Convert HTML to markdown, keeping only the documentation content
+        let markdown_body = rustdoc_to_markdown(&html_body);
+
+        // Cache the markdown result
+        self.cache.set(cache_key, markdown_body.clone()).await;
+
+        Ok(markdown_body)
+    }
+
+    // Structured description of an item, cached next to its markdown
+    async fn lookup_item_record(&self, crate_name: String, item_path: String, version: Option<String>) -> Result<ItemRecord, ToolError> {
+        let item_path = strip_crate_prefix(&crate_name, item_path);
+        let cache_key = item_cache_key(&crate_name, &item_path, version.as_deref());
+        if let Some(record) = self.cache.get(&format!("{}#record", cache_key)).await {
+            if let Ok(record) = serde_json::from_str(&record) {
+                return Ok(record);
+            }
+        }
+
+        let html_body = self.fetch_item_html(&crate_name, &item_path, version.as_deref()).await?;
+        Ok(self.cache_item_record(&cache_key, &crate_name, &item_path, &html_body).await)
+    }
+
+    async fn cache_item_record(&self, cache_key: &str, crate_name: &str, item_path: &str, html_body: &str) -> ItemRecord {
+        let record = rustdoc_item_record(html_body, &format!("{}::{}", crate_name, item_path));
+        if let Ok(json) = serde_json::to_string(&record) {
+            self.cache.set(format!("{}#record", cache_key), json).await;
         }
+        record
+    }
 
+    // Find the rustdoc page of an item, trying each item type in turn
+    async fn fetch_item_html(&self, crate_name: &str, item_path: &str, version: Option<&str>) -> Result<String, ToolError> {
         // Process the item path to determine the item type
         // Format: module::path::ItemName
         // Need to split into module path and item name, and guess item type
@@ -201,7 +409,7 @@ impl DocRouter {
         
         for item_type in item_types.iter() {
             // Construct the docs.rs URL for the specific item
-            let url = if let Some(ver) = version.clone() {
+            let url = if let Some(ver) = version {
                 if module_path.is_empty() {
                     format!("https://docs.rs/{}/{}/{}/{}.{}.html", crate_name, ver, crate_name, item_type, item_name)
                 } else {
@@ -216,9 +424,10 @@ impl DocRouter {
             };
             
             // Try to fetch the documentation page
-            let response = match self.client.get(&url)
+            let response = self.client.get(&url)
                 .header("User-Agent", "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)")
-                .send().await {
+                .send().await;
+            let response = match observed(&url, response) {
                 Ok(resp) => resp,
                 Err(e) => {
                     last_error = Some(e.to_string());
@@ -226,19 +435,11 @@ impl DocRouter {
                 }
             };
             
-            // If found, process and return
+            // If found, return the page
             if response.status().is_success() {
-                let html_body = response.text().await.map_err(|e| {
+                return response.text().await.map_err(|e| {
                     ToolError::ExecutionError(format!("Failed to read response body: {}", e))
-                })?;
-                
-                // Convert HTML to markdown
-                let markdown_body = parse_html(&html_body);
-                
-                // Cache the markdown result
-                self.cache.set(cache_key, markdown_body.clone()).await;
-                
-                return Ok(markdown_body);
+                });
             }
             
             last_error = Some(format!("Status code: {}", response.status()));
@@ -250,6 +451,46 @@ impl DocRouter {
             last_error.unwrap_or_else(|| "Unknown error".to_string())
         )))
     }
+
+    // Contents of a `cratedocs://` resource
+    async fn read_doc_resource(&self, resource: &DocResource) -> Result<String, ToolError> {
+        let version = |version: &str| (version != "latest").then(|| version.to_string());
+        match resource {
+            DocResource::Crate { crate_name, version: ver } => {
+                self.lookup_crate(crate_name.clone(), version(ver), CrateView::Overview).await
+            }
+            DocResource::Readme { crate_name, version: ver } => {
+                self.lookup_crate(crate_name.clone(), version(ver), CrateView::Readme).await
+            }
+            DocResource::Item { crate_name, version: ver, path } => {
+                self.lookup_item(crate_name.clone(), path.clone(), version(ver)).await
+            }
+            DocResource::Source { crate_name, version: ver, file } => {
+                let ver = match version(ver) {
+                    Some(ver) => ver,
+                    None => self.latest_version(crate_name).await?,
+                };
+                view_source::read_source_file(crate_name, &ver, file)
+                    .await
+                    .map_err(|e| ToolError::ExecutionError(format!("Failed to read source: {}", e)))
+            }
+        }
+    }
+}
+
+/// Strip the crate name prefix from an item path if it exists
+fn strip_crate_prefix(crate_name: &str, item_path: String) -> String {
+    match item_path.strip_prefix(&format!("{}::", crate_name)) {
+        Some(rest) => rest.to_string(),
+        None => item_path,
+    }
+}
+
+fn item_cache_key(crate_name: &str, item_path: &str, version: Option<&str>) -> String {
+    match version {
+        Some(ver) => format!("{}:{}:{}", crate_name, ver, item_path),
+        None => format!("{}:{}", crate_name, item_path),
+    }
 }
 
 impl mcp_server::Router for DocRouter {
@@ -262,7 +503,10 @@ impl mcp_server::Router for DocRouter {
         You can search for crates, lookup documentation for specific crates or \
         items within crates. Use these tools to find information about Rust libraries \
         you are not familiar with. All HTML documentation is automatically converted to markdown \
-        for better compatibility with language models.".to_string()
+        for better compatibility with language models. Documentation is also available as \
+        cratedocs:// resources; recently viewed crates and items are listed by resources/list. \
+        Prompts such as explain_crate, how_to, migrate_version and compare_crates walk through \
+        common documentation tasks.".to_string()
     }
 
     fn capabilities(&self) -> ServerCapabilities {
@@ -274,10 +518,10 @@ impl mcp_server::Router for DocRouter {
     }
 
     fn list_tools(&self) -> Vec<Tool> {
-        vec![
+        let tools = vec![
             Tool::new(
                 "lookup_crate".to_string(),
-                "Look up documentation for a Rust crate (returns markdown)".to_string(),
+                "Look up documentation for a Rust crate (returns markdown). By default returns the crate root documentation; use 'view' to get the README, the docs.rs metadata page, or everything.".to_string(),
                 json!({
                     "type": "object",
                     "properties": {
@@ -288,6 +532,20 @@ impl mcp_server::Router for DocRouter {
                         "version": {
                             "type": "string",
                             "description": "The version of the crate (optional, defaults to latest)"
+                        },
+                        "view": {
+                            "type": "string",
+                            "enum": ["readme", "overview", "metadata", "all"],
+                            "description": "Which documentation to return: 'overview' (crate root docs, modules and top-level items; default), 'readme', 'metadata' (docs.rs landing page) or 'all'"
+                        },
+                        "sections": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Only return these sections, matched by heading (e.g., ['Examples', 'Panics']); use doc_outline to list them (optional)"
+                        },
+                        "structured": {
+                            "type": "boolean",
+                            "description": "Also return crates.io metadata as a JSON resource (schema cratedocs/crate/v1) (optional)"
                         }
                     },
                     "required": ["crate_name"]
@@ -306,6 +564,10 @@ impl mcp_server::Router for DocRouter {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum number of results to return (optional, defaults to 10, max 100)"
+                        },
+                        "structured": {
+                            "type": "boolean",
+                            "description": "Also return the results as a JSON resource (schema cratedocs/search/v1) (optional)"
                         }
                     },
                     "required": ["query"]
@@ -328,11 +590,42 @@ impl mcp_server::Router for DocRouter {
                         "version": {
                             "type": "string",
                             "description": "The version of the crate (optional, defaults to latest)"
+                        },
+                        "sections": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Only return these sections, matched by heading (e.g., ['Examples', 'Panics']); use doc_outline to list them (optional)"
+                        },
+                        "structured": {
+                            "type": "boolean",
+                            "description": "Also return the item's kind, path, signature, docs, since-version, deprecation and feature gates as a JSON resource (schema cratedocs/item/v1) (optional)"
                         }
                     },
                     "required": ["crate_name", "item_path"]
                 }),
             ),
+            Tool::new(
+                "doc_outline".to_string(),
+                "Show the heading tree of a crate's or item's documentation page with the approximate token size of each section, to decide which sections to fetch with the 'sections' argument of lookup_crate or lookup_item.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "item_path": {
+                            "type": "string",
+                            "description": "Path to the item (optional; omit for the crate root page)"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate (optional, defaults to latest)"
+                        }
+                    },
+                    "required": ["crate_name"]
+                }),
+            ),
             Tool::new(
                 "list_crate_items".to_string(),
                 "Enumerate all items in a Rust crate (optionally filtered by type, visibility, or module). Returns a concise, categorized list.".to_string(),
@@ -358,12 +651,177 @@ impl mcp_server::Router for DocRouter {
                         "module": {
                             "type": "string",
                             "description": "Filter by module path (e.g., serde::de)"
+                        },
+                        "structured": {
+                            "type": "boolean",
+                            "description": "Also return every matching item with kind, path, signature, docs, deprecation and feature gates as a JSON resource (schema cratedocs/item-list/v1) (optional)"
+                        }
+                    },
+                    "required": ["crate_name", "version"]
+                }),
+            ),
+            Tool::new(
+                "diff_crate_api".to_string(),
+                "Compare the public API of two versions of a Rust crate. Lists added, removed and changed items and signatures, flagging semver-breaking changes.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "old_version": {
+                            "type": "string",
+                            "description": "The version being upgraded from (e.g., 0.11.27)"
+                        },
+                        "new_version": {
+                            "type": "string",
+                            "description": "The version being upgraded to (e.g., 0.12.0)"
+                        }
+                    },
+                    "required": ["crate_name", "old_version", "new_version"]
+                }),
+            ),
+            Tool::new(
+                "find_examples".to_string(),
+                "Find code examples for a Rust crate or one of its items: doctests, files from examples/ and tests/, and README snippets, ranked by relevance and trimmed to a token budget.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate"
+                        },
+                        "item_path": {
+                            "type": "string",
+                            "description": "Path to the item (e.g., 'sync::mpsc::channel'); omit for crate-level examples"
+                        },
+                        "token_budget": {
+                            "type": "integer",
+                            "description": "Maximum number of tokens of examples to return (optional, defaults to 2000)"
+                        }
+                    },
+                    "required": ["crate_name", "version"]
+                }),
+            ),
+            Tool::new(
+                "view_source".to_string(),
+                "View the source code of a specific item in a Rust crate (function body, struct or enum with its impls, trait with default methods), with file path and line numbers.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate"
+                        },
+                        "item_path": {
+                            "type": "string",
+                            "description": "Path to the item (e.g., 'sync::mpsc::Sender' or 'Sender::send')"
+                        },
+                        "context_lines": {
+                            "type": "integer",
+                            "description": "Number of extra lines to show before and after each span (optional, defaults to 0)"
+                        }
+                    },
+                    "required": ["crate_name", "version", "item_path"]
+                }),
+            ),
+            Tool::new(
+                "search_in_crate".to_string(),
+                "Full-text search inside a Rust crate: ranks its public items and README sections against a free-text query (e.g., 'graceful shutdown') using their names and doc comments, and returns item paths with doc snippets.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate"
+                        },
+                        "query": {
+                            "type": "string",
+                            "description": "Words describing the concept to find (e.g., 'backpressure')"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return (optional, defaults to 10)"
+                        }
+                    },
+                    "required": ["crate_name", "version", "query"]
+                }),
+            ),
+            Tool::new(
+                "search_by_signature".to_string(),
+                "Search a Rust crate's public functions and methods by type signature (e.g., '&str -> Result<Url, _>' or 'impl Iterator<Item = T> -> Vec<T>'). Matches ignore references and argument order, treat single capital letters as generics and '_' as any type, and are ranked by type similarity.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate"
+                        },
+                        "query": {
+                            "type": "string",
+                            "description": "Signature to search for, as 'ArgType, ArgType -> ReturnType'"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return (optional, defaults to 10)"
+                        }
+                    },
+                    "required": ["crate_name", "version", "query"]
+                }),
+            ),
+            Tool::new(
+                "find_impls".to_string(),
+                "List the implementors of a trait (e.g., 'tower::Service') or the traits implemented for a type (e.g., 'bytes::Bytes') in a Rust crate, including derives and, for traits, blanket impls. Optionally also scans the crate's direct dependencies.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "The name of the crate"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "The version of the crate"
+                        },
+                        "trait_name": {
+                            "type": "string",
+                            "description": "Trait whose implementors to list (give either trait_name or type_name)"
+                        },
+                        "type_name": {
+                            "type": "string",
+                            "description": "Type whose trait impls to list (give either trait_name or type_name)"
+                        },
+                        "include_dependencies": {
+                            "type": "boolean",
+                            "description": "Also scan the crate's direct dependencies (optional, defaults to false)"
+                        },
+                        "include_blanket_impls": {
+                            "type": "boolean",
+                            "description": "With type_name, also list blanket impls such as `impl<T: Display> ToString for T`, which apply to any type meeting their bounds (optional, defaults to false)"
                         }
                     },
                     "required": ["crate_name", "version"]
                 }),
             ),
-        ]
+        ];
+        tools.into_iter().map(with_pagination).map(with_output_options).collect()
     }
 
     fn call_tool(
@@ -374,10 +832,14 @@ impl mcp_server::Router for DocRouter {
         let this = self.clone();
         let tool_name = tool_name.to_string();
         let arguments = arguments.clone();
-        let tldr = self.tldr;
-        let max_tokens = self.max_tokens;
+        let default_pipeline = self.pipeline.clone();
+        let default_tldr = self.tldr;
+        let default_max_tokens = self.max_tokens;
+        let default_format = self.format;
+        let metric_name = tool_name.clone();
+        let started = Instant::now();
 
-        Box::pin(async move {
+        let call = Box::pin(async move {
             let mut result = match tool_name.as_str() {
                 "lookup_crate" => {
                     let crate_name = arguments
@@ -391,8 +853,36 @@ impl mcp_server::Router for DocRouter {
                         .and_then(|v| v.as_str())
                         .map(|s| s.to_string());
                     
-                    let doc = this.lookup_crate(crate_name, version).await?;
-                    Ok(vec![Content::text(doc)])
+                    let view = match arguments.get("view").and_then(|v| v.as_str()) {
+                        Some(view) => CrateView::parse(view).ok_or_else(|| {
+                            ToolError::InvalidParameters(format!(
+                                "view must be one of readme, overview, metadata or all, got {}",
+                                view
+                            ))
+                        })?,
+                        None => CrateView::default(),
+                    };
+                    
+                    let doc = this.lookup_crate(crate_name.clone(), version.clone(), view).await?;
+                    let viewed_version = version.clone().unwrap_or_else(|| "latest".to_string());
+                    match view {
+                        CrateView::Overview => this.recent.record(DocResource::Crate {
+                            crate_name: crate_name.clone(),
+                            version: viewed_version,
+                        }),
+                        CrateView::Readme => this.recent.record(DocResource::Readme {
+                            crate_name: crate_name.clone(),
+                            version: viewed_version,
+                        }),
+                        CrateView::Metadata | CrateView::All => {}
+                    }
+                    let mut contents = vec![Content::text(select_sections(doc, &arguments)?)];
+                    if structured::requested(&arguments) {
+                        let metadata = this.crate_metadata_record(&crate_name, version.as_deref()).await?;
+                        let uri = structured::crate_uri(&crate_name, version.as_deref());
+                        contents.push(structured::embed(&uri, structured::CRATE_SCHEMA, &metadata));
+                    }
+                    Ok(contents)
                 }
                 "search_crates" => {
                     let query = arguments
@@ -406,8 +896,15 @@ impl mcp_server::Router for DocRouter {
                         .and_then(|v| v.as_u64())
                         .map(|v| v as u32);
                     
-                    let results = this.search_crates(query, limit).await?;
-                    Ok(vec![Content::text(results)])
+                    let results = this.search_crates(query.clone(), limit).await?;
+                    let mut contents = vec![Content::text(results.clone())];
+                    if structured::requested(&arguments) {
+                        let hits = structured::search_results(&query, &results).map_err(|e| {
+                            ToolError::ExecutionError(format!("Failed to parse crates.io response: {}", e))
+                        })?;
+                        contents.push(structured::embed(&structured::search_uri(&query), structured::SEARCH_SCHEMA, &hits));
+                    }
+                    Ok(contents)
                 }
                 "lookup_item" => {
                     let crate_name = arguments
@@ -427,8 +924,48 @@ impl mcp_server::Router for DocRouter {
                         .and_then(|v| v.as_str())
                         .map(|s| s.to_string());
                     
-                    let doc = this.lookup_item(crate_name, item_path, version).await?;
-                    Ok(vec![Content::text(doc)])
+                    let doc = this.lookup_item(crate_name.clone(), item_path.clone(), version.clone()).await?;
+                    this.recent.record(DocResource::Item {
+                        crate_name: crate_name.clone(),
+                        version: version.clone().unwrap_or_else(|| "latest".to_string()),
+                        path: strip_crate_prefix(&crate_name, item_path.clone()),
+                    });
+                    let mut contents = vec![Content::text(select_sections(doc, &arguments)?)];
+                    if structured::requested(&arguments) {
+                        let uri = format!(
+                            "{}/item/{}",
+                            structured::crate_uri(&crate_name, version.as_deref()),
+                            strip_crate_prefix(&crate_name, item_path.clone())
+                        );
+                        let record = this.lookup_item_record(crate_name, item_path, version).await?;
+                        contents.push(structured::embed(&uri, structured::ITEM_SCHEMA, &record));
+                    }
+                    Ok(contents)
+                }
+                "doc_outline" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+                    let (title, doc) = match arguments.get("item_path").and_then(|v| v.as_str()) {
+                        Some(item_path) => (
+                            format!("{}::{}", crate_name, item_path),
+                            this.lookup_item(crate_name, item_path.to_string(), version).await?,
+                        ),
+                        None => (crate_name.clone(), this.lookup_crate(crate_name, version, CrateView::Overview).await?),
+                    };
+                    let sections = outline::outline(&doc);
+                    let result = if sections.is_empty() {
+                        format!("# Outline of `{}`\n\nThe page has no headings.\n", title)
+                    } else {
+                        format!("# Outline of `{}`\n\n{}", title, outline::render_outline(&doc, &sections))
+                    };
+                    Ok(vec![Content::text(result)])
                 }
                 "list_crate_items" => {
                     let crate_name = arguments
@@ -458,79 +995,382 @@ impl mcp_server::Router for DocRouter {
                         visibility,
                         module,
                     };
-                    let result = item_list::list_crate_items(
+                    let list = item_list::list_crate_item_records(&crate_name, &version, Some(&filters))
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("list_crate_items failed: {}", e)))?;
+                    let records = structured::requested(&arguments).then(|| {
+                        let uri = format!("{}/items", structured::crate_uri(&crate_name, Some(&version)));
+                        structured::embed(&uri, structured::ITEM_LIST_SCHEMA, &list)
+                    });
+                    let result = item_list::render_item_list(&list);
+                    Ok(std::iter::once(Content::text(result)).chain(records).collect())
+                }
+                "diff_crate_api" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let old_version = arguments
+                        .get("old_version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("old_version is required".to_string()))?
+                        .to_string();
+                    let new_version = arguments
+                        .get("new_version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("new_version is required".to_string()))?
+                        .to_string();
+                    let result = api_diff::diff_crate_api(&crate_name, &old_version, &new_version)
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("diff_crate_api failed: {}", e)))?;
+                    Ok(vec![Content::text(result)])
+                }
+                "find_examples" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("version is required".to_string()))?
+                        .to_string();
+                    let item_path = arguments
+                        .get("item_path")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+                    let token_budget = arguments
+                        .get("token_budget")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize);
+                    let result = examples::find_examples(&crate_name, &version, item_path.as_deref(), token_budget)
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("find_examples failed: {}", e)))?;
+                    Ok(vec![Content::text(result)])
+                }
+                "view_source" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("version is required".to_string()))?
+                        .to_string();
+                    let item_path = arguments
+                        .get("item_path")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("item_path is required".to_string()))?
+                        .to_string();
+                    let context_lines = arguments
+                        .get("context_lines")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize);
+                    let result = view_source::view_source(&crate_name, &version, &item_path, context_lines)
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("view_source failed: {}", e)))?;
+                    Ok(vec![Content::text(result)])
+                }
+                "search_in_crate" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("version is required".to_string()))?
+                        .to_string();
+                    let query = arguments
+                        .get("query")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("query is required".to_string()))?
+                        .to_string();
+                    let limit = arguments
+                        .get("limit")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize);
+                    let result = crate_search::search_in_crate(&crate_name, &version, &query, limit)
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("search_in_crate failed: {}", e)))?;
+                    Ok(vec![Content::text(result)])
+                }
+                "search_by_signature" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("version is required".to_string()))?
+                        .to_string();
+                    let query = arguments
+                        .get("query")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("query is required".to_string()))?
+                        .to_string();
+                    let limit = arguments
+                        .get("limit")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize);
+                    let result = signature_search::search_by_signature(&crate_name, &version, &query, limit)
+                        .await
+                        .map_err(|e| ToolError::ExecutionError(format!("search_by_signature failed: {}", e)))?;
+                    Ok(vec![Content::text(result)])
+                }
+                "find_impls" => {
+                    let crate_name = arguments
+                        .get("crate_name")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("crate_name is required".to_string()))?
+                        .to_string();
+                    let version = arguments
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ToolError::InvalidParameters("version is required".to_string()))?
+                        .to_string();
+                    let trait_name = arguments.get("trait_name").and_then(|v| v.as_str());
+                    let type_name = arguments.get("type_name").and_then(|v| v.as_str());
+                    let query = match (trait_name, type_name) {
+                        (Some(name), None) => impls::ImplQuery::Trait(name),
+                        (None, Some(name)) => impls::ImplQuery::Type(name),
+                        _ => {
+                            return Err(ToolError::InvalidParameters(
+                                "exactly one of trait_name or type_name is required".to_string(),
+                            ))
+                        }
+                    };
+                    let include_dependencies = arguments
+                        .get("include_dependencies")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+                    let include_blanket = arguments
+                        .get("include_blanket_impls")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+                    let result = impls::find_impls(
+                        &this.client,
                         &crate_name,
                         &version,
-                        Some(filters),
+                        query,
+                        include_dependencies,
+                        include_blanket,
                     )
                     .await
-                    .map_err(|e| ToolError::ExecutionError(format!("list_crate_items failed: {}", e)))?;
+                        .map_err(|e| ToolError::ExecutionError(format!("find_impls failed: {}", e)))?;
                     Ok(vec![Content::text(result)])
                 }
                 _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
             }?;
 
-            // Apply TLDR filter if enabled
-            if tldr {
+            // Per-call output controls override the server defaults
+            let max_tokens = match arguments.get("max_tokens").and_then(|v| v.as_u64()) {
+                Some(0) => return Err(ToolError::InvalidParameters("max_tokens must be positive".to_string())),
+                Some(tokens) => Some(tokens as usize),
+                None => default_max_tokens,
+            };
+            let format = match arguments.get("format").and_then(|v| v.as_str()) {
+                Some(name) => OutputFormat::parse(name).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("unknown format '{}'; expected markdown, text or json", name))
+                })?,
+                None => default_format,
+            };
+
+            // Post-process the output: TL;DR first, then the call's filters or the server's pipeline
+            let call_filters = match arguments.get("filters") {
+                Some(Value::String(spec)) => Some(Pipeline::parse(spec).map_err(ToolError::InvalidParameters)?),
+                Some(Value::Array(names)) => Some(
+                    Pipeline::from_names(names.iter().filter_map(|v| v.as_str())).map_err(ToolError::InvalidParameters)?,
+                ),
+                _ => None,
+            };
+            // A call's own filters replace the server's TL;DR default too, unless it asks for `tldr`
+            let tldr = arguments
+                .get("tldr")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(default_tldr && call_filters.is_none());
+            let filters = call_filters.unwrap_or(default_pipeline);
+            let pipeline = if tldr { Pipeline::tldr().then(filters) } else { filters };
+            if !pipeline.is_empty() {
                 for content in &mut result {
                     if let Content::Text(text) = content {
-                        text.text = tldr::apply_tldr(&text.text);
+                        text.text = pipeline.apply(&text.text);
                     }
                 }
             }
 
-            // Apply max_tokens truncation if enabled
-            if let Some(max_tokens) = max_tokens {
+            // Split long output into pages: `page` or `cursor` selects one, and with
+            // max_tokens alone the first page is returned along with a continuation cursor
+            let requested_page = match arguments.get("cursor").and_then(|v| v.as_str()) {
+                Some(cursor) => Some(paginate::page_from_cursor(cursor).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("invalid cursor: {}", cursor))
+                })?),
+                None => arguments.get("page").and_then(|v| v.as_u64()).map(|v| v as usize),
+            };
+            if requested_page.is_some() || max_tokens.is_some() {
+                let page_tokens = max_tokens.unwrap_or(paginate::DEFAULT_PAGE_TOKENS);
+                let number = requested_page.unwrap_or(1);
                 for content in &mut result {
                     if let Content::Text(text) = content {
-                        if let Ok(token_count) = crate::tools::count_tokens(&text.text) {
-                            if token_count > max_tokens {
-                                let mut truncated = text.text.clone();
-                                while crate::tools::count_tokens(&truncated).map_or(0, |c| c) > max_tokens && !truncated.is_empty() {
-                                    truncated.pop();
-                                }
-                                if let Some(last_space) = truncated.rfind(' ') {
-                                    truncated.truncate(last_space);
-                                }
-                                truncated.push_str(" 内容被截断");
-                                text.text = truncated;
-                            }
+                        let pages = paginate::split_pages(&text.text, page_tokens, &crate::tools::estimate_tokens);
+                        if number == 0 || number > pages.len() {
+                            return Err(ToolError::InvalidParameters(format!(
+                                "page {} does not exist; the output has {} page(s)",
+                                number,
+                                pages.len()
+                            )));
                         }
+                        text.text = paginate::render_page(&pages, number);
+                    }
+                }
+            }
+
+            if format != OutputFormat::Markdown {
+                for content in &mut result {
+                    if let Content::Text(text) = content {
+                        text.text = format.apply(&tool_name, &text.text);
                     }
                 }
             }
 
             Ok(result)
+        });
+        Box::pin(async move {
+            let result = call.await;
+            let tool = match &result {
+                // Unknown names are not labels, so clients cannot grow the metrics
+                Err(ToolError::NotFound(_)) => "unknown",
+                _ => metric_name.as_str(),
+            };
+            let bytes = result
+                .as_ref()
+                .map_or(0, |contents| contents.iter().filter_map(Content::as_text).map(str::len).sum());
+            metrics().record_tool_call(tool, started.elapsed(), result.is_ok(), bytes);
+            result
         })
     }
 
     fn list_resources(&self) -> Vec<Resource> {
-        vec![]
+        self.recent.list()
     }
 
     fn read_resource(
         &self,
-        _uri: &str,
+        uri: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        let this = self.clone();
+        let uri = uri.to_string();
         Box::pin(async move {
-            Err(ResourceError::NotFound("Resource not found".to_string()))
+            let resource = DocResource::parse(&uri)
+                .ok_or_else(|| ResourceError::NotFound(format!("Resource {} not found", uri)))?;
+            let contents = this
+                .read_doc_resource(&resource)
+                .await
+                .map_err(|e| ResourceError::ExecutionError(e.to_string()))?;
+            this.recent.record(resource);
+            Ok(contents)
         })
     }
 
     fn list_prompts(&self) -> Vec<Prompt> {
-        vec![]
+        prompts::list_prompts()
     }
 
     fn get_prompt(
         &self,
         prompt_name: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        // The unfilled template; `DocService` renders `prompts/get` with defaults for optional arguments
+        let template = prompts::find_prompt(prompt_name).map(|prompt| prompt.template.to_string());
         let prompt_name = prompt_name.to_string();
         Box::pin(async move {
-            Err(PromptError::NotFound(format!(
+            template.ok_or_else(|| PromptError::NotFound(format!(
                 "Prompt {} not found",
                 prompt_name
             )))
         })
     }
-}
\ No newline at end of file
+}
+
+/// Narrow `doc` to the sections named in the `sections` argument, if any.
+fn select_sections(doc: String, arguments: &Value) -> Result<String, ToolError> {
+    let names: Vec<String> = match arguments.get("sections") {
+        Some(Value::Array(names)) => names.iter().filter_map(|v| v.as_str()).map(str::to_string).collect(),
+        Some(Value::String(names)) => names.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    if names.is_empty() {
+        return Ok(doc);
+    }
+    outline::select_sections(&doc, &names).map_err(ToolError::InvalidParameters)
+}
+
+/// Add the `page` and `cursor` arguments every tool accepts to a tool's schema.
+fn with_pagination(mut tool: Tool) -> Tool {
+    if let Some(properties) = tool.input_schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        properties.insert(
+            "page".to_string(),
+            json!({
+                "type": "integer",
+                "description": "1-based page of the output to return; long output is split into pages at section boundaries (optional)"
+            }),
+        );
+        properties.insert(
+            "cursor".to_string(),
+            json!({
+                "type": "string",
+                "description": "Continuation cursor from the footer of a previous page (optional, takes precedence over page)"
+            }),
+        );
+    }
+    tool
+}
+
+/// Add the `filters`, `tldr`, `max_tokens` and `format` arguments, which override the
+/// server's output defaults for one call.
+fn with_output_options(mut tool: Tool) -> Tool {
+    if let Some(properties) = tool.input_schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        properties.insert(
+            "filters".to_string(),
+            json!({
+                "type": "array",
+                "items": { "type": "string" },
+                "description": format!(
+                    "Output filters applied in order, replacing the server default (optional). Presets: {}. Filters: {}, strip_sections:<regex>",
+                    pipeline::PRESET_NAMES.join(", "),
+                    pipeline::FILTER_NAMES.join(", ")
+                )
+            }),
+        );
+        properties.insert(
+            "tldr".to_string(),
+            json!({
+                "type": "boolean",
+                "description": "Strip LICENSE and VERSION sections and leftover HTML (optional, overrides the server default, which a call's own filters turn off)"
+            }),
+        );
+        properties.insert(
+            "max_tokens".to_string(),
+            json!({
+                "type": "integer",
+                "description": "Maximum tokens per page; longer output is paginated (optional, overrides the server default)"
+            }),
+        );
+        properties.insert(
+            "format".to_string(),
+            json!({
+                "type": "string",
+                "enum": ["markdown", "text", "json"],
+                "description": "Output format (optional, defaults to markdown)"
+            }),
+        );
+    }
+    tool
+}
@@ -1,6 +1,10 @@
 pub mod docs;
+pub mod resources;
+pub mod service;
+pub mod subscriptions;
 
 pub use docs::DocRouter;
+pub use service::DocService;
 
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+mod tests;
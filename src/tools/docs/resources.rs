@@ -0,0 +1,160 @@
+use mcp_core::Resource;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex};
+
+/// URI scheme of documentation resources.
+pub const SCHEME: &str = "cratedocs://";
+
+/// Recently viewed resources kept for `list_resources`.
+pub const MAX_RECENT_RESOURCES: usize = 20;
+
+/// A piece of crate documentation addressable by URI. `version` may be `latest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocResource {
+    /// `cratedocs://{crate}/{version}`: the crate root documentation.
+    Crate { crate_name: String, version: String },
+    /// `cratedocs://{crate}/{version}/readme`
+    Readme { crate_name: String, version: String },
+    /// `cratedocs://{crate}/{version}/item/{path}`, e.g. `.../item/sync::mpsc::Sender`.
+    Item { crate_name: String, version: String, path: String },
+    /// `cratedocs://{crate}/{version}/source/{file}`, a file of the published crate.
+    Source { crate_name: String, version: String, file: String },
+}
+
+impl DocResource {
+    /// Parse a `cratedocs://` URI. Source paths must stay inside the crate.
+    pub fn parse(uri: &str) -> Option<Self> {
+        let rest = uri.strip_prefix(SCHEME)?;
+        let mut parts = rest.splitn(4, '/');
+        let crate_name = parts.next().filter(|name| is_crate_name(name))?.to_string();
+        let version = match parts.next() {
+            Some("") | None => "latest".to_string(),
+            Some(version) => version.to_string(),
+        };
+        let resource = match (parts.next(), parts.next()) {
+            (None, _) | (Some(""), None) => Self::Crate { crate_name, version },
+            (Some("readme"), None) => Self::Readme { crate_name, version },
+            (Some("item"), Some(path)) if !path.is_empty() => Self::Item {
+                crate_name,
+                version,
+                path: path.to_string(),
+            },
+            (Some("source"), Some(file)) if is_relative_path(file) => Self::Source {
+                crate_name,
+                version,
+                file: file.to_string(),
+            },
+            _ => return None,
+        };
+        Some(resource)
+    }
+
+    pub fn uri(&self) -> String {
+        match self {
+            Self::Crate { crate_name, version } => format!("{}{}/{}", SCHEME, crate_name, version),
+            Self::Readme { crate_name, version } => format!("{}{}/{}/readme", SCHEME, crate_name, version),
+            Self::Item { crate_name, version, path } => format!("{}{}/{}/item/{}", SCHEME, crate_name, version, path),
+            Self::Source { crate_name, version, file } => format!("{}{}/{}/source/{}", SCHEME, crate_name, version, file),
+        }
+    }
+
+    pub fn crate_version(&self) -> (&str, &str) {
+        match self {
+            Self::Crate { crate_name, version }
+            | Self::Readme { crate_name, version }
+            | Self::Item { crate_name, version, .. }
+            | Self::Source { crate_name, version, .. } => (crate_name, version),
+        }
+    }
+
+    /// Human-readable name shown by clients.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Crate { crate_name, version } => format!("{} {}", crate_name, version),
+            Self::Readme { crate_name, version } => format!("{} {} README", crate_name, version),
+            Self::Item { crate_name, version, path } => format!("{}::{} ({})", crate_name, path, version),
+            Self::Source { crate_name, version, file } => format!("{} {} {}", crate_name, version, file),
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Source { file, .. } if file.ends_with(".rs") => "text/x-rust",
+            Self::Source { .. } => "text/plain",
+            _ => "text/markdown",
+        }
+    }
+
+    /// The tool that reads the same documentation, for tool allow-lists.
+    pub fn tool(&self) -> &'static str {
+        match self {
+            Self::Crate { .. } | Self::Readme { .. } => "lookup_crate",
+            Self::Item { .. } => "lookup_item",
+            Self::Source { .. } => "view_source",
+        }
+    }
+
+    pub fn to_resource(&self) -> Option<Resource> {
+        Resource::new(self.uri(), Some(self.mime_type().to_string()), Some(self.name())).ok()
+    }
+}
+
+fn is_crate_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_relative_path(file: &str) -> bool {
+    !file.is_empty() && Path::new(file).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// The resource templates, in the shape of a `resources/templates/list` result entry.
+pub fn resource_templates() -> Vec<Value> {
+    vec![
+        json!({
+            "uriTemplate": "cratedocs://{crate}/{version}",
+            "name": "Crate documentation",
+            "description": "Crate root documentation: //! docs, modules and top-level items. Use 'latest' as the version for the newest release.",
+            "mimeType": "text/markdown"
+        }),
+        json!({
+            "uriTemplate": "cratedocs://{crate}/{version}/readme",
+            "name": "Crate README",
+            "description": "The README as published on crates.io",
+            "mimeType": "text/markdown"
+        }),
+        json!({
+            "uriTemplate": "cratedocs://{crate}/{version}/item/{path}",
+            "name": "Item documentation",
+            "description": "Documentation of an item, e.g. cratedocs://tokio/latest/item/sync::mpsc::Sender",
+            "mimeType": "text/markdown"
+        }),
+        json!({
+            "uriTemplate": "cratedocs://{crate}/{version}/source/{file}",
+            "name": "Source file",
+            "description": "A file of the published crate, e.g. cratedocs://serde/1.0.203/source/src/lib.rs",
+            "mimeType": "text/x-rust"
+        }),
+    ]
+}
+
+/// Most recently viewed resources first, without duplicates.
+#[derive(Clone, Default)]
+pub struct RecentResources {
+    resources: Arc<Mutex<VecDeque<DocResource>>>,
+}
+
+impl RecentResources {
+    pub fn record(&self, resource: DocResource) {
+        let mut resources = self.resources.lock().unwrap_or_else(|e| e.into_inner());
+        resources.retain(|r| *r != resource);
+        resources.push_front(resource);
+        resources.truncate(MAX_RECENT_RESOURCES);
+    }
+
+    pub fn list(&self) -> Vec<Resource> {
+        let resources = self.resources.lock().unwrap_or_else(|e| e.into_inner());
+        resources.iter().filter_map(DocResource::to_resource).collect()
+    }
+}
@@ -0,0 +1,182 @@
+use crate::tools::docs::resources::{resource_templates, DocResource};
+use crate::tools::docs::subscriptions::Subscriptions;
+use crate::tools::prompts::{find_prompt, render_prompt};
+use crate::tools::DocRouter;
+use mcp_core::handler::PromptError;
+use mcp_core::protocol::{ErrorData, JsonRpcRequest, JsonRpcResponse, INVALID_PARAMS};
+use mcp_server::router::RouterService;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower_service::Service;
+
+type RouterError = <RouterService<DocRouter> as Service<JsonRpcRequest>>::Error;
+
+/// The router's JSON-RPC service, plus the methods `Router` has no hook for
+/// (`resources/templates/list`, `resources/subscribe`) or handles too simply
+/// (`prompts/get`, which only substitutes the arguments given and leaves
+/// optional ones unfilled).
+#[derive(Clone)]
+pub struct DocService {
+    service: RouterService<DocRouter>,
+    subscriptions: Option<Subscriptions>,
+    /// Tools this client may call; resources count as the tool that reads them.
+    allowed_tools: Option<Arc<BTreeSet<String>>>,
+}
+
+impl DocService {
+    pub fn new(router: DocRouter) -> Self {
+        Self {
+            service: RouterService(router),
+            subscriptions: None,
+            allowed_tools: None,
+        }
+    }
+
+    /// A service that supports resource subscriptions, checking subscribed crates for
+    /// new releases every `poll_interval`. Notifications arrive on the returned receiver;
+    /// polling stops once it is dropped.
+    pub fn with_subscriptions(router: DocRouter, poll_interval: Duration) -> (Self, mpsc::UnboundedReceiver<String>) {
+        let (subscriptions, notifications) = Subscriptions::new(router.client.clone(), router.cache.clone());
+        subscriptions.spawn_poller(poll_interval);
+        let service = Self {
+            service: RouterService(router),
+            subscriptions: Some(subscriptions),
+            allowed_tools: None,
+        };
+        (service, notifications)
+    }
+
+    /// Restrict the client to `tools`, or lift the restriction with `None`.
+    pub fn with_allowed_tools(mut self, tools: Option<Arc<BTreeSet<String>>>) -> Self {
+        self.allowed_tools = tools;
+        self
+    }
+
+    /// Why `req` is refused by the tool allow-list, if it is.
+    fn denied(&self, req: &JsonRpcRequest) -> Option<String> {
+        let allowed = self.allowed_tools.as_ref()?;
+        let params = req.params.as_ref();
+        let tool = match req.method.as_str() {
+            "tools/call" => params.and_then(|p| p.get("name")).and_then(|v| v.as_str())?,
+            "resources/read" | "resources/subscribe" => {
+                DocResource::parse(resource_uri(params).ok()?)?.tool()
+            }
+            _ => return None,
+        };
+        (!allowed.contains(tool)).then(|| format!("Tool {} is not allowed for this client", tool))
+    }
+}
+
+impl Service<JsonRpcRequest> for DocService {
+    type Response = JsonRpcResponse;
+    type Error = RouterError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: JsonRpcRequest) -> Self::Future {
+        if let Some(denied) = self.denied(&req) {
+            let response = response(req.id, Err(denied));
+            return Box::pin(async move { Ok(response) });
+        }
+        let result = match (req.method.as_str(), &self.subscriptions) {
+            ("resources/templates/list", _) => Ok(json!({ "resourceTemplates": resource_templates() })),
+            ("prompts/get", _) => get_prompt(req.params.as_ref()).map_err(|e| e.to_string()),
+            ("initialize", Some(_)) => {
+                let response = self.service.call(req);
+                return Box::pin(async move {
+                    let mut response = response.await?;
+                    // The router's capabilities cannot say whether this transport delivers notifications
+                    if let Some(resources) = response
+                        .result
+                        .as_mut()
+                        .and_then(|result| result.pointer_mut("/capabilities/resources"))
+                        .and_then(|resources| resources.as_object_mut())
+                    {
+                        resources.insert("subscribe".to_string(), Value::Bool(true));
+                    }
+                    Ok(response)
+                });
+            }
+            ("tools/list", _) if self.allowed_tools.is_some() => {
+                let allowed = self.allowed_tools.clone().unwrap_or_default();
+                let response = self.service.call(req);
+                return Box::pin(async move {
+                    let mut response = response.await?;
+                    if let Some(tools) = response
+                        .result
+                        .as_mut()
+                        .and_then(|result| result.get_mut("tools"))
+                        .and_then(|tools| tools.as_array_mut())
+                    {
+                        tools.retain(|tool| tool["name"].as_str().is_some_and(|name| allowed.contains(name)));
+                    }
+                    Ok(response)
+                });
+            }
+            ("resources/subscribe", Some(subscriptions)) => {
+                let subscriptions = subscriptions.clone();
+                return Box::pin(async move {
+                    let result = match resource_uri(req.params.as_ref()) {
+                        Ok(uri) => subscriptions.subscribe(uri).await.map(|_| json!({})).map_err(|e| e.to_string()),
+                        Err(e) => Err(e),
+                    };
+                    Ok(response(req.id, result))
+                });
+            }
+            ("resources/unsubscribe", Some(subscriptions)) => resource_uri(req.params.as_ref()).map(|uri| {
+                subscriptions.unsubscribe(uri);
+                json!({})
+            }),
+            _ => return Box::pin(self.service.call(req)),
+        };
+        let response = response(req.id, result);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+fn response(id: Option<u64>, result: Result<Value, String>) -> JsonRpcResponse {
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(message) => (None, Some(ErrorData { code: INVALID_PARAMS, message, data: None })),
+    };
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result,
+        error,
+    }
+}
+
+fn resource_uri(params: Option<&Value>) -> Result<&str, String> {
+    params
+        .and_then(|p| p.get("uri"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "uri is required".to_string())
+}
+
+/// A `prompts/get` result: the rendered prompt as a single user message.
+fn get_prompt(params: Option<&Value>) -> Result<Value, PromptError> {
+    let name = params
+        .and_then(|p| p.get("name"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| PromptError::InvalidParameters("name is required".to_string()))?;
+    let arguments = params.and_then(|p| p.get("arguments")).and_then(|v| v.as_object());
+    let text = render_prompt(name, arguments)?;
+    let description = find_prompt(name).map(|prompt| prompt.description);
+    Ok(json!({
+        "description": description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
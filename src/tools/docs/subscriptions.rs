@@ -0,0 +1,177 @@
+use crate::tools::docs::resources::DocResource;
+use crate::tools::metrics::observed;
+use crate::tools::DocCache;
+use anyhow::{bail, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often subscribed crates are checked for new releases, unless configured.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The sparse index path of a crate, e.g. `se/rd/serde` or `3/s/syn`.
+pub fn index_path(crate_name: &str) -> String {
+    let name = crate_name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Newest non-yanked version in a sparse index file, preferring stable releases.
+pub fn newest_version(index_body: &str) -> Option<String> {
+    let versions: Vec<semver::Version> = index_body
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|entry| !entry["yanked"].as_bool().unwrap_or(false))
+        .filter_map(|entry| entry["vers"].as_str().and_then(|v| semver::Version::parse(v).ok()))
+        .collect();
+    let stable = versions.iter().filter(|v| v.pre.is_empty()).max();
+    stable.or_else(|| versions.iter().max()).map(|v| v.to_string())
+}
+
+/// Look up the newest published version of a crate in the crates.io sparse index.
+pub async fn latest_indexed_version(client: &Client, crate_name: &str) -> Result<Option<String>> {
+    let url = format!("https://index.crates.io/{}", index_path(crate_name));
+    let response = client
+        .get(&url)
+        .header("User-Agent", "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)")
+        .send()
+        .await;
+    let response = observed(&url, response)?;
+    if !response.status().is_success() {
+        bail!("sparse index returned {} for {}", response.status(), crate_name);
+    }
+    Ok(newest_version(&response.text().await?))
+}
+
+#[derive(Default)]
+struct CrateWatch {
+    uris: BTreeSet<String>,
+    /// Newest version seen so far; `None` until the first successful poll.
+    version: Option<String>,
+}
+
+/// Resource subscriptions of one client. Only `latest` resources change: when a new
+/// version of a watched crate is published, its cached docs are dropped and a
+/// `notifications/resources/updated` message is sent for each subscribed URI.
+#[derive(Clone)]
+pub struct Subscriptions {
+    client: Client,
+    cache: DocCache,
+    watches: Arc<Mutex<HashMap<String, CrateWatch>>>,
+    notifications: mpsc::UnboundedSender<String>,
+}
+
+impl Subscriptions {
+    /// Subscriptions whose notifications, serialized JSON-RPC messages, arrive on the returned receiver.
+    pub fn new(client: Client, cache: DocCache) -> (Self, mpsc::UnboundedReceiver<String>) {
+        let (notifications, receiver) = mpsc::unbounded_channel();
+        let subscriptions = Self {
+            client,
+            cache,
+            watches: Default::default(),
+            notifications,
+        };
+        (subscriptions, receiver)
+    }
+
+    pub async fn subscribe(&self, uri: &str) -> Result<()> {
+        let Some(resource) = DocResource::parse(uri) else {
+            bail!("Resource {} not found", uri);
+        };
+        let (crate_name, version) = resource.crate_version();
+        if version != "latest" {
+            // Published versions never change
+            return Ok(());
+        }
+        let known = self.watches.lock().unwrap_or_else(|e| e.into_inner()).contains_key(crate_name);
+        // Record the current version so the first poll does not report it as new
+        let current = if known {
+            None
+        } else {
+            latest_indexed_version(&self.client, crate_name).await.ok().flatten()
+        };
+        self.watch(crate_name, uri, current);
+        Ok(())
+    }
+
+    /// Add `uri` to the subscriptions of `crate_name`, whose newest version is `version` if known.
+    pub fn watch(&self, crate_name: &str, uri: &str, version: Option<String>) {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        let watch = watches.entry(crate_name.to_string()).or_default();
+        watch.uris.insert(uri.to_string());
+        if watch.version.is_none() {
+            watch.version = version;
+        }
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        watches.retain(|_, watch| {
+            watch.uris.remove(uri);
+            !watch.uris.is_empty()
+        });
+    }
+
+    /// Watched crates and the subscribed URIs of each.
+    pub fn watched(&self) -> Vec<(String, Vec<String>)> {
+        let watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        watches
+            .iter()
+            .map(|(crate_name, watch)| (crate_name.clone(), watch.uris.iter().cloned().collect()))
+            .collect()
+    }
+
+    /// Record the newest version of a crate, notifying subscribers if it changed.
+    pub async fn release_seen(&self, crate_name: &str, version: &str) {
+        let uris = {
+            let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(watch) = watches.get_mut(crate_name) else { return };
+            let previous = watch.version.replace(version.to_string());
+            match previous {
+                Some(previous) if previous != version => watch.uris.iter().cloned().collect::<Vec<_>>(),
+                _ => return,
+            }
+        };
+        tracing::info!(crate_name, version, "new release of a subscribed crate");
+        self.cache.invalidate_latest(crate_name).await;
+        for uri in uris {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/resources/updated",
+                "params": { "uri": uri }
+            });
+            let _ = self.notifications.send(notification.to_string());
+        }
+    }
+
+    /// Check every watched crate once.
+    pub async fn poll(&self) {
+        for (crate_name, _) in self.watched() {
+            match latest_indexed_version(&self.client, &crate_name).await {
+                Ok(Some(version)) => self.release_seen(&crate_name, &version).await,
+                Ok(None) => {}
+                Err(e) => tracing::warn!(crate_name, error = %e, "failed to poll the sparse index"),
+            }
+        }
+    }
+
+    /// Poll every `interval` until the notification receiver is dropped.
+    pub fn spawn_poller(&self, interval: Duration) {
+        let subscriptions = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+            ticker.tick().await;
+            while !subscriptions.notifications.is_closed() {
+                ticker.tick().await;
+                subscriptions.poll().await;
+            }
+        });
+    }
+}
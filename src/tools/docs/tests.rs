@@ -1,8 +1,13 @@
-use crate::tools::{DocCache, DocRouter};
-use mcp_core::{Content, ToolError};
+use crate::tools::docs::resources::{DocResource, RecentResources, MAX_RECENT_RESOURCES};
+use crate::tools::docs::subscriptions::{index_path, newest_version, Subscriptions};
+use crate::tools::{DocCache, DocRouter, DocService};
+use mcp_core::handler::ResourceError;
+use mcp_core::protocol::JsonRpcRequest;
+use mcp_core::{Content, ResourceContents, ToolError};
 use mcp_server::Router;
 use serde_json::json;
 use std::time::Duration;
+use tower_service::Service;
 use reqwest::Client;
 
 // Test DocCache functionality
@@ -75,14 +80,21 @@ async fn test_list_tools() {
     let router = DocRouter::new();
     let tools = router.list_tools();
     
-    // Should have exactly 4 tools (lookup_crate, search_crates, lookup_item, list_crate_items)
-    assert_eq!(tools.len(), 4);
+    // Should have exactly 11 tools (lookup_crate, search_crates, lookup_item, list_crate_items, diff_crate_api, find_examples, view_source, search_in_crate, search_by_signature, find_impls, doc_outline)
+    assert_eq!(tools.len(), 11);
     
     // Check tool names
     let tool_names: Vec<String> = tools.iter().map(|t| t.name.clone()).collect();
     assert!(tool_names.contains(&"lookup_crate".to_string()));
     assert!(tool_names.contains(&"search_crates".to_string()));
     assert!(tool_names.contains(&"lookup_item".to_string()));
+    assert!(tool_names.contains(&"diff_crate_api".to_string()));
+    assert!(tool_names.contains(&"find_examples".to_string()));
+    assert!(tool_names.contains(&"view_source".to_string()));
+    assert!(tool_names.contains(&"search_in_crate".to_string()));
+    assert!(tool_names.contains(&"search_by_signature".to_string()));
+    assert!(tool_names.contains(&"find_impls".to_string()));
+    assert!(tool_names.contains(&"doc_outline".to_string()));
     
     // Verify schema properties
     for tool in &tools {
@@ -114,6 +126,19 @@ async fn test_invalid_tool_call() {
     }
 }
 
+#[tokio::test]
+async fn test_tool_calls_are_counted() {
+    let router = DocRouter::new();
+    let _ = router.call_tool("find_impls", json!({})).await;
+    let _ = router.call_tool("no_such_tool_for_metrics", json!({})).await;
+
+    let text = crate::tools::metrics::metrics().render();
+    assert!(text.contains("cratedocs_tool_calls_total{tool=\"find_impls\",outcome=\"error\"}"));
+    // Unknown names are counted together
+    assert!(text.contains("cratedocs_tool_calls_total{tool=\"unknown\",outcome=\"error\"}"));
+    assert!(!text.contains("no_such_tool_for_metrics"));
+}
+
 #[tokio::test]
 async fn test_lookup_crate_missing_parameter() {
     let router = DocRouter::new();
@@ -165,6 +190,19 @@ async fn test_lookup_item_missing_parameters() {
     }
 }
 
+#[tokio::test]
+async fn test_diff_crate_api_missing_parameters() {
+    let router = DocRouter::new();
+    let result = router.call_tool("diff_crate_api", json!({
+        "crate_name": "reqwest",
+        "old_version": "0.11.27"
+    })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+    if let Err(ToolError::InvalidParameters(msg)) = result {
+        assert!(msg.contains("new_version is required"));
+    }
+}
+
 // Mock-based tests that don't require actual network
 #[tokio::test]
 async fn test_lookup_crate_network_error() {
@@ -239,6 +277,202 @@ async fn test_lookup_crate_uses_cache() {
     }
 }
 
+#[tokio::test]
+async fn test_lookup_crate_view_selects_cache_entry() {
+    let router = DocRouter::new();
+    router.cache.set("test_crate:1.0.0".to_string(), "Crate root docs".to_string()).await;
+    router.cache.set("test_crate:1.0.0#readme".to_string(), "README".to_string()).await;
+
+    for (view, expected) in [(None, "Crate root docs"), (Some("overview"), "Crate root docs"), (Some("readme"), "README")] {
+        let contents = router.call_tool("lookup_crate", json!({
+            "crate_name": "test_crate",
+            "version": "1.0.0",
+            "view": view,
+        })).await.unwrap();
+        if let Content::Text(text) = &contents[0] {
+            assert_eq!(text.text, expected);
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    let result = router.call_tool("lookup_crate", json!({
+        "crate_name": "test_crate",
+        "view": "everything",
+    })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+}
+
+#[tokio::test]
+async fn test_call_tool_paginates_output() {
+    let router = DocRouter::new_with_tldr_and_max_tokens(false, Some(40));
+    let long_doc: String = (1..=6)
+        .map(|i| format!("## Section {}\n{}\n\n", i, "word ".repeat(20)))
+        .collect();
+    router.cache.set("paged_crate".to_string(), long_doc).await;
+
+    let first = router.call_tool("lookup_crate", json!({ "crate_name": "paged_crate" })).await.unwrap();
+    let Content::Text(first) = &first[0] else { panic!("Expected text content") };
+    assert!(first.text.starts_with("## Section 1"));
+    assert!(first.text.contains("\"cursor\": \"page-2\""));
+
+    let second = router.call_tool("lookup_crate", json!({ "crate_name": "paged_crate", "cursor": "page-2" })).await.unwrap();
+    let Content::Text(second) = &second[0] else { panic!("Expected text content") };
+    assert!(second.text.starts_with("## Section 2"));
+
+    let result = router.call_tool("lookup_crate", json!({ "crate_name": "paged_crate", "page": 99 })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+    let result = router.call_tool("lookup_crate", json!({ "crate_name": "paged_crate", "cursor": "bogus" })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+}
+
+#[tokio::test]
+async fn test_call_tool_applies_default_and_per_call_filters() {
+    let router = DocRouter::new_with_tldr(true);
+    router.cache.set(
+        "filtered_crate".to_string(),
+        "# filtered_crate\n![logo](https://example.com/logo.png)\n\n## License\nMIT\n\n## Usage\nCall it.\n".to_string()
+    ).await;
+
+    let text_of = |contents: Vec<Content>| match &contents[0] {
+        Content::Text(text) => text.text.clone(),
+        _ => panic!("Expected text content"),
+    };
+
+    let default = text_of(router.call_tool("lookup_crate", json!({ "crate_name": "filtered_crate" })).await.unwrap());
+    assert!(!default.contains("MIT"));
+    assert!(default.contains("logo.png"));
+
+    let compact = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "filters": ["compact"],
+    })).await.unwrap());
+    assert!(!compact.contains("MIT") && !compact.contains("logo.png"));
+
+    let unfiltered = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "filters": "none",
+    })).await.unwrap());
+    assert!(unfiltered.contains("MIT"));
+
+    let unfiltered = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "tldr": false,
+    })).await.unwrap());
+    assert!(unfiltered.contains("MIT"));
+
+    let tldr = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "filters": "none",
+        "tldr": true,
+    })).await.unwrap());
+    assert!(!tldr.contains("MIT"));
+
+    let text = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "tldr": false,
+        "filters": "none",
+        "format": "json",
+    })).await.unwrap());
+    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert!(value["content"].as_str().unwrap().starts_with("# filtered_crate\n"));
+
+    let paged = text_of(router.call_tool("lookup_crate", json!({
+        "crate_name": "filtered_crate",
+        "max_tokens": 5,
+    })).await.unwrap());
+    assert!(paged.contains("\"cursor\": \"page-2\""));
+
+    let result = router.call_tool("lookup_crate", json!({ "crate_name": "filtered_crate", "format": "yaml" })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+    let result = router.call_tool("lookup_crate", json!({ "crate_name": "filtered_crate", "filters": ["shrink"] })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+}
+
+#[tokio::test]
+async fn test_lookup_item_structured_output() {
+    let router = DocRouter::new();
+    router.cache.set("test_crate:Sender".to_string(), "# Struct Sender\nSends values.\n".to_string()).await;
+    router.cache.set(
+        "test_crate:Sender#record".to_string(),
+        json!({ "kind": "struct", "path": "test_crate::Sender", "signature": "pub struct Sender", "docs": "Sends values.", "since": null, "deprecated": null, "feature_gates": ["sync"] }).to_string(),
+    ).await;
+
+    let plain = router.call_tool("lookup_item", json!({ "crate_name": "test_crate", "item_path": "Sender" })).await.unwrap();
+    assert_eq!(plain.len(), 1);
+
+    let contents = router.call_tool("lookup_item", json!({
+        "crate_name": "test_crate",
+        "item_path": "test_crate::Sender",
+        "structured": true,
+    })).await.unwrap();
+    assert_eq!(contents.len(), 2);
+    assert!(matches!(&contents[0], Content::Text(text) if text.text.starts_with("# Struct Sender")));
+    let Content::Resource(resource) = &contents[1] else { panic!("Expected an embedded resource") };
+    let ResourceContents::TextResourceContents { uri, mime_type, text } = &resource.resource else {
+        panic!("Expected text resource contents")
+    };
+    assert_eq!(uri, "cratedocs://test_crate/latest/item/Sender");
+    assert_eq!(mime_type.as_deref(), Some("application/json"));
+    let value: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(value["schema"], "cratedocs/item/v1");
+    assert_eq!(value["kind"], "struct");
+    assert_eq!(value["feature_gates"], json!(["sync"]));
+}
+
+#[tokio::test]
+async fn test_lookup_item_sections_and_outline() {
+    let router = DocRouter::new();
+    router.cache.set(
+        "test_crate:Sender".to_string(),
+        "# Struct Sender\nSends values.\n\n## Examples\n```rust\n# not a heading\n```\n\n## Panics\nNever.\n\n## Implementations\n### impl Sender\nMethods.\n".to_string()
+    ).await;
+
+    let contents = router.call_tool("lookup_item", json!({
+        "crate_name": "test_crate",
+        "item_path": "Sender",
+        "sections": ["examples", "Implementations"]
+    })).await.unwrap();
+    let Content::Text(text) = &contents[0] else { panic!("Expected text content") };
+    assert_eq!(text.text, "## Examples\n```rust\n# not a heading\n```\n\n## Implementations\n### impl Sender\nMethods.");
+
+    let result = router.call_tool("lookup_item", json!({
+        "crate_name": "test_crate",
+        "item_path": "Sender",
+        "sections": ["Safety"]
+    })).await;
+    match result {
+        Err(ToolError::InvalidParameters(msg)) => assert!(msg.contains("available sections: Struct Sender, Examples, Panics")),
+        _ => panic!("Expected InvalidParameters"),
+    }
+
+    // Blank names match nothing rather than every section
+    let contents = router.call_tool("lookup_item", json!({
+        "crate_name": "test_crate",
+        "item_path": "Sender",
+        "sections": "Panics, "
+    })).await.unwrap();
+    let Content::Text(text) = &contents[0] else { panic!("Expected text content") };
+    assert_eq!(text.text, "## Panics\nNever.");
+    let result = router.call_tool("lookup_item", json!({
+        "crate_name": "test_crate",
+        "item_path": "Sender",
+        "sections": [" "]
+    })).await;
+    assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+    let contents = router.call_tool("doc_outline", json!({
+        "crate_name": "test_crate",
+        "item_path": "Sender"
+    })).await.unwrap();
+    let Content::Text(text) = &contents[0] else { panic!("Expected text content") };
+    assert!(text.text.starts_with("# Outline of `test_crate::Sender`\n\n- Struct Sender (~"));
+    assert!(text.text.contains("\n  - Examples (~"));
+    assert!(text.text.contains("\n    - impl Sender (~"));
+    assert!(!text.text.contains("not a heading"));
+}
+
 #[tokio::test]
 async fn test_lookup_item_uses_cache() {
     let router = DocRouter::new();
@@ -362,4 +596,223 @@ async fn test_search_crates_with_version() {
     } else {
         panic!("Expected text content");
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_doc_resource_uris() {
+    let item = DocResource::parse("cratedocs://tokio/1.38.0/item/sync::mpsc::Sender").unwrap();
+    assert_eq!(item, DocResource::Item {
+        crate_name: "tokio".to_string(),
+        version: "1.38.0".to_string(),
+        path: "sync::mpsc::Sender".to_string(),
+    });
+    assert_eq!(item.uri(), "cratedocs://tokio/1.38.0/item/sync::mpsc::Sender");
+
+    let source = DocResource::parse("cratedocs://serde/latest/source/src/de/mod.rs").unwrap();
+    assert_eq!(source.uri(), "cratedocs://serde/latest/source/src/de/mod.rs");
+    assert_eq!(source.mime_type(), "text/x-rust");
+    assert!(matches!(DocResource::parse("cratedocs://serde/1.0.0/readme"), Some(DocResource::Readme { .. })));
+    assert!(matches!(DocResource::parse("cratedocs://serde"), Some(DocResource::Crate { version, .. }) if version == "latest"));
+
+    // Paths may not leave the crate
+    assert_eq!(DocResource::parse("cratedocs://serde/1.0.0/source/../../etc/passwd"), None);
+    assert_eq!(DocResource::parse("cratedocs://serde/1.0.0/source//etc/passwd"), None);
+    assert_eq!(DocResource::parse("cratedocs://serde/1.0.0/changelog"), None);
+    assert_eq!(DocResource::parse("https://docs.rs/serde"), None);
+}
+
+#[test]
+fn test_recent_resources() {
+    let recent = RecentResources::default();
+    for i in 0..MAX_RECENT_RESOURCES + 5 {
+        recent.record(DocResource::Crate { crate_name: format!("crate{}", i), version: "latest".to_string() });
+    }
+    recent.record(DocResource::Crate { crate_name: "crate10".to_string(), version: "latest".to_string() });
+
+    let resources = recent.list();
+    assert_eq!(resources.len(), MAX_RECENT_RESOURCES);
+    assert_eq!(resources[0].uri, "cratedocs://crate10/latest");
+    assert_eq!(resources.iter().filter(|r| r.uri == "cratedocs://crate10/latest").count(), 1);
+    assert!(resources.iter().all(|r| r.uri != "cratedocs://crate0/latest"));
+}
+
+#[tokio::test]
+async fn test_session_router_shares_cache() {
+    let router = DocRouter::new_with_tldr_and_max_tokens(true, Some(2000));
+    router.recent.record(DocResource::Crate { crate_name: "serde".to_string(), version: "latest".to_string() });
+    let session = router.for_session();
+
+    assert!(session.tldr);
+    assert_eq!(session.max_tokens, Some(2000));
+    assert!(session.recent.list().is_empty());
+
+    // Documentation fetched for one session is served to the others
+    session.cache.set("tokio".to_string(), "cached docs".to_string()).await;
+    assert_eq!(router.for_session().cache.get("tokio").await.as_deref(), Some("cached docs"));
+}
+
+#[tokio::test]
+async fn test_read_resource_unknown_uri() {
+    let router = DocRouter::new();
+    let result = router.read_resource("cratedocs://serde/1.0.0/source/../Cargo.toml").await;
+    assert!(matches!(result, Err(ResourceError::NotFound(_))));
+    assert!(router.list_resources().is_empty());
+}
+
+#[tokio::test]
+async fn test_resource_templates_list() {
+    let mut service = DocService::new(DocRouter::new());
+    let response = service
+        .call(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(7),
+            method: "resources/templates/list".to_string(),
+            params: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.id, Some(7));
+    let templates = response.result.unwrap()["resourceTemplates"].as_array().cloned().unwrap();
+    let uris: Vec<&str> = templates.iter().filter_map(|t| t["uriTemplate"].as_str()).collect();
+    assert!(uris.contains(&"cratedocs://{crate}/{version}/item/{path}"));
+    assert!(uris.contains(&"cratedocs://{crate}/{version}/readme"));
+    assert!(uris.contains(&"cratedocs://{crate}/{version}/source/{file}"));
+}
+
+#[tokio::test]
+async fn test_prompts_get_renders_prompt() {
+    let router = DocRouter::new();
+    assert_eq!(router.list_prompts().len(), 4);
+    assert!(router.get_prompt("compare_crates").await.unwrap().contains("{crate_a}"));
+
+    let mut service = DocService::new(router);
+    let request = |params| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(1),
+        method: "prompts/get".to_string(),
+        params: Some(params),
+    };
+    let response = service
+        .call(request(json!({ "name": "compare_crates", "arguments": { "crate_a": "reqwest", "crate_b": "ureq" } })))
+        .await
+        .unwrap();
+    let result = response.result.unwrap();
+    assert_eq!(result["messages"][0]["role"], "user");
+    let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+    assert!(text.starts_with("Compare the Rust crates `reqwest` and `ureq` for general use."));
+
+    let response = service.call(request(json!({ "name": "compare_crates" }))).await.unwrap();
+    assert!(response.result.is_none());
+    assert!(response.error.unwrap().message.contains("crate_a is required"));
+}
+
+#[tokio::test]
+async fn test_allowed_tools() {
+    let allowed = std::collections::BTreeSet::from(["lookup_crate".to_string()]);
+    let mut service = DocService::new(DocRouter::new()).with_allowed_tools(Some(std::sync::Arc::new(allowed)));
+    let request = |method: &str, params| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(1),
+        method: method.to_string(),
+        params: Some(params),
+    };
+
+    let response = service
+        .call(request("tools/call", json!({ "name": "view_source", "arguments": { "crate_name": "serde" } })))
+        .await
+        .unwrap();
+    assert_eq!(response.error.unwrap().message, "Tool view_source is not allowed for this client");
+
+    // Resources count as the tool that reads them
+    let response = service
+        .call(request("resources/read", json!({ "uri": "cratedocs://serde/1.0.0/source/src/lib.rs" })))
+        .await
+        .unwrap();
+    assert!(response.error.unwrap().message.contains("view_source"));
+    let response = service
+        .call(request("resources/subscribe", json!({ "uri": "cratedocs://serde/latest/item/de::Deserialize" })))
+        .await
+        .unwrap();
+    assert!(response.error.unwrap().message.contains("lookup_item"));
+}
+
+#[test]
+fn test_sparse_index_newest_version() {
+    assert_eq!(index_path("a"), "1/a");
+    assert_eq!(index_path("cc"), "2/cc");
+    assert_eq!(index_path("syn"), "3/s/syn");
+    assert_eq!(index_path("Serde_JSON"), "se/rd/serde_json");
+
+    let index = [
+        r#"{"name":"demo","vers":"1.2.0","yanked":false}"#,
+        r#"{"name":"demo","vers":"1.10.0","yanked":false}"#,
+        r#"{"name":"demo","vers":"1.11.0","yanked":true}"#,
+        r#"{"name":"demo","vers":"2.0.0-rc.1","yanked":false}"#,
+    ]
+    .join("\n");
+    assert_eq!(newest_version(&index).as_deref(), Some("1.10.0"));
+    assert_eq!(newest_version(r#"{"vers":"0.1.0-alpha","yanked":false}"#).as_deref(), Some("0.1.0-alpha"));
+    assert_eq!(newest_version(""), None);
+}
+
+#[tokio::test]
+async fn test_subscriptions_notify_on_new_release() {
+    let cache = DocCache::new();
+    cache.set("tokio".to_string(), "old overview".to_string()).await;
+    cache.set("tokio#readme".to_string(), "old readme".to_string()).await;
+    cache.set("tokio:sync::Mutex".to_string(), "old item".to_string()).await;
+    cache.set("tokio:1.38.0".to_string(), "pinned overview".to_string()).await;
+    cache.set("tokio-util".to_string(), "other crate".to_string()).await;
+
+    let (subscriptions, mut notifications) = Subscriptions::new(Client::new(), cache.clone());
+    subscriptions.watch("tokio", "cratedocs://tokio/latest", Some("1.38.0".to_string()));
+    subscriptions.watch("tokio", "cratedocs://tokio/latest/item/sync::Mutex", None);
+
+    // The known version is not news
+    subscriptions.release_seen("tokio", "1.38.0").await;
+    assert!(notifications.try_recv().is_err());
+    assert!(cache.get("tokio").await.is_some());
+
+    subscriptions.release_seen("tokio", "1.39.0").await;
+    let mut uris = Vec::new();
+    while let Ok(notification) = notifications.try_recv() {
+        let notification: serde_json::Value = serde_json::from_str(&notification).unwrap();
+        assert_eq!(notification["method"], "notifications/resources/updated");
+        uris.push(notification["params"]["uri"].as_str().unwrap().to_string());
+    }
+    assert_eq!(uris, ["cratedocs://tokio/latest", "cratedocs://tokio/latest/item/sync::Mutex"]);
+
+    // Unversioned docs are refetched; pinned versions and other crates stay cached
+    assert_eq!(cache.get("tokio").await, None);
+    assert_eq!(cache.get("tokio#readme").await, None);
+    assert_eq!(cache.get("tokio:sync::Mutex").await, None);
+    assert!(cache.get("tokio:1.38.0").await.is_some());
+    assert!(cache.get("tokio-util").await.is_some());
+
+    subscriptions.unsubscribe("cratedocs://tokio/latest");
+    subscriptions.unsubscribe("cratedocs://tokio/latest/item/sync::Mutex");
+    assert!(subscriptions.watched().is_empty());
+}
+
+#[tokio::test]
+async fn test_resources_subscribe_requests() {
+    let (mut service, _notifications) = DocService::with_subscriptions(DocRouter::new(), Duration::from_secs(3600));
+    let request = |method: &str, params| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(3),
+        method: method.to_string(),
+        params: Some(params),
+    };
+
+    // Pinned versions never change, so subscribing needs no lookup
+    let response = service
+        .call(request("resources/subscribe", json!({ "uri": "cratedocs://serde/1.0.203/readme" })))
+        .await
+        .unwrap();
+    assert_eq!(response.result, Some(json!({})));
+
+    let response = service.call(request("resources/subscribe", json!({ "uri": "file:///etc/passwd" }))).await.unwrap();
+    assert!(response.error.unwrap().message.contains("not found"));
+
+    let response = service.call(request("resources/unsubscribe", json!({}))).await.unwrap();
+    assert_eq!(response.error.unwrap().message, "uri is required");
+}
@@ -0,0 +1,269 @@
+use crate::tools::item_list::{
+    doc_comment, download_and_cache_crate, line_range, parse_crate_modules, release_spans, visit_items,
+    visit_rs_files,
+};
+use crate::tools::estimate_tokens;
+use anyhow::Result;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+/// Default token budget for `find_examples` output.
+pub const DEFAULT_TOKEN_BUDGET: usize = 2000;
+
+/// Code fence languages that rustdoc treats as Rust doctests.
+const RUST_FENCE_ATTRS: [&str; 8] = [
+    "", "rust", "no_run", "should_panic", "ignore", "compile_fail", "edition2018", "edition2021",
+];
+
+/// A code example found in a crate's sources.
+#[derive(Debug, Clone)]
+pub struct Example {
+    /// Where the example came from, e.g. "doctest on `sync::mpsc::channel`".
+    pub title: String,
+    /// File the example was taken from, relative to the crate root.
+    pub file: String,
+    pub code: String,
+    pub score: usize,
+}
+
+/// Download a crate and return its best examples for `item_path`, trimmed to `token_budget`.
+pub async fn find_examples(
+    crate_name: &str,
+    version: &str,
+    item_path: Option<&str>,
+    token_budget: Option<usize>,
+) -> Result<String> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let item_path = item_path.map(|p| {
+        p.strip_prefix(&format!("{}::", crate_name.replace('-', "_")))
+            .unwrap_or(p)
+            .to_string()
+    });
+    let examples = collect_examples(Path::new(&crate_path), item_path.as_deref());
+
+    let heading = match &item_path {
+        Some(path) => format!("# Examples for `{}` ({} {})\n\n", path, crate_name, version),
+        None => format!("# Examples for {} {}\n\n", crate_name, version),
+    };
+    if examples.is_empty() {
+        return Ok(format!("{}No examples found.\n", heading));
+    }
+    Ok(format!(
+        "{}{}",
+        heading,
+        render_examples(&examples, token_budget.unwrap_or(DEFAULT_TOKEN_BUDGET))
+    ))
+}
+
+/// Gather doctests, `examples/` and `tests/` files and README snippets, best match first.
+pub fn collect_examples(crate_dir: &Path, item_path: Option<&str>) -> Vec<Example> {
+    let name = item_path.and_then(|p| p.rsplit("::").next()).filter(|n| !n.is_empty());
+    let name_re = name.and_then(|n| Regex::new(&format!(r"\b{}\b", regex::escape(n))).ok());
+    let mentions = |code: &str| name_re.as_ref().map_or(0, |re| count_mentions(code, re));
+    let relative = |path: &Path| {
+        path.strip_prefix(crate_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    };
+    let mut examples = Vec::new();
+
+    // Doctests, scored highest when attached to the requested item.
+    for module in parse_crate_modules(crate_dir) {
+        let file = relative(&module.file);
+        let mut docs = Vec::new();
+        if module.module_path.is_empty() {
+            docs.push((String::new(), doc_comment(&module.ast.attrs)));
+        }
+        visit_items(&module.ast.items, &module.module_path, &mut |item| {
+            docs.push((item.path, doc_comment(item.attrs)));
+        });
+        for (path, doc) in docs {
+            for code in rust_code_blocks(&doc) {
+                let attached = match item_path {
+                    Some(query) => path == query || path.ends_with(&format!("::{}", query)),
+                    None => path.is_empty(),
+                };
+                let score = if attached {
+                    100
+                } else if name.is_some() {
+                    match mentions(&code) {
+                        0 => continue,
+                        n => 20 + 2 * n,
+                    }
+                } else {
+                    1
+                };
+                let title = if path.is_empty() {
+                    "crate-level doctest".to_string()
+                } else {
+                    format!("doctest on `{}`", path)
+                };
+                examples.push(Example { title, file: file.clone(), code, score });
+            }
+        }
+    }
+
+    // Whole files from examples/, matching test functions from tests/.
+    visit_rs_files(&crate_dir.join("examples"), &mut |path: &Path| {
+        let Ok(content) = fs::read_to_string(path) else { return };
+        let score = match (name, mentions(&content)) {
+            (Some(_), 0) => return,
+            (_, n) => 15 + 2 * n,
+        };
+        examples.push(Example {
+            title: format!("example `{}`", relative(path)),
+            file: relative(path),
+            code: content,
+            score,
+        });
+    });
+    if let Some(name_re) = &name_re {
+        visit_rs_files(&crate_dir.join("tests"), &mut |path: &Path| {
+            let Ok(content) = fs::read_to_string(path) else { return };
+            let Ok(parsed) = syn::parse_file(&content) else { return };
+            for item in parsed.items {
+                let syn::Item::Fn(f) = item else { continue };
+                let code = crate::tools::api_diff::render(&f.block);
+                let n = count_mentions(&code, name_re);
+                if n == 0 {
+                    continue;
+                }
+                examples.push(Example {
+                    title: format!("test `{}` in `{}`", f.sig.ident, relative(path)),
+                    file: relative(path),
+                    code: test_source(&content, &f).unwrap_or(code),
+                    score: 10 + 2 * n,
+                });
+            }
+        });
+    }
+
+    // README code blocks.
+    for readme in ["README.md", "readme.md", "README"] {
+        let Ok(content) = fs::read_to_string(crate_dir.join(readme)) else { continue };
+        for code in rust_code_blocks(&content) {
+            let score = match (name, mentions(&code)) {
+                (Some(_), 0) => continue,
+                (Some(_), n) => 12 + 2 * n,
+                (None, _) => 30,
+            };
+            examples.push(Example {
+                title: "README snippet".to_string(),
+                file: readme.to_string(),
+                code,
+                score,
+            });
+        }
+        break;
+    }
+
+    release_spans();
+
+    // Stable sort keeps source order among equally ranked examples.
+    examples.sort_by_key(|example| std::cmp::Reverse(example.score));
+    examples
+}
+
+/// Render ranked examples as markdown, stopping once `token_budget` is used up.
+pub fn render_examples(examples: &[Example], token_budget: usize) -> String {
+    let mut output = String::new();
+    let mut used = 0;
+    for (index, example) in examples.iter().enumerate() {
+        let section = format!(
+            "## {}. {} ({})\n```rust\n{}\n```\n\n",
+            index + 1,
+            example.title,
+            example.file,
+            example.code.trim_end()
+        );
+        let tokens = estimate_tokens(&section);
+        if used + tokens > token_budget {
+            if index == 0 {
+                // Always return something: keep the leading lines of the best example.
+                let mut code = String::new();
+                for line in example.code.lines() {
+                    if estimate_tokens(&code) + estimate_tokens(line) + 40 > token_budget {
+                        break;
+                    }
+                    code.push_str(line);
+                    code.push('\n');
+                }
+                output.push_str(&format!(
+                    "## 1. {} ({})\n```rust\n{}// ...\n```\n\n",
+                    example.title, example.file, code
+                ));
+            }
+            output.push_str(&format!(
+                "_{} more example(s) omitted to stay within {} tokens._\n",
+                examples.len() - index - usize::from(index == 0),
+                token_budget
+            ));
+            break;
+        }
+        used += tokens;
+        output.push_str(&section);
+    }
+    output
+}
+
+/// Extract Rust code blocks from markdown, dropping rustdoc's hidden `# ` lines.
+pub fn rust_code_blocks(markdown: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let fence = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+        match (&mut current, fence) {
+            (None, Some(fence)) => {
+                let info = trimmed.trim_start_matches(fence).trim().to_string();
+                current = Some((info, Vec::new()));
+            }
+            (Some((info, lines)), Some(_)) => {
+                if is_rust_fence(info) && !lines.is_empty() {
+                    let code: Vec<&str> = lines
+                        .iter()
+                        .filter(|l| {
+                            let t = l.trim_start();
+                            !(t == "#" || t.starts_with("# "))
+                        })
+                        .copied()
+                        .collect();
+                    blocks.push(code.join("\n"));
+                }
+                current = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, None) => {}
+        }
+    }
+    blocks
+}
+
+fn is_rust_fence(info: &str) -> bool {
+    info.split([',', ' '])
+        .map(str::trim)
+        .all(|attr| RUST_FENCE_ATTRS.contains(&attr))
+}
+
+/// Count the matches of `name_re`, the whole-word pattern of a name, in `code`.
+fn count_mentions(code: &str, name_re: &Regex) -> usize {
+    name_re.find_iter(code).count()
+}
+
+/// Cut the original source text of test function `f`, with its attributes, out of `content`.
+fn test_source(content: &str, f: &syn::ItemFn) -> Option<String> {
+    let (start, end) = line_range(f);
+    if start == 0 {
+        return None;
+    }
+    let lines: Vec<&str> = content.lines().skip(start - 1).take(end + 1 - start).collect();
+    Some(lines.join("\n"))
+}
@@ -0,0 +1,84 @@
+use serde_json::{json, Value};
+
+/// How a tool's text output is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The tool's markdown (or raw JSON for `search_crates`), unchanged.
+    #[default]
+    Markdown,
+    /// Markdown with heading, emphasis and code markers removed; search results as a numbered list.
+    Text,
+    /// A JSON document: search results pretty-printed, anything else as `{"content": ...}`.
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "text" | "txt" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+
+    /// Render the output of `tool` in this format.
+    pub fn apply(self, tool: &str, content: &str) -> String {
+        let search_json = if tool == "search_crates" && content.trim().starts_with('{') {
+            serde_json::from_str::<Value>(content).ok()
+        } else {
+            None
+        };
+        match self {
+            Self::Markdown => content.to_string(),
+            Self::Json => match search_json {
+                Some(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| content.to_string()),
+                None => json!({ "content": content }).to_string(),
+            },
+            Self::Text => match search_json {
+                Some(value) => match value.get("crates").and_then(|v| v.as_array()) {
+                    Some(crates) => search_results_text(crates),
+                    None => content.to_string(),
+                },
+                None => markdown_to_text(content),
+            },
+        }
+    }
+}
+
+fn search_results_text(crates: &[Value]) -> String {
+    let mut text_output = String::from("Search Results:\n\n");
+    for (i, crate_info) in crates.iter().enumerate() {
+        let name = crate_info.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown");
+        let description = crate_info.get("description").and_then(|v| v.as_str()).unwrap_or("No description");
+        let downloads = crate_info.get("downloads").and_then(|v| v.as_u64()).unwrap_or(0);
+        text_output.push_str(&format!("{}. {} - {} (Downloads: {})\n", i + 1, name, description, downloads));
+    }
+    text_output
+}
+
+/// A very basic conversion; a proper one would need a markdown parser.
+fn markdown_to_text(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| {
+            let hashes = line.len() - line.trim_start_matches('#').len();
+            if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+                &line[hashes + 1..]
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("**", "")
+        .replace(['*', '`'], "")
+}
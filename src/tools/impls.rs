@@ -0,0 +1,323 @@
+use crate::tools::api_diff::render;
+use crate::tools::item_list::{download_and_cache_crate, is_cfg_test, line_range, parse_crate_modules, release_spans};
+use crate::tools::metrics::observed;
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::path::Path;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Item, Token};
+
+const USER_AGENT: &str = "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)";
+
+/// Direct dependencies scanned at most, to bound downloads.
+const MAX_DEPENDENCIES: usize = 20;
+
+/// One `impl` block or derived trait implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplRecord {
+    /// Crate the impl was found in.
+    pub crate_name: String,
+    /// The implemented trait as written, or `None` for inherent impls.
+    pub trait_path: Option<String>,
+    pub self_ty: String,
+    /// Rendered header, e.g. `impl<T: Buf> Buf for Box<T>` or `#[derive(Clone)]`.
+    pub header: String,
+    /// `impl<T> Trait for T`: applies to every type meeting the bounds.
+    pub blanket: bool,
+    pub derived: bool,
+    /// Location as `file:line`, relative to the crate root.
+    pub location: String,
+}
+
+/// What to look for.
+#[derive(Debug, Clone, Copy)]
+pub enum ImplQuery<'a> {
+    /// Implementors of a trait, e.g. `tower::Service`.
+    Trait(&'a str),
+    /// Traits implemented for a type, e.g. `bytes::Bytes`.
+    Type(&'a str),
+}
+
+/// Download a crate (and optionally its direct dependencies) and list matching impls.
+/// Blanket impls match every type, so a type query lists them only with `include_blanket`.
+pub async fn find_impls(
+    client: &reqwest::Client,
+    crate_name: &str,
+    version: &str,
+    query: ImplQuery<'_>,
+    include_dependencies: bool,
+    include_blanket: bool,
+) -> Result<String> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let mut impls = collect_impls(Path::new(&crate_path), crate_name);
+
+    let mut skipped = Vec::new();
+    if include_dependencies {
+        for (dep_name, dep_version) in direct_dependencies(client, crate_name, version).await? {
+            let dep_path = match dep_version {
+                Ok(dep_version) => download_and_cache_crate(&dep_name, &dep_version).await,
+                Err(e) => Err(e),
+            };
+            match dep_path {
+                Ok(dep_path) => impls.extend(collect_impls(Path::new(&dep_path), &dep_name)),
+                Err(e) => {
+                    tracing::warn!(dependency = %dep_name, error = %e, "skipping dependency");
+                    skipped.push(dep_name);
+                }
+            }
+        }
+    }
+
+    let (heading, matches) = match query {
+        ImplQuery::Trait(name) => (format!("Implementors of `{}`", name), filter_by_trait(&impls, name)),
+        ImplQuery::Type(name) => (format!("Impls for `{}`", name), filter_by_type(&impls, name, include_blanket)),
+    };
+    let mut output = format!("# {} ({} {})\n\n", heading, crate_name, version);
+    output.push_str(&render_impls(&matches, query));
+    if !skipped.is_empty() {
+        output.push_str(&format!("\n_Could not resolve or download: {}_\n", skipped.join(", ")));
+    }
+    Ok(output)
+}
+
+/// Collect every impl block and derive in an unpacked crate, including private modules
+/// but not `#[cfg(test)]` ones.
+pub fn collect_impls(crate_dir: &Path, crate_name: &str) -> Vec<ImplRecord> {
+    let mut impls = Vec::new();
+    for module in parse_crate_modules(crate_dir) {
+        let file = module
+            .file
+            .strip_prefix(crate_dir)
+            .unwrap_or(&module.file)
+            .display()
+            .to_string();
+        collect_items(&module.ast.items, crate_name, &file, &mut impls);
+    }
+    release_spans();
+    impls
+}
+
+fn collect_items(items: &[Item], crate_name: &str, file: &str, out: &mut Vec<ImplRecord>) {
+    for item in items {
+        match item {
+            Item::Impl(block) if !is_cfg_test(&block.attrs) => {
+                let params: Vec<String> = block.generics.type_params().map(|p| p.ident.to_string()).collect();
+                let self_ty = render(&block.self_ty);
+                let header = match &block.trait_ {
+                    Some((negative, trait_path, _)) => format!(
+                        "impl{} {}{} for {}",
+                        render(&block.generics),
+                        if negative.is_some() { "!" } else { "" },
+                        render(trait_path),
+                        self_ty
+                    ),
+                    None => format!("impl{} {}", render(&block.generics), self_ty),
+                };
+                let header = match &block.generics.where_clause {
+                    Some(where_clause) => format!("{} {}", header, render(where_clause)),
+                    None => header,
+                };
+                out.push(ImplRecord {
+                    crate_name: crate_name.to_string(),
+                    trait_path: block.trait_.as_ref().map(|(_, path, _)| render(path)),
+                    blanket: block.trait_.is_some() && params.contains(&strip_refs(&self_ty).to_string()),
+                    self_ty,
+                    header,
+                    derived: false,
+                    location: format!("{}:{}", file, line_range(block).0),
+                });
+            }
+            Item::Struct(s) => push_derives(&s.attrs, &s.ident, item, crate_name, file, out),
+            Item::Enum(e) => push_derives(&e.attrs, &e.ident, item, crate_name, file, out),
+            Item::Union(u) => push_derives(&u.attrs, &u.ident, item, crate_name, file, out),
+            Item::Mod(m) if !is_cfg_test(&m.attrs) => {
+                if let Some((_, inline_items)) = &m.content {
+                    collect_items(inline_items, crate_name, file, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn push_derives(
+    attrs: &[Attribute],
+    ident: &syn::Ident,
+    item: &Item,
+    crate_name: &str,
+    file: &str,
+    out: &mut Vec<ImplRecord>,
+) {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let Ok(paths) = attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated) else {
+            continue;
+        };
+        for path in paths {
+            let trait_path = render(&path);
+            out.push(ImplRecord {
+                crate_name: crate_name.to_string(),
+                header: format!("#[derive({})]", trait_path),
+                trait_path: Some(trait_path),
+                self_ty: ident.to_string(),
+                blanket: false,
+                derived: true,
+                location: format!("{}:{}", file, line_range(item).0),
+            });
+        }
+    }
+}
+
+/// Impls of the trait named `query`, compared on the last path segment without generics.
+pub fn filter_by_trait<'a>(impls: &'a [ImplRecord], query: &str) -> Vec<&'a ImplRecord> {
+    let wanted = last_segment(query);
+    impls
+        .iter()
+        .filter(|record| record.trait_path.as_deref().map(last_segment) == Some(wanted))
+        .collect()
+}
+
+/// Impls whose self type is `query` (through references), plus blanket impls if `include_blanket`.
+pub fn filter_by_type<'a>(impls: &'a [ImplRecord], query: &str, include_blanket: bool) -> Vec<&'a ImplRecord> {
+    let wanted = last_segment(query);
+    impls
+        .iter()
+        .filter(|record| {
+            if record.blanket {
+                include_blanket
+            } else {
+                last_segment(strip_refs(&record.self_ty)) == wanted
+            }
+        })
+        .collect()
+}
+
+/// A section heading and the predicate selecting its impls.
+type ImplGroup = (&'static str, fn(&ImplRecord) -> bool);
+
+/// Render matches grouped into direct, derived and blanket impls.
+pub fn render_impls(matches: &[&ImplRecord], query: ImplQuery<'_>) -> String {
+    if matches.is_empty() {
+        return "No matching impls found.\n".to_string();
+    }
+    let mut output = String::new();
+    let groups: [ImplGroup; 4] = [
+        ("Implementations", |r| !r.derived && !r.blanket && r.trait_path.is_some()),
+        ("Derived", |r| r.derived),
+        ("Blanket implementations", |r| r.blanket),
+        ("Inherent impls", |r| r.trait_path.is_none()),
+    ];
+    for (title, belongs) in groups {
+        let group: Vec<&&ImplRecord> = matches.iter().filter(|r| belongs(r)).collect();
+        if group.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("## {} ({})\n", title, group.len()));
+        if title == "Blanket implementations" && matches!(query, ImplQuery::Type(_)) {
+            output.push_str("_These apply when the type satisfies the bounds._\n");
+        }
+        for record in group {
+            let subject = match query {
+                ImplQuery::Trait(_) => &record.self_ty,
+                ImplQuery::Type(_) => record.trait_path.as_ref().unwrap_or(&record.self_ty),
+            };
+            output.push_str(&format!(
+                "- `{}`: `{}` ({} {})\n",
+                subject, record.header, record.crate_name, record.location
+            ));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn last_segment(path: &str) -> &str {
+    let path = path.split('<').next().unwrap_or(path).trim();
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+/// `&'a mut dyn Foo` becomes `Foo`.
+fn strip_refs(mut ty: &str) -> &str {
+    loop {
+        ty = ty.trim_start();
+        if let Some(rest) = ty.strip_prefix('&') {
+            ty = rest;
+        } else if ty.starts_with('\'') {
+            ty = ty.split_once(' ').map_or("", |(_, rest)| rest);
+        } else if let Some(rest) = ty.strip_prefix("mut ").or_else(|| ty.strip_prefix("dyn ")) {
+            ty = rest;
+        } else {
+            return ty.trim_end();
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DependenciesResponse {
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    crate_id: String,
+    req: String,
+    kind: String,
+    optional: bool,
+}
+
+#[derive(Deserialize)]
+struct VersionsResponse {
+    versions: Vec<Version>,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    num: String,
+    yanked: bool,
+}
+
+/// Resolve a crate's required normal dependencies to the newest versions crates.io allows.
+/// A dependency that cannot be resolved comes with the error, so the others are still scanned.
+async fn direct_dependencies(
+    client: &reqwest::Client,
+    crate_name: &str,
+    version: &str,
+) -> Result<Vec<(String, Result<String>)>> {
+    let url = format!("https://crates.io/api/v1/crates/{}/{}/dependencies", crate_name, version);
+    let response = observed(&url, client.get(&url).header("User-Agent", USER_AGENT).send().await)?;
+    if !response.status().is_success() {
+        bail!("failed to fetch dependencies of {} {}: {}", crate_name, version, response.status());
+    }
+    let deps: DependenciesResponse = response.json().await?;
+
+    let mut resolved = Vec::new();
+    for dep in deps
+        .dependencies
+        .into_iter()
+        .filter(|dep| dep.kind == "normal" && !dep.optional)
+        .take(MAX_DEPENDENCIES)
+    {
+        let version = resolve_dependency(client, &dep).await;
+        resolved.push((dep.crate_id, version));
+    }
+    Ok(resolved)
+}
+
+/// The newest unyanked version of `dep` its requirement matches.
+async fn resolve_dependency(client: &reqwest::Client, dep: &Dependency) -> Result<String> {
+    let url = format!("https://crates.io/api/v1/crates/{}/versions", dep.crate_id);
+    let response = observed(&url, client.get(&url).header("User-Agent", USER_AGENT).send().await)?;
+    if !response.status().is_success() {
+        bail!("failed to fetch versions of {}: {}", dep.crate_id, response.status());
+    }
+    let versions: VersionsResponse = response.json().await?;
+    let req = semver::VersionReq::parse(&dep.req).map_err(|e| anyhow!("{}: {}", dep.crate_id, e))?;
+    versions
+        .versions
+        .iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| semver::Version::parse(&v.num).ok())
+        .filter(|v| req.matches(v))
+        .max()
+        .map(|best| best.to_string())
+        .ok_or_else(|| anyhow!("no version of {} matches {}", dep.crate_id, dep.req))
+}
@@ -1,10 +1,20 @@
-use anyhow::Result;
+use crate::tools::api_diff::collect_public_api;
+use crate::tools::metrics::observed;
+use crate::tools::structured::{Deprecation, ItemList, ItemRecord};
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
 use reqwest;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tar::Archive;
 use flate2::read::GzDecoder;
-use syn::{Item};
+use quote::ToTokens;
+use syn::{Attribute, ImplItem, Item, TraitItem, UseTree};
+
+/// Where downloaded crate sources are unpacked.
+pub const CACHE_DIR: &str = "./cache";
 
 /// Represents filters for item listing.
 #[derive(Debug)]
@@ -14,96 +24,709 @@ pub struct ItemListFilters {
     pub module: Option<String>,
 }
 
+/// Name of the cache directory for a crate version, after checking that neither part of
+/// it, both taken from tool calls, can lead the path out of the cache.
+pub(crate) fn crate_dir_name(crate_name: &str, version: &str) -> Result<String> {
+    let valid_name = !crate_name.is_empty()
+        && crate_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        bail!("invalid crate name `{}`", crate_name);
+    }
+    let version = semver::Version::parse(version).map_err(|e| anyhow!("invalid version `{}`: {}", version, e))?;
+    Ok(format!("{}-{}", crate_name, version))
+}
+
 /// Utility function to download and cache crate source.
-async fn download_and_cache_crate(crate_name: &str, version: &str) -> Result<String> {
-    let cache_dir = Path::new("./cache");
-    let crate_dir = cache_dir.join(format!("{}-{}", crate_name, version));
+///
+/// The tarball is unpacked into a private staging directory and renamed into place,
+/// so a failed or concurrent download never leaves a partial crate in the cache.
+pub(crate) async fn download_and_cache_crate(crate_name: &str, version: &str) -> Result<String> {
+    let dir_name = crate_dir_name(crate_name, version)?;
+    let cache_dir = Path::new(CACHE_DIR);
+    let crate_dir = cache_dir.join(&dir_name);
+
+    if !crate_dir.exists() {
+        let url = format!("https://crates.io/api/v1/crates/{}/{}/download", crate_name, version);
+        let response = observed(&url, reqwest::get(&url).await)?.error_for_status()?;
+        let tarball = response.bytes().await?;
 
-    if crate_dir.exists() {
-        return Ok(crate_dir.to_string_lossy().to_string());
+        fs::create_dir_all(cache_dir)?;
+        let staging = cache_dir.join(format!(".download-{:016x}", rand::random::<u64>()));
+        let unpacked = (|| -> Result<()> {
+            Archive::new(GzDecoder::new(&*tarball)).unpack(&staging)?;
+            match fs::rename(staging.join(&dir_name), &crate_dir) {
+                // Another request unpacked the same version first
+                Err(_) if crate_dir.exists() => Ok(()),
+                result => Ok(result.with_context(|| format!("{} {} has no {} directory", crate_name, version, dir_name))?),
+            }
+        })();
+        let _ = fs::remove_dir_all(&staging);
+        unpacked?;
     }
 
-    let url = format!("https://crates.io/api/v1/crates/{}/{}/download", crate_name, version);
-    let response = reqwest::get(&url).await?;
-    let tarball = response.bytes().await?;
+    let crate_dir = fs::canonicalize(&crate_dir)?;
+    if !crate_dir.starts_with(fs::canonicalize(cache_dir)?) {
+        bail!("{} {} is not in the crate cache", crate_name, version);
+    }
+    Ok(crate_dir.to_string_lossy().to_string())
+}
 
-    fs::create_dir_all(&cache_dir)?;
-    let tar_gz = GzDecoder::new(&*tarball);
-    let mut archive = Archive::new(tar_gz);
-    archive.unpack(&cache_dir)?;
+/// Recursively visit every `.rs` file below `dir`.
+pub(crate) fn visit_rs_files<F: FnMut(&Path)>(dir: &Path, cb: &mut F) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                visit_rs_files(&path, cb);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some("rs") {
+                cb(&path);
+            }
+        }
+    }
+}
 
-    Ok(crate_dir.to_string_lossy().to_string())
+/// A parsed source file together with the module path it is mounted at.
+pub(crate) struct SourceModule {
+    /// Module path relative to the crate root (empty for `lib.rs`).
+    pub module_path: Vec<String>,
+    pub file: PathBuf,
+    /// Whether every module on the way from the crate root is declared `pub`.
+    pub public: bool,
+    pub ast: syn::File,
 }
 
-/// Stub for the crate item enumeration tool.
-/// This will use rust-analyzer to enumerate items in a crate.
-pub async fn list_crate_items(
-    crate_name: &str,
-    version: &str,
-    filters: Option<ItemListFilters>,
-) -> Result<String> {
-    let crate_path = download_and_cache_crate(crate_name, version).await?;
-    let mut items = Vec::new();
-
-    // Most crates have their source in a "src" subdirectory
-    let src_path = Path::new(&crate_path).join("src");
-
-    fn visit_rs_files<F: FnMut(&Path)>(dir: &Path, cb: &mut F) {
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    visit_rs_files(&path, cb);
-                } else if path.extension().and_then(|ext| ext.to_str()) == Some("rs") {
-                    cb(&path);
+/// Parse a crate's module tree by following `mod` declarations from `src/lib.rs`.
+///
+/// Inline modules stay inside their parent's `ast`; only file-backed modules
+/// get their own entry. Files that fail to parse are skipped.
+pub(crate) fn parse_crate_modules(crate_dir: &Path) -> Vec<SourceModule> {
+    let src = crate_dir.join("src");
+    let root = if src.join("lib.rs").exists() {
+        src.join("lib.rs")
+    } else {
+        src.join("main.rs")
+    };
+    let mut modules = Vec::new();
+    parse_module_file(&root, &src, Vec::new(), true, &mut modules);
+    modules
+}
+
+fn parse_module_file(
+    file: &Path,
+    dir: &Path,
+    module_path: Vec<String>,
+    public: bool,
+    out: &mut Vec<SourceModule>,
+) {
+    let Ok(content) = fs::read_to_string(file) else {
+        return;
+    };
+    let Ok(ast) = syn::parse_file(&content) else {
+        return;
+    };
+    let file_dir = file.parent().unwrap_or(dir);
+    collect_file_mods(&ast.items, dir, file_dir, &module_path, public, out);
+    out.push(SourceModule {
+        module_path,
+        file: file.to_path_buf(),
+        public,
+        ast,
+    });
+}
+
+/// Find `mod foo;` declarations (also inside inline modules) and parse their files.
+fn collect_file_mods(
+    items: &[Item],
+    dir: &Path,
+    file_dir: &Path,
+    module_path: &[String],
+    public: bool,
+    out: &mut Vec<SourceModule>,
+) {
+    for item in items {
+        let Item::Mod(m) = item else { continue };
+        let name = m.ident.to_string();
+        let mut child_path = module_path.to_vec();
+        child_path.push(name.clone());
+        let child_public = public && matches!(m.vis, syn::Visibility::Public(_));
+        let path_attr = m.attrs.iter().find_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) if nv.path.is_ident("path") => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        });
+        match &m.content {
+            Some((_, inline_items)) => {
+                collect_file_mods(inline_items, &dir.join(&name), file_dir, &child_path, child_public, out);
+            }
+            None => {
+                let candidates = match path_attr {
+                    Some(p) => vec![file_dir.join(p)],
+                    None => vec![dir.join(format!("{}.rs", name)), dir.join(&name).join("mod.rs")],
+                };
+                if let Some(file) = candidates.into_iter().find(|p| p.exists()) {
+                    parse_module_file(&file, &dir.join(&name), child_path, child_public, out);
                 }
             }
         }
     }
+}
+
+/// Where the public items of a module can be reached from outside the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModuleExport {
+    /// Module path the items appear at, relative to the crate root.
+    pub path: Vec<String>,
+    /// Items re-exported one by one (`pub use imp::{A, B}`); every item if `None`.
+    pub names: Option<BTreeSet<String>>,
+}
+
+impl ModuleExport {
+    /// Whether the module's top-level item `name` is reachable through this export.
+    pub fn includes(&self, name: &str) -> bool {
+        self.names.as_ref().is_none_or(|names| names.contains(name))
+    }
+
+    /// Whether `item`, a top-level item of the module, is reachable through this export.
+    /// Impls go with the type they extend; other unnamed items only with the whole module.
+    pub fn includes_item(&self, item: &Item) -> bool {
+        match item_name(item) {
+            Some(name) => self.includes(&name),
+            None => self.names.is_none(),
+        }
+    }
+}
+
+/// Rounds of re-export resolution, which also bound cycles such as `pub use super::*`.
+const MAX_EXPORT_ROUNDS: usize = 8;
+
+/// Where each of `modules` is exported: at its own path if every module on the way is
+/// `pub`, and wherever a `pub use` re-exports it or some of its items, as in
+/// `mod imp; pub use imp::*;`. Renamed re-exports (`pub use imp::A as B`) are not
+/// followed. Modules nothing outside the crate can reach get no exports.
+pub(crate) fn module_exports(modules: &[SourceModule]) -> Vec<Vec<ModuleExport>> {
+    let index: HashMap<&[String], usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(i, module)| (module.module_path.as_slice(), i))
+        .collect();
+    let mut exports: Vec<Vec<ModuleExport>> = modules
+        .iter()
+        .map(|module| match module.public {
+            true => vec![ModuleExport {
+                path: module.module_path.clone(),
+                names: None,
+            }],
+            false => Vec::new(),
+        })
+        .collect();
 
-    visit_rs_files(&src_path, &mut |path: &Path| {
-        if let Ok(content) = fs::read_to_string(path) {
-            if let Ok(parsed_file) = syn::parse_file(&content) {
-                for item in parsed_file.items {
-                    if let Item::Struct(s) = &item {
-                        if filters.as_ref().map_or(true, |f| f.item_type.as_deref().map_or(true, |ty| ty == "struct")) {
-                            items.push(("Structs", format!("{}", s.ident)));
+    for _ in 0..MAX_EXPORT_ROUNDS {
+        let mut found = Vec::new();
+        for (module, module_exports) in modules.iter().zip(&exports) {
+            for export in module_exports {
+                for item in &module.ast.items {
+                    match item {
+                        Item::Use(u) if is_pub(&u.vis) && u.leading_colon.is_none() => {
+                            let mut targets = Vec::new();
+                            use_targets(&u.tree, Vec::new(), &mut targets);
+                            for (target, name) in targets {
+                                let Some(target) = resolve_module(&module.module_path, target) else { continue };
+                                if let Some(&i) = index.get(target.as_slice()) {
+                                    let names = name.map(|name| BTreeSet::from([name]));
+                                    found.push((i, ModuleExport { path: export.path.clone(), names }));
+                                }
+                            }
                         }
-                    }
-                    if let Item::Enum(e) = &item {
-                        if filters.as_ref().map_or(true, |f| f.item_type.as_deref().map_or(true, |ty| ty == "enum")) {
-                            items.push(("Enums", format!("{}", e.ident)));
+                        // Public child modules go wherever their parent is re-exported
+                        Item::Mod(m) if m.content.is_none() && is_pub(&m.vis) && export.includes(&m.ident.to_string()) => {
+                            let mut child = module.module_path.clone();
+                            child.push(m.ident.to_string());
+                            if let Some(&i) = index.get(child.as_slice()) {
+                                let mut path = export.path.clone();
+                                path.push(m.ident.to_string());
+                                found.push((i, ModuleExport { path, names: None }));
+                            }
                         }
+                        _ => {}
                     }
-                    if let Item::Trait(t) = &item {
-                        if filters.as_ref().map_or(true, |f| f.item_type.as_deref().map_or(true, |ty| ty == "trait")) {
-                            items.push(("Traits", format!("{}", t.ident)));
-                        }
+                }
+            }
+        }
+        let mut changed = false;
+        for (i, export) in found {
+            changed |= merge_export(&mut exports[i], export);
+        }
+        if !changed {
+            break;
+        }
+    }
+    exports
+}
+
+/// Add `export` to `exports`, returning whether anything new became reachable.
+fn merge_export(exports: &mut Vec<ModuleExport>, export: ModuleExport) -> bool {
+    let Some(existing) = exports.iter_mut().find(|existing| existing.path == export.path) else {
+        exports.push(export);
+        return true;
+    };
+    match (&mut existing.names, export.names) {
+        (None, _) => false,
+        (names, None) => {
+            *names = None;
+            true
+        }
+        (Some(names), Some(added)) => {
+            let before = names.len();
+            names.extend(added);
+            names.len() > before
+        }
+    }
+}
+
+/// Flatten a `use` tree into `(module path, name)` pairs; globs have no name.
+fn use_targets(tree: &UseTree, prefix: Vec<String>, out: &mut Vec<(Vec<String>, Option<String>)>) {
+    match tree {
+        UseTree::Path(p) => {
+            let mut prefix = prefix;
+            prefix.push(p.ident.to_string());
+            use_targets(&p.tree, prefix, out);
+        }
+        UseTree::Name(n) if !prefix.is_empty() && n.ident != "self" => out.push((prefix, Some(n.ident.to_string()))),
+        UseTree::Glob(_) => out.push((prefix, None)),
+        UseTree::Group(g) => {
+            for tree in &g.items {
+                use_targets(tree, prefix.clone(), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolve a `use` path written in module `current` to a module path of this crate.
+fn resolve_module(current: &[String], segments: Vec<String>) -> Option<Vec<String>> {
+    let mut path = current.to_vec();
+    for (i, segment) in segments.into_iter().enumerate() {
+        match segment.as_str() {
+            "crate" if i == 0 => path.clear(),
+            "self" => {}
+            "super" => {
+                path.pop()?;
+            }
+            _ => path.push(segment),
+        }
+    }
+    Some(path)
+}
+
+/// The public path of every type and trait reachable through `exports`, by name, so
+/// impls written elsewhere can be attributed to the type they extend.
+pub(crate) fn public_type_paths(modules: &[SourceModule], exports: &[Vec<ModuleExport>]) -> HashMap<String, String> {
+    let mut paths: HashMap<String, String> = HashMap::new();
+    for (module, module_exports) in modules.iter().zip(exports) {
+        for export in module_exports {
+            for item in &module.ast.items {
+                let (Item::Struct(syn::ItemStruct { vis, .. })
+                | Item::Enum(syn::ItemEnum { vis, .. })
+                | Item::Union(syn::ItemUnion { vis, .. })
+                | Item::Type(syn::ItemType { vis, .. })
+                | Item::Trait(syn::ItemTrait { vis, .. })) = item
+                else {
+                    continue;
+                };
+                let Some(name) = item_name(item).filter(|name| is_pub(vis) && export.includes(name)) else { continue };
+                let mut path = export.path.clone();
+                path.push(name.clone());
+                let path = path.join("::");
+                // The shortest path is the one users write
+                if paths.get(&name).is_none_or(|known| path.len() < known.len()) {
+                    paths.insert(name, path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Impl blocks the exported items do not cover: those in modules without exports and in
+/// private inline modules, leaving out `#[cfg(test)]` modules.
+pub(crate) fn hidden_impls<'a>(modules: &'a [SourceModule], exports: &[Vec<ModuleExport>]) -> Vec<&'a syn::ItemImpl> {
+    fn walk<'a>(items: &'a [Item], reached: bool, out: &mut Vec<&'a syn::ItemImpl>) {
+        for item in items {
+            match item {
+                Item::Impl(i) if !reached => out.push(i),
+                Item::Mod(m) if !is_cfg_test(&m.attrs) => {
+                    if let Some((_, inline_items)) = &m.content {
+                        walk(inline_items, reached && is_pub(&m.vis), out);
                     }
-                    if let Item::Fn(f) = &item {
-                        if filters.as_ref().map_or(true, |f| f.item_type.as_deref().map_or(true, |ty| ty == "fn")) {
-                            items.push(("Functions", format!("{}", f.sig.ident)));
-                        }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut impls = Vec::new();
+    for (module, module_exports) in modules.iter().zip(exports) {
+        walk(&module.ast.items, !module_exports.is_empty(), &mut impls);
+    }
+    impls
+}
+
+/// Name of the type an impl block extends, e.g. `Client` for `impl<T> Client<T>`.
+pub(crate) fn impl_type_name(i: &syn::ItemImpl) -> Option<String> {
+    match &*i.self_ty {
+        syn::Type::Path(p) => p.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Name a top-level item is known by; an impl goes by the type it extends.
+fn item_name(item: &Item) -> Option<String> {
+    let ident = match item {
+        Item::Fn(f) => &f.sig.ident,
+        Item::Struct(s) => &s.ident,
+        Item::Enum(e) => &e.ident,
+        Item::Union(u) => &u.ident,
+        Item::Trait(t) => &t.ident,
+        Item::Type(t) => &t.ident,
+        Item::Const(c) => &c.ident,
+        Item::Static(s) => &s.ident,
+        Item::Mod(m) => &m.ident,
+        Item::Macro(m) => m.ident.as_ref()?,
+        Item::Impl(i) => return impl_type_name(i),
+        _ => return None,
+    };
+    Some(ident.to_string())
+}
+
+fn is_pub(vis: &syn::Visibility) -> bool {
+    matches!(vis, syn::Visibility::Public(_))
+}
+
+/// `#[cfg(test)]`, also within `all(...)` and the like.
+pub(crate) fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    fn names_test(tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => ident == "test",
+            proc_macro2::TokenTree::Group(group) => names_test(group.stream()),
+            _ => false,
+        })
+    }
+    attrs.iter().any(|attr| match &attr.meta {
+        syn::Meta::List(list) if list.path.is_ident("cfg") => names_test(list.tokens.clone()),
+        _ => false,
+    })
+}
+
+/// An item found while walking a module, with the path it is declared at.
+pub(crate) struct VisitedItem<'a> {
+    /// Full path relative to the crate root; methods are `Type::method`.
+    pub path: String,
+    pub kind: &'static str,
+    /// Declared `pub` (trait methods and trait impl methods inherit this).
+    pub public: bool,
+    pub attrs: &'a [Attribute],
+    /// First and last line of the item in its file, including doc comments.
+    pub lines: (usize, usize),
+}
+
+/// Walk `items` depth-first, descending into inline modules, impls and traits.
+pub(crate) fn visit_items<'a, F: FnMut(VisitedItem<'a>)>(items: &'a [Item], module_path: &[String], cb: &mut F) {
+    let path_of = |name: String| {
+        let mut parts = module_path.to_vec();
+        parts.push(name);
+        parts.join("::")
+    };
+    let is_pub = |vis: &syn::Visibility| matches!(vis, syn::Visibility::Public(_));
+    for item in items {
+        let (name, kind, public, attrs) = match item {
+            Item::Fn(f) => (f.sig.ident.to_string(), "fn", is_pub(&f.vis), &f.attrs),
+            Item::Struct(s) => (s.ident.to_string(), "struct", is_pub(&s.vis), &s.attrs),
+            Item::Enum(e) => (e.ident.to_string(), "enum", is_pub(&e.vis), &e.attrs),
+            Item::Union(u) => (u.ident.to_string(), "union", is_pub(&u.vis), &u.attrs),
+            Item::Type(t) => (t.ident.to_string(), "type", is_pub(&t.vis), &t.attrs),
+            Item::Const(c) => (c.ident.to_string(), "const", is_pub(&c.vis), &c.attrs),
+            Item::Static(s) => (s.ident.to_string(), "static", is_pub(&s.vis), &s.attrs),
+            Item::Macro(m) => match &m.ident {
+                Some(ident) => {
+                    let exported = m.attrs.iter().any(|attr| attr.path().is_ident("macro_export"));
+                    (ident.to_string(), "macro", exported, &m.attrs)
+                }
+                None => continue,
+            },
+            Item::Trait(t) => {
+                let trait_path = path_of(t.ident.to_string());
+                for trait_item in &t.items {
+                    if let TraitItem::Fn(f) = trait_item {
+                        cb(VisitedItem {
+                            path: format!("{}::{}", trait_path, f.sig.ident),
+                            kind: "method",
+                            public: is_pub(&t.vis),
+                            attrs: &f.attrs,
+                            lines: line_range(f),
+                        });
                     }
                 }
+                (t.ident.to_string(), "trait", is_pub(&t.vis), &t.attrs)
+            }
+            Item::Impl(i) => {
+                if let Some(type_name) = impl_type_name(i) {
+                    visit_impl(i, &path_of(type_name), cb);
+                }
+                continue;
+            }
+            Item::Mod(m) => {
+                if let Some((_, inline_items)) = &m.content {
+                    let mut child_path = module_path.to_vec();
+                    child_path.push(m.ident.to_string());
+                    visit_items(inline_items, &child_path, cb);
+                }
+                (m.ident.to_string(), "mod", is_pub(&m.vis), &m.attrs)
             }
+            _ => continue,
+        };
+        cb(VisitedItem {
+            path: path_of(name),
+            kind,
+            public,
+            attrs,
+            lines: line_range(item),
+        });
+    }
+}
+
+/// Report the methods of impl block `i` as members of the type at `type_path`.
+pub(crate) fn visit_impl<'a, F: FnMut(VisitedItem<'a>)>(i: &'a syn::ItemImpl, type_path: &str, cb: &mut F) {
+    for impl_item in &i.items {
+        if let ImplItem::Fn(f) = impl_item {
+            cb(VisitedItem {
+                path: format!("{}::{}", type_path, f.sig.ident),
+                kind: "method",
+                public: i.trait_.is_some() || is_pub(&f.vis),
+                attrs: &f.attrs,
+                lines: line_range(f),
+            });
         }
-    });
+    }
+}
 
-    use std::collections::BTreeMap;
-    let mut grouped: BTreeMap<&str, Vec<String>> = BTreeMap::new();
-    for (kind, name) in items {
-        grouped.entry(kind).or_default().push(name);
+/// First and last source line covered by `node`.
+pub(crate) fn line_range<T: ToTokens>(node: &T) -> (usize, usize) {
+    let mut range: Option<(usize, usize)> = None;
+    for token in node.to_token_stream() {
+        let span = token.span();
+        let start = range.map_or(span.start().line, |(start, _)| start);
+        range = Some((start, span.end().line));
     }
+    range.unwrap_or((0, 0))
+}
+
+/// Free the source text that `proc-macro2` keeps per thread for span lookups.
+///
+/// Must only be called once every syntax tree parsed on this thread is dropped;
+/// otherwise a long-running server would keep every parsed file in memory.
+pub(crate) fn release_spans() {
+    proc_macro2::extra::invalidate_current_thread_spans();
+}
 
+/// Join the `///` or `//!` doc comment lines in `attrs` into a single string.
+pub(crate) fn doc_comment(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+        .collect();
+    lines.join("\n")
+}
+
+/// Section headings of an item listing, in the order they are shown.
+const ITEM_SECTIONS: &[(&str, &str)] = &[
+    ("mod", "Modules"),
+    ("struct", "Structs"),
+    ("enum", "Enums"),
+    ("union", "Unions"),
+    ("trait", "Traits"),
+    ("type", "Type aliases"),
+    ("fn", "Functions"),
+    ("method", "Methods"),
+    ("const", "Constants"),
+    ("static", "Statics"),
+    ("macro", "Macros"),
+];
+
+/// Render an item listing as markdown with a section per kind. Built from the same
+/// records as the structured output, so both honour the listing's filters alike.
+pub fn render_item_list(list: &ItemList) -> String {
     let mut output = String::new();
-    for (kind, names) in grouped {
-        output.push_str(&format!("## {}\n", kind));
-        for name in names {
-            output.push_str(&format!("- {}\n", name));
+    for (kind, heading) in ITEM_SECTIONS {
+        let mut items = list.items.iter().filter(|item| item.kind == *kind).peekable();
+        if items.peek().is_none() {
+            continue;
+        }
+        output.push_str(&format!("## {}\n", heading));
+        for item in items {
+            let deprecated = if item.deprecated.is_some() { " (deprecated)" } else { "" };
+            output.push_str(&format!("- `{}`{}\n", item.path, deprecated));
         }
         output.push('\n');
     }
+    if output.is_empty() {
+        output = format!("No items found in {} {}.\n", list.crate_name, list.version);
+    }
+    output
+}
+
+/// Download a crate and describe its items for structured output.
+pub async fn list_crate_item_records(
+    crate_name: &str,
+    version: &str,
+    filters: Option<&ItemListFilters>,
+) -> Result<ItemList> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    Ok(ItemList {
+        crate_name: crate_name.to_string(),
+        version: version.to_string(),
+        items: collect_item_records(Path::new(&crate_path), crate_name, filters),
+    })
+}
+
+/// Describe every item of an unpacked crate: kind, path, docs, deprecation and feature
+/// gates from attributes, and signatures for items in the public API.
+///
+/// Public items are listed at the paths users reach them by, including `pub use`
+/// re-exports; everything else at the path it is declared at.
+///
+/// `filters` selects by kind, by `public`/`private` visibility and by module path prefix.
+pub fn collect_item_records(crate_dir: &Path, crate_name: &str, filters: Option<&ItemListFilters>) -> Vec<ItemRecord> {
+    let api = collect_public_api(crate_dir);
+    let signatures: HashMap<(&str, &str), &str> = api
+        .iter()
+        .map(|((path, kind), item)| ((path.as_str(), *kind), item.signature.as_str()))
+        .collect();
+    let module = filters.and_then(|filters| filters.module.as_deref()).map(|module| match module.strip_prefix(crate_name) {
+        Some("") => "",
+        Some(rest) => rest.strip_prefix("::").unwrap_or(module),
+        None => module,
+    });
+    let wanted = |kind: &str, public: bool, path: &str| {
+        let Some(filters) = filters else { return true };
+        filters.item_type.as_deref().is_none_or(|ty| ty == kind)
+            && filters.visibility.as_deref().is_none_or(|vis| matches!(vis, "pub" | "public") == public)
+            && module.is_none_or(|module| module.is_empty() || path.starts_with(&format!("{}::", module)))
+    };
+
+    let mut records = Vec::new();
+    let mut record = |item: &VisitedItem, public: bool| {
+        if !wanted(item.kind, public, &item.path) {
+            return;
+        }
+        let docs = doc_comment(item.attrs);
+        records.push(ItemRecord {
+            kind: item.kind.to_string(),
+            path: format!("{}::{}", crate_name, item.path),
+            signature: public
+                .then(|| signatures.get(&(item.path.as_str(), item.kind)).map(|s| s.to_string()))
+                .flatten(),
+            docs: (!docs.is_empty()).then_some(docs),
+            since: attr_since(item.attrs),
+            deprecated: deprecation(item.attrs),
+            feature_gates: feature_gates(item.attrs),
+        });
+    };
+    let modules = parse_crate_modules(crate_dir);
+    let exports = module_exports(&modules);
+    let type_paths = public_type_paths(&modules, &exports);
+    for (module, module_exports) in modules.iter().zip(&exports) {
+        // Items are told apart by where they sit in the file, so the declared path of an
+        // exported item is not listed again as private
+        let key = |item: &VisitedItem| (item.lines, item.kind, item.path.rsplit("::").next().unwrap_or_default().to_string());
+        let mut reached = HashSet::new();
+        for export in module_exports {
+            visit_items(&module.ast.items, &export.path, &mut |item| {
+                let name = item.path.split("::").nth(export.path.len()).unwrap_or_default();
+                if item.public && export.includes(name) {
+                    reached.insert(key(&item));
+                    record(&item, true);
+                }
+            });
+        }
+        for i in hidden_impls(std::slice::from_ref(module), std::slice::from_ref(module_exports)) {
+            if let Some(type_path) = impl_type_name(i).and_then(|name| type_paths.get(&name)) {
+                visit_impl(i, type_path, &mut |item| {
+                    if item.public && reached.insert(key(&item)) {
+                        record(&item, true);
+                    }
+                });
+            }
+        }
+        visit_items(&module.ast.items, &module.module_path, &mut |item| {
+            if !reached.contains(&key(&item)) {
+                record(&item, false);
+            }
+        });
+    }
+    drop(modules);
+    release_spans();
+    records.sort_by(|a, b| a.path.cmp(&b.path));
+    records
+}
+
+fn string_arg_re(name: &str) -> Regex {
+    Regex::new(&format!(r#"\b{}\s*=\s*"((?:[^"\\]|\\.)*)""#, name)).unwrap()
+}
+
+/// Features named in `#[cfg(feature = "...")]` and `#[doc(cfg(feature = "..."))]`, also via `cfg_attr`.
+fn feature_gates(attrs: &[Attribute]) -> Vec<String> {
+    static FEATURE_RE: OnceLock<Regex> = OnceLock::new();
+    let feature_re = FEATURE_RE.get_or_init(|| string_arg_re("feature"));
+    let mut features = Vec::new();
+    for attr in attrs {
+        if !["cfg", "cfg_attr", "doc"].iter().any(|name| attr.path().is_ident(name)) {
+            continue;
+        }
+        let tokens = attr.meta.to_token_stream().to_string();
+        for caps in feature_re.captures_iter(&tokens) {
+            if !features.contains(&caps[1].to_string()) {
+                features.push(caps[1].to_string());
+            }
+        }
+    }
+    features
+}
+
+/// `#[deprecated]`, `#[deprecated = "note"]` or `#[deprecated(since = "...", note = "...")]`.
+fn deprecation(attrs: &[Attribute]) -> Option<Deprecation> {
+    static SINCE_RE: OnceLock<Regex> = OnceLock::new();
+    static NOTE_RE: OnceLock<Regex> = OnceLock::new();
+    let attr = attrs.iter().find(|attr| attr.path().is_ident("deprecated"))?;
+    let tokens = attr.meta.to_token_stream().to_string();
+    let arg = |re: &Regex| re.captures(&tokens).map(|caps| caps[1].to_string());
+    let note = match &attr.meta {
+        syn::Meta::NameValue(nv) => match &nv.value {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value()),
+            _ => None,
+        },
+        _ => arg(NOTE_RE.get_or_init(|| string_arg_re("note"))),
+    };
+    Some(Deprecation {
+        since: arg(SINCE_RE.get_or_init(|| string_arg_re("since"))),
+        note,
+    })
+}
 
-    Ok(output)
+/// `since` of a `#[stable(...)]` attribute, as used by the standard library.
+fn attr_since(attrs: &[Attribute]) -> Option<String> {
+    static SINCE_RE: OnceLock<Regex> = OnceLock::new();
+    let since_re = SINCE_RE.get_or_init(|| string_arg_re("since"));
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("stable"))
+        .find_map(|attr| since_re.captures(&attr.meta.to_token_stream().to_string()).map(|caps| caps[1].to_string()))
 }
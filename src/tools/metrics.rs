@@ -0,0 +1,137 @@
+use reqwest::Url;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the tool latency histogram buckets.
+pub const LATENCY_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Debug, Default, Clone)]
+struct ToolStats {
+    ok: u64,
+    errors: u64,
+    bytes: u64,
+    /// Calls per latency bucket; the last slot counts calls slower than every bound.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    seconds: f64,
+}
+
+/// Counters for the Prometheus `/metrics` endpoint, shared by the whole process.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Responses from upstream hosts by host and status; `error` when no response arrived.
+    upstream: Mutex<BTreeMap<(String, String), u64>>,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The process-wide metrics.
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    /// Record a finished tool call and the bytes of text it returned.
+    pub fn record_tool_call(&self, tool: &str, elapsed: Duration, ok: bool, bytes: usize) {
+        let mut tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = tools.entry(tool.to_string()).or_default();
+        if ok {
+            stats.ok += 1;
+        } else {
+            stats.errors += 1;
+        }
+        stats.bytes += bytes as u64;
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound).unwrap_or(LATENCY_BUCKETS.len());
+        stats.buckets[bucket] += 1;
+        stats.seconds += seconds;
+    }
+
+    pub fn record_cache(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the status of an upstream request to `url`, or `None` if it failed without one.
+    pub fn record_upstream(&self, url: &str, status: Option<u16>) {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| "unknown".to_string());
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+        let mut upstream = self.upstream.lock().unwrap_or_else(|e| e.into_inner());
+        *upstream.entry((host, status)).or_default() += 1;
+    }
+
+    /// Cache hits over lookups, or 0 before the first lookup.
+    pub fn cache_hit_ratio(&self) -> f64 {
+        let hits = self.cache_hits.load(Ordering::Relaxed);
+        let lookups = hits + self.cache_misses.load(Ordering::Relaxed);
+        if lookups == 0 {
+            0.0
+        } else {
+            hits as f64 / lookups as f64
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let tools = self.tools.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        header(&mut out, "cratedocs_tool_calls_total", "counter", "Tool calls by tool and outcome.");
+        for (tool, stats) in &tools {
+            let _ = writeln!(out, "cratedocs_tool_calls_total{{tool=\"{}\",outcome=\"ok\"}} {}", tool, stats.ok);
+            let _ = writeln!(out, "cratedocs_tool_calls_total{{tool=\"{}\",outcome=\"error\"}} {}", tool, stats.errors);
+        }
+
+        header(&mut out, "cratedocs_tool_duration_seconds", "histogram", "Tool call latency.");
+        for (tool, stats) in &tools {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&stats.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "cratedocs_tool_duration_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}", tool, bound, cumulative);
+            }
+            let count = stats.ok + stats.errors;
+            let _ = writeln!(out, "cratedocs_tool_duration_seconds_bucket{{tool=\"{}\",le=\"+Inf\"}} {}", tool, count);
+            let _ = writeln!(out, "cratedocs_tool_duration_seconds_sum{{tool=\"{}\"}} {}", tool, stats.seconds);
+            let _ = writeln!(out, "cratedocs_tool_duration_seconds_count{{tool=\"{}\"}} {}", tool, count);
+        }
+
+        header(&mut out, "cratedocs_tool_response_bytes_total", "counter", "Bytes of text returned by tools.");
+        for (tool, stats) in &tools {
+            let _ = writeln!(out, "cratedocs_tool_response_bytes_total{{tool=\"{}\"}} {}", tool, stats.bytes);
+        }
+
+        header(&mut out, "cratedocs_cache_hits_total", "counter", "Documentation cache hits.");
+        let _ = writeln!(out, "cratedocs_cache_hits_total {}", self.cache_hits.load(Ordering::Relaxed));
+        header(&mut out, "cratedocs_cache_misses_total", "counter", "Documentation cache misses.");
+        let _ = writeln!(out, "cratedocs_cache_misses_total {}", self.cache_misses.load(Ordering::Relaxed));
+        header(&mut out, "cratedocs_cache_hit_ratio", "gauge", "Documentation cache hits over lookups.");
+        let _ = writeln!(out, "cratedocs_cache_hit_ratio {}", self.cache_hit_ratio());
+
+        header(&mut out, "cratedocs_upstream_responses_total", "counter", "Upstream HTTP responses by host and status.");
+        let upstream = self.upstream.lock().unwrap_or_else(|e| e.into_inner());
+        for ((host, status), count) in upstream.iter() {
+            let _ = writeln!(out, "cratedocs_upstream_responses_total{{host=\"{}\",status=\"{}\"}} {}", host, status, count);
+        }
+        out
+    }
+}
+
+/// Pass an upstream response through, recording its status.
+pub fn observed(url: &str, response: reqwest::Result<reqwest::Response>) -> reqwest::Result<reqwest::Response> {
+    metrics().record_upstream(url, response.as_ref().ok().map(|r| r.status().as_u16()));
+    response
+}
+
+/// The `# HELP` and `# TYPE` lines of a metric.
+pub fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
@@ -1,17 +1,26 @@
+pub mod api_diff;
+pub mod crate_search;
 pub mod docs;
+pub mod examples;
+pub mod format;
+pub mod impls;
 pub mod item_list;
+pub mod metrics;
+pub mod outline;
+pub mod paginate;
+pub mod pipeline;
+pub mod prompts;
+pub mod rustdoc;
+pub mod signature_search;
+pub mod structured;
 pub mod tldr;
+pub mod tokens;
+pub mod view_source;
 
-pub use docs::DocRouter;
+pub use docs::{DocRouter, DocService};
 pub use docs::docs::DocCache;
+pub use tokens::{count_tokens, estimate_tokens};
 
+#[cfg(test)]
+mod tests;
 
-// Function to count tokens in a given text using a pretrained model from Hugging Face Hub
-use tokenizers::tokenizer::Tokenizer;
-
-pub fn count_tokens(text: &str) -> Result<usize, tokenizers::Error> {
-    // 🦨 skunky: This loads the tokenizer from Hugging Face Hub every call; cache for production.
-    let tokenizer = Tokenizer::from_pretrained("bert-base-cased", None)?;
-    let encoding = tokenizer.encode(text, true)?;
-    Ok(encoding.get_ids().len())
-}
\ No newline at end of file
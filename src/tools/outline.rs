@@ -0,0 +1,99 @@
+use crate::tools::estimate_tokens;
+
+/// A markdown section: a heading and everything up to the next heading of the same or higher level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Heading level, 1 for `#` through 6 for `######`.
+    pub level: usize,
+    pub title: String,
+    /// Byte range of the section in the markdown, heading and subsections included.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The sections of `markdown` in document order. Headings inside code fences are ignored.
+pub fn outline(markdown: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut offset = 0;
+    let mut in_code = false;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+        if !in_code && (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            for open in sections.iter_mut().filter(|s| s.end == usize::MAX && s.level >= level) {
+                open.end = offset;
+            }
+            sections.push(Section {
+                level,
+                title: trimmed[level..].trim().to_string(),
+                start: offset,
+                end: usize::MAX,
+            });
+        }
+        offset += line.len();
+    }
+    for open in sections.iter_mut().filter(|s| s.end == usize::MAX) {
+        open.end = markdown.len();
+    }
+    sections
+}
+
+/// Render the heading tree with the approximate token size of each section.
+pub fn render_outline(markdown: &str, sections: &[Section]) -> String {
+    let min_level = sections.iter().map(|s| s.level).min().unwrap_or(1);
+    let mut output = String::new();
+    for section in sections {
+        output.push_str(&format!(
+            "{}- {} (~{} tokens)\n",
+            "  ".repeat(section.level - min_level),
+            section.title,
+            estimate_tokens(&markdown[section.start..section.end])
+        ));
+    }
+    output
+}
+
+/// Return only the sections whose titles match `names`, case-insensitively.
+///
+/// A name matches a heading equal to it or starting with it, so "Examples" also
+/// finds "Examples (async)". Blank names are ignored, and giving only blank names is
+/// an error. Nested matches are only included once. On failure the error lists the
+/// available headings.
+pub fn select_sections(markdown: &str, names: &[String]) -> Result<String, String> {
+    let sections = outline(markdown);
+    let wanted: Vec<String> = names
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if wanted.is_empty() {
+        return Err("sections must name at least one heading".to_string());
+    }
+    let mut selected: Vec<&Section> = Vec::new();
+    for section in &sections {
+        let title = section.title.to_lowercase();
+        let matches = wanted.iter().any(|name| title == *name || title.starts_with(name.as_str()));
+        let covered = selected.iter().any(|s| s.start <= section.start && section.end <= s.end);
+        if matches && !covered {
+            selected.push(section);
+        }
+    }
+
+    if selected.is_empty() {
+        let mut available: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        available.dedup();
+        return Err(format!(
+            "no section matching {} found; available sections: {}",
+            names.join(", "),
+            available.join(", ")
+        ));
+    }
+    Ok(selected
+        .iter()
+        .map(|section| markdown[section.start..section.end].trim_end())
+        .collect::<Vec<_>>()
+        .join("\n\n"))
+}
@@ -0,0 +1,163 @@
+use crate::tools::tokens::truncate_to_tokens;
+
+/// Page size used when a page is requested but no `max_tokens` is configured.
+pub const DEFAULT_PAGE_TOKENS: usize = 4000;
+
+const CURSOR_PREFIX: &str = "page-";
+
+/// Split markdown into pages of at most `page_tokens` tokens.
+///
+/// Pages break at headings where possible, then at paragraphs, then at lines.
+/// A code block split across pages is closed and reopened so every page stays
+/// valid markdown. `count` estimates the tokens in a piece of text.
+pub fn split_pages(text: &str, page_tokens: usize, count: &dyn Fn(&str) -> usize) -> Vec<String> {
+    let page_tokens = page_tokens.max(1);
+    let mut pieces = Vec::new();
+    for section in split_blocks(text, is_heading) {
+        if count(&section) <= page_tokens {
+            pieces.push(section);
+            continue;
+        }
+        for paragraph in split_blocks(&section, |line| line.trim().is_empty()) {
+            if count(&paragraph) <= page_tokens {
+                pieces.push(paragraph);
+            } else {
+                pieces.extend(split_lines(&paragraph, page_tokens, count));
+            }
+        }
+    }
+
+    let mut pages = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    for piece in pieces {
+        let tokens = count(&piece);
+        if !current.is_empty() && current_tokens + tokens > page_tokens {
+            pages.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        current.push_str(&piece);
+        current_tokens += tokens;
+    }
+    if !current.trim().is_empty() || pages.is_empty() {
+        pages.push(current);
+    }
+    pages
+}
+
+/// Return page `number` (1-based) followed by a footer telling the caller how to continue.
+pub fn render_page(pages: &[String], number: usize) -> String {
+    let mut text = pages[number - 1].trim_end().to_string();
+    if number < pages.len() {
+        text.push_str(&format!(
+            "\n\n---\nPage {} of {}. To read on, call this tool again with the same arguments and \"cursor\": \"{}\".\n",
+            number,
+            pages.len(),
+            cursor_for(number + 1)
+        ));
+    } else if pages.len() > 1 {
+        text.push_str(&format!("\n\n---\nPage {} of {} (last page).\n", number, pages.len()));
+    }
+    text
+}
+
+/// The continuation cursor pointing at page `number`.
+pub fn cursor_for(number: usize) -> String {
+    format!("{}{}", CURSOR_PREFIX, number)
+}
+
+/// The page a cursor points at, if it is well formed.
+pub fn page_from_cursor(cursor: &str) -> Option<usize> {
+    cursor
+        .strip_prefix(CURSOR_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|page| *page > 0)
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+/// Split into blocks that start at lines matching `starts_block`, ignoring lines in code fences.
+fn split_blocks(text: &str, starts_block: fn(&str) -> bool) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+    for line in text.split_inclusive('\n') {
+        if !in_code && starts_block(line.trim_end_matches('\n')) && !current.trim().is_empty() {
+            blocks.push(std::mem::take(&mut current));
+        }
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+/// Split an oversized paragraph line by line, closing and reopening code fences at the cut.
+fn split_lines(text: &str, page_tokens: usize, count: &dyn Fn(&str) -> usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    let mut fence: Option<String> = None;
+    for line in text.split_inclusive('\n') {
+        let tokens = count(line);
+        let closing = if fence.is_some() { "```\n" } else { "" };
+        if !current.is_empty() && current_tokens + tokens + count(closing) > page_tokens {
+            current.push_str(closing);
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+            if let Some(opening) = &fence {
+                current.push_str(opening);
+                current_tokens = count(opening);
+            }
+        }
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            fence = match fence {
+                Some(_) => None,
+                None => Some(format!("{}\n", trimmed.trim_end())),
+            };
+        }
+        if tokens > page_tokens {
+            // A single line longer than a page: cut it into page-sized pieces, each
+            // wrapped in the code fence it is in.
+            let (opening, closing) = match &fence {
+                Some(opening) if !trimmed.starts_with("```") => (opening.as_str(), "```\n"),
+                _ => ("", ""),
+            };
+            // Flush the lines before it, unless all there is is the reopened fence
+            if !current.is_empty() && current != opening {
+                current.push_str(closing);
+                chunks.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            let budget = page_tokens.saturating_sub(count(opening) + count(closing)).max(1);
+            let mut rest = line;
+            while !rest.is_empty() {
+                let mut piece = truncate_to_tokens(rest, budget, count);
+                if piece.is_empty() {
+                    piece = &rest[..rest.chars().next().map_or(rest.len(), char::len_utf8)];
+                }
+                rest = &rest[piece.len()..];
+                let newline = if closing.is_empty() || piece.ends_with('\n') { "" } else { "\n" };
+                chunks.push(format!("{}{}{}{}", opening, piece, newline, closing));
+            }
+            current.push_str(opening);
+            current_tokens = count(opening);
+            continue;
+        }
+        current.push_str(line);
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
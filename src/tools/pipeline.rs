@@ -0,0 +1,316 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Filter names accepted by [`Pipeline::parse`], besides `strip_sections:<regex>`.
+pub const FILTER_NAMES: [&str; 7] = [
+    "drop_badges",
+    "drop_images",
+    "collapse_trait_impls",
+    "remove_auto_trait_impls",
+    "remove_blanket_impls",
+    "dedupe_links",
+    "strip_html",
+];
+
+/// Preset names accepted by [`Pipeline::parse`].
+pub const PRESET_NAMES: [&str; 3] = ["tldr", "compact", "none"];
+
+/// Sections dropped by the `tldr` preset.
+const TLDR_SECTIONS: &str = r"(?i)^(license|version(s)?)\b";
+
+/// HTML elements removed by `strip_html`. Anything else in angle brackets (`Vec<T>`) is kept.
+const HTML_TAGS: &str = "a|b|br|center|code|del|details?|div|em|h[1-6]|hr|i|img|kbd|li|ol|p|picture|pre|section|source|span|strong|sub|summary|sup|table|tbody|td|th|thead|tr|u|ul";
+
+/// One post-processing step over a tool's markdown output.
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// Drop sections whose heading title matches, with their subsections.
+    StripSections(Regex),
+    /// Drop CI, coverage and version badges.
+    DropBadges,
+    /// Drop every image.
+    DropImages,
+    /// Reduce trait, auto trait and blanket implementation sections to one line per impl.
+    CollapseTraitImpls,
+    /// Drop rustdoc's "Auto Trait Implementations" section.
+    RemoveAutoTraitImpls,
+    /// Drop rustdoc's "Blanket Implementations" section.
+    RemoveBlanketImpls,
+    /// Keep only the first link to each URL; later ones become plain text.
+    DedupeLinks,
+    /// Remove HTML tags and comments left over from READMEs.
+    StripHtml,
+}
+
+impl Filter {
+    /// Parse a filter name, or `strip_sections:<regex>`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if let Some(pattern) = name.strip_prefix("strip_sections:") {
+            let regex = Regex::new(pattern).map_err(|e| format!("invalid strip_sections pattern: {}", e))?;
+            return Ok(Self::StripSections(regex));
+        }
+        Ok(match name {
+            "drop_badges" => Self::DropBadges,
+            "drop_images" => Self::DropImages,
+            "collapse_trait_impls" => Self::CollapseTraitImpls,
+            "remove_auto_trait_impls" => Self::RemoveAutoTraitImpls,
+            "remove_blanket_impls" => Self::RemoveBlanketImpls,
+            "dedupe_links" => Self::DedupeLinks,
+            "strip_html" => Self::StripHtml,
+            _ => {
+                return Err(format!(
+                    "unknown filter '{}'; expected one of {}, {} or strip_sections:<regex>",
+                    name,
+                    PRESET_NAMES.join(", "),
+                    FILTER_NAMES.join(", ")
+                ))
+            }
+        })
+    }
+
+    pub fn apply(&self, markdown: &str) -> String {
+        match self {
+            Self::StripSections(regex) => strip_sections(markdown, |title| regex.is_match(title)),
+            Self::DropBadges => drop_images(markdown, is_badge),
+            Self::DropImages => drop_images(markdown, |_| true),
+            Self::CollapseTraitImpls => collapse_trait_impls(markdown),
+            Self::RemoveAutoTraitImpls => strip_sections(markdown, |title| title == "Auto Trait Implementations"),
+            Self::RemoveBlanketImpls => strip_sections(markdown, |title| title == "Blanket Implementations"),
+            Self::DedupeLinks => dedupe_links(markdown),
+            Self::StripHtml => strip_html(markdown),
+        }
+    }
+}
+
+/// An ordered list of filters applied to tool output.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    filters: Vec<Filter>,
+}
+
+impl Pipeline {
+    /// The empty pipeline, which leaves output unchanged.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// The TL;DR preset: drop LICENSE and VERSION(S) sections and leftover HTML tags.
+    pub fn tldr() -> Self {
+        Self {
+            filters: vec![
+                Filter::StripSections(Regex::new(TLDR_SECTIONS).unwrap()),
+                Filter::StripHtml,
+            ],
+        }
+    }
+
+    /// Everything in `tldr`, plus badges, images, duplicate links and impl boilerplate.
+    pub fn compact() -> Self {
+        let mut pipeline = Self::tldr();
+        pipeline.filters.extend([
+            Filter::RemoveAutoTraitImpls,
+            Filter::RemoveBlanketImpls,
+            Filter::CollapseTraitImpls,
+            Filter::DropBadges,
+            Filter::DropImages,
+            Filter::DedupeLinks,
+        ]);
+        pipeline
+    }
+
+    /// Parse a comma-separated list of preset and filter names, applied in order.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        Self::from_names(spec.split(',').map(str::trim).filter(|name| !name.is_empty()))
+    }
+
+    /// Build a pipeline from preset and filter names, applied in order.
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut filters = Vec::new();
+        for name in names {
+            match name.trim() {
+                "none" => {}
+                "tldr" => filters.extend(Self::tldr().filters),
+                "compact" => filters.extend(Self::compact().filters),
+                name => filters.push(Filter::parse(name)?),
+            }
+        }
+        Ok(Self { filters })
+    }
+
+    /// This pipeline followed by `next`.
+    pub fn then(mut self, next: Pipeline) -> Self {
+        self.filters.extend(next.filters);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    pub fn apply(&self, markdown: &str) -> String {
+        let mut output = markdown.to_string();
+        for filter in &self.filters {
+            output = filter.apply(&output);
+        }
+        output
+    }
+}
+
+/// Heading level and title of a markdown heading line. `#Title` without a space counts too.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim();
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    (1..=6).contains(&level).then(|| (level, trimmed[level..].trim()))
+}
+
+/// Call `f` with each line and whether it lies inside a code fence (fence lines included).
+fn map_lines(markdown: &str, mut f: impl FnMut(&str, bool) -> Option<String>) -> String {
+    let mut output = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let fence = line.trim_start().starts_with("```");
+        if let Some(line) = f(line, in_code || fence) {
+            output.push(line);
+        }
+        if fence {
+            in_code = !in_code;
+        }
+    }
+    let mut joined = output.join("\n");
+    if markdown.ends_with('\n') && !joined.is_empty() {
+        joined.push('\n');
+    }
+    joined
+}
+
+fn strip_sections(markdown: &str, matches: impl Fn(&str) -> bool) -> String {
+    let mut skip_level: Option<usize> = None;
+    map_lines(markdown, |line, in_code| {
+        if !in_code {
+            if let Some((level, title)) = heading(line) {
+                if skip_level.is_some_and(|skip| level <= skip) {
+                    skip_level = None;
+                }
+                if skip_level.is_none() && matches(title) {
+                    skip_level = Some(level);
+                }
+            }
+        }
+        skip_level.is_none().then(|| line.to_string())
+    })
+}
+
+fn collapse_trait_impls(markdown: &str) -> String {
+    let collapsible = |title: &str| {
+        (title.ends_with("Implementations") && title != "Implementations") || title == "Implementors"
+    };
+    let mut section_level: Option<usize> = None;
+    map_lines(markdown, |line, in_code| {
+        if !in_code {
+            if let Some((level, title)) = heading(line) {
+                match section_level {
+                    Some(section) if level > section => {
+                        // Keep the impl headers as a list; drop method headings.
+                        return (level == section + 1).then(|| format!("- `{}`", title));
+                    }
+                    _ => section_level = collapsible(title).then_some(level),
+                }
+                return Some(line.to_string());
+            }
+        }
+        section_level.is_none().then(|| line.to_string())
+    })
+}
+
+fn image_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // A linked image `[![alt](src)](href)`, a bare image `![alt](src)` or an `<img>` tag.
+    RE.get_or_init(|| {
+        Regex::new(r#"\[!\[[^\]]*\]\(([^)\s]*)[^)]*\)\]\([^)]*\)|!\[[^\]]*\]\(([^)\s]*)[^)]*\)|<img\b[^>]*?src="([^"]*)"[^>]*>"#).unwrap()
+    })
+}
+
+fn is_badge(src: &str) -> bool {
+    let src = src.to_lowercase();
+    ["shields.io", "badge", "travis-ci", "codecov.io", "coveralls.io", "deps.rs", "/workflows/"]
+        .iter()
+        .any(|marker| src.contains(marker))
+}
+
+/// Remove images whose source passes `drop`, and lines left empty by the removal.
+fn drop_images(markdown: &str, drop: impl Fn(&str) -> bool) -> String {
+    map_lines(markdown, |line, in_code| {
+        if in_code {
+            return Some(line.to_string());
+        }
+        let cleaned = image_re().replace_all(line, |caps: &regex::Captures| {
+            let src = (1..=3).find_map(|i| caps.get(i)).map_or("", |m| m.as_str());
+            if drop(src) {
+                String::new()
+            } else {
+                caps[0].to_string()
+            }
+        });
+        if cleaned.trim().is_empty() && !line.trim().is_empty() {
+            None
+        } else {
+            Some(cleaned.trim_end().to_string())
+        }
+    })
+}
+
+fn dedupe_links(markdown: &str) -> String {
+    static LINK_RE: OnceLock<Regex> = OnceLock::new();
+    let link_re = LINK_RE.get_or_init(|| Regex::new(r"(^|[^!\]])\[([^\]]+)\]\(([^)\s]+)[^)]*\)").unwrap());
+    let mut seen = HashSet::new();
+    map_lines(markdown, |line, in_code| {
+        if in_code {
+            return Some(line.to_string());
+        }
+        let deduped = link_re.replace_all(line, |caps: &regex::Captures| {
+            if seen.insert(caps[3].to_string()) {
+                caps[0].to_string()
+            } else {
+                format!("{}{}", &caps[1], &caps[2])
+            }
+        });
+        Some(deduped.into_owned())
+    })
+}
+
+/// Remove known HTML tags and comments outside code blocks and inline code.
+fn strip_html(markdown: &str) -> String {
+    static TAG_RE: OnceLock<Regex> = OnceLock::new();
+    let tag_re = TAG_RE.get_or_init(|| {
+        Regex::new(&format!(r"(?i)<!--.*?-->|</?(?:{})\b[^<>]*>", HTML_TAGS)).unwrap()
+    });
+    map_lines(markdown, |line, in_code| {
+        if in_code {
+            return Some(line.to_string());
+        }
+        // Odd segments between backticks are inline code.
+        let cleaned: Vec<String> = line
+            .split('`')
+            .enumerate()
+            .map(|(i, segment)| {
+                if i % 2 == 0 {
+                    tag_re.replace_all(segment, "").into_owned()
+                } else {
+                    segment.to_string()
+                }
+            })
+            .collect();
+        let cleaned = cleaned.join("`");
+        if cleaned.trim().is_empty() && !line.trim().is_empty() {
+            None
+        } else {
+            Some(cleaned)
+        }
+    })
+}
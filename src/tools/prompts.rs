@@ -0,0 +1,192 @@
+use mcp_core::handler::PromptError;
+use mcp_core::prompt::{Prompt, PromptArgument};
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// An argument of a built-in prompt. Optional arguments fall back to `default` when omitted.
+pub struct PromptArg {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: Option<&'static str>,
+}
+
+/// A built-in prompt: `template` mentions each argument as `{name}`.
+pub struct PromptTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arguments: &'static [PromptArg],
+    pub template: &'static str,
+}
+
+const VERSION: PromptArg = PromptArg {
+    name: "version",
+    description: "Crate version to use (default: the latest release)",
+    default: Some("the latest release"),
+};
+
+pub const PROMPTS: [PromptTemplate; 4] = [
+    PromptTemplate {
+        name: "explain_crate",
+        description: "Explain what a crate is for, its core types and how to get started with it",
+        arguments: &[
+            PromptArg {
+                name: "crate_name",
+                description: "Name of the crate, e.g. tokio",
+                default: None,
+            },
+            VERSION,
+            PromptArg {
+                name: "focus",
+                description: "Aspect to concentrate on, e.g. error handling",
+                default: Some("its purpose, core concepts and typical usage"),
+            },
+        ],
+        template: "Explain the Rust crate `{crate_name}` ({version}), focusing on {focus}.
+
+1. Call `lookup_crate` with `crate_name` \"{crate_name}\" and `view` \"all\" to read its documentation, README and feature flags.
+2. Call `list_crate_items` to see its modules and public types. It needs an exact version number: take it from the metadata if none was given.
+3. Call `lookup_item` on the two or three types or functions a new user meets first.
+4. Call `find_examples` with the same version for a short, real usage example.
+
+Then write the explanation: what the crate does and when to use it, the key types and how they fit together, the feature flags worth knowing, and a minimal example. Cite item paths such as `{crate_name}::module::Type`.",
+    },
+    PromptTemplate {
+        name: "how_to",
+        description: "Find out how to accomplish a task with a crate, with working code",
+        arguments: &[
+            PromptArg {
+                name: "crate_name",
+                description: "Name of the crate, e.g. serde_json",
+                default: None,
+            },
+            PromptArg {
+                name: "task",
+                description: "What you want to do, e.g. parse JSON into a struct with optional fields",
+                default: None,
+            },
+            VERSION,
+        ],
+        template: "How do I {task} with the Rust crate `{crate_name}` ({version})?
+
+The tools below need an exact version number: if none was given, take the newest from `search_crates`.
+
+1. Call `search_in_crate` with `crate_name` \"{crate_name}\" and a query describing the task to find the relevant items.
+2. If you know the types involved, call `search_by_signature` to find functions that turn one into the other.
+3. Call `lookup_item` on the most promising items, and `find_examples` for how they are used in practice.
+4. Use `view_source` only if the documentation leaves the behavior unclear.
+
+Answer with a complete, compiling example using only APIs you have seen in the documentation, the `Cargo.toml` dependency line including any required feature flags, and a short explanation of each step.",
+    },
+    PromptTemplate {
+        name: "migrate_version",
+        description: "Plan an upgrade of a crate between two versions",
+        arguments: &[
+            PromptArg {
+                name: "crate_name",
+                description: "Name of the crate, e.g. axum",
+                default: None,
+            },
+            PromptArg {
+                name: "old_version",
+                description: "Version currently in use, e.g. 0.6.20",
+                default: None,
+            },
+            PromptArg {
+                name: "new_version",
+                description: "Version to migrate to, e.g. 0.7.5",
+                default: None,
+            },
+        ],
+        template: "Help me migrate from `{crate_name}` {old_version} to {new_version}.
+
+1. Call `diff_crate_api` with `crate_name` \"{crate_name}\", `old_version` \"{old_version}\" and `new_version` \"{new_version}\" to list removed, added and changed items.
+2. Call `lookup_crate` with `version` \"{new_version}\" and `view` \"readme\", and look for a changelog or upgrade guide.
+3. For each breaking change, call `lookup_item` with `version` \"{new_version}\" on the replacement API.
+
+Write a migration guide: the breaking changes ordered by how likely they are to affect a typical user, each with a before and after snippet, then notable new features and deprecations.",
+    },
+    PromptTemplate {
+        name: "compare_crates",
+        description: "Compare two crates that solve the same problem",
+        arguments: &[
+            PromptArg {
+                name: "crate_a",
+                description: "First crate, e.g. reqwest",
+                default: None,
+            },
+            PromptArg {
+                name: "crate_b",
+                description: "Second crate, e.g. ureq",
+                default: None,
+            },
+            PromptArg {
+                name: "use_case",
+                description: "What the crate is needed for, e.g. a blocking CLI HTTP client",
+                default: Some("general use"),
+            },
+        ],
+        template: "Compare the Rust crates `{crate_a}` and `{crate_b}` for {use_case}.
+
+1. Call `lookup_crate` with `view` \"all\" for each crate to read its documentation, README and feature flags.
+2. Call `search_crates` with each name for download counts and the latest versions.
+3. Call `list_crate_items` with those versions to compare the size and shape of their APIs.
+4. Call `find_examples` with those versions to compare how the same task looks in both.
+
+Summarize the differences in a table (API style, async or blocking, dependencies and feature flags, maturity and popularity), show the same small task in both, and recommend one for {use_case}, with the cases where the other is the better choice.",
+    },
+];
+
+/// The built-in prompts, as listed by `prompts/list`.
+pub fn list_prompts() -> Vec<Prompt> {
+    PROMPTS
+        .iter()
+        .map(|prompt| {
+            let arguments = prompt
+                .arguments
+                .iter()
+                .map(|arg| PromptArgument {
+                    name: arg.name.to_string(),
+                    description: Some(arg.description.to_string()),
+                    required: Some(arg.default.is_none()),
+                })
+                .collect();
+            Prompt::new(prompt.name, Some(prompt.description), Some(arguments))
+        })
+        .collect()
+}
+
+pub fn find_prompt(name: &str) -> Option<&'static PromptTemplate> {
+    PROMPTS.iter().find(|prompt| prompt.name == name)
+}
+
+/// Fill in a prompt's template from `prompts/get` arguments (a JSON object of strings).
+pub fn render_prompt(name: &str, arguments: Option<&Map<String, Value>>) -> Result<String, PromptError> {
+    let prompt = find_prompt(name).ok_or_else(|| PromptError::NotFound(format!("Prompt {} not found", name)))?;
+    let empty = Map::new();
+    let arguments = arguments.unwrap_or(&empty);
+
+    if let Some(unknown) = arguments.keys().find(|key| !prompt.arguments.iter().any(|arg| arg.name == key.as_str())) {
+        return Err(PromptError::InvalidParameters(format!("Unknown argument {} for prompt {}", unknown, name)));
+    }
+
+    let mut values = HashMap::new();
+    for arg in prompt.arguments {
+        let value = match arguments.get(arg.name).and_then(|v| v.as_str()).map(str::trim) {
+            Some(value) if !value.is_empty() => value,
+            _ => arg
+                .default
+                .ok_or_else(|| PromptError::InvalidParameters(format!("{} is required", arg.name)))?,
+        };
+        values.insert(arg.name, value);
+    }
+    // One pass, so placeholders inside argument values are left as written
+    static PLACEHOLDER_RE: OnceLock<Regex> = OnceLock::new();
+    let placeholder_re = PLACEHOLDER_RE.get_or_init(|| Regex::new(r"\{(\w+)\}").unwrap());
+    let text = placeholder_re.replace_all(prompt.template, |caps: &Captures| match values.get(&caps[1]) {
+        Some(value) => value.to_string(),
+        None => caps[0].to_string(),
+    });
+    Ok(text.into_owned())
+}
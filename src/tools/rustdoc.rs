@@ -0,0 +1,308 @@
+use crate::tools::structured::{Deprecation, ItemRecord};
+use scraper::node::Node;
+use scraper::{ElementRef, Html, Selector};
+
+/// Elements that never carry documentation.
+const SKIPPED_TAGS: [&str; 13] = [
+    "script", "style", "noscript", "button", "nav", "header", "footer", "form", "svg", "input",
+    "rustdoc-toolbar", "rustdoc-search", "rustdoc-topbar",
+];
+
+/// rustdoc and docs.rs classes used for navigation, toggles and links back to the source.
+const SKIPPED_CLASSES: [&str; 13] = [
+    "sidebar", "sidebar-elems", "rightside", "out-of-band", "src", "srclink", "anchor", "hideme",
+    "search-form", "notable-traits", "tooltip", "sub-heading", "rustdoc-breadcrumbs",
+];
+
+/// Ids of UI widgets that rustdoc renders inside the page body.
+const SKIPPED_IDS: [&str; 6] = [
+    "settings-menu", "help-button", "toggle-all-docs", "search", "copy-path", "sidebar-button",
+];
+
+/// Convert a docs.rs/rustdoc page to markdown, keeping only the documentation itself.
+///
+/// The item declaration, doc blocks and implementation sections are kept; the
+/// sidebar, navigation, settings and search widgets, anchors and source links
+/// are dropped. Code blocks become fenced blocks tagged with their language.
+/// Pages without rustdoc's `#main-content` (e.g. docs.rs landing pages) use `<main>`
+/// or the whole body instead, with the same chrome removed.
+pub fn rustdoc_to_markdown(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let root = ["#main-content", "main", "body"]
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| document.select(&selector).next());
+    let Some(root) = root else {
+        return String::new();
+    };
+
+    let mut writer = MarkdownWriter::default();
+    writer.children(root);
+    tidy(&writer.out)
+}
+
+/// Extract the structured description of the item documented on a rustdoc page.
+///
+/// `path` is the full item path the page was requested for. The kind comes from the
+/// page heading, the signature from the item declaration, the docs from the top doc
+/// block, and deprecation and feature gates from rustdoc's stability notes.
+pub fn rustdoc_item_record(html: &str, path: &str) -> ItemRecord {
+    let document = Html::parse_document(html);
+    let select = |selector: &str| {
+        Selector::parse(selector)
+            .ok()
+            .and_then(|selector| document.select(&selector).next())
+    };
+    let text_of = |element: ElementRef| collapse_whitespace(&element.text().collect::<String>());
+
+    // The heading reads "Struct Sender", "Function channel", "Type Alias Result", ...
+    let heading = select("#main-content h1").map(text_of).unwrap_or_default();
+    let word = heading.split_whitespace().next().unwrap_or("").to_lowercase();
+    let kind = match word.as_str() {
+        "function" => "fn",
+        "constant" => "const",
+        "module" => "mod",
+        "derive" | "attribute" => "macro",
+        "struct" | "enum" | "union" | "trait" | "macro" | "type" | "static" | "primitive" | "keyword" => &word,
+        _ => "item",
+    }
+    .to_string();
+
+    let signature = select("#main-content .item-decl").map(|decl| {
+        decl.text().collect::<String>().trim().to_string()
+    });
+    let docs = select("#main-content > details.top-doc .docblock")
+        .or_else(|| select("#main-content > .docblock"))
+        .map(|block| {
+            let mut writer = MarkdownWriter::default();
+            writer.children(block);
+            tidy(&writer.out).trim_end().to_string()
+        });
+    let since = select("#main-content .main-heading .since").map(text_of).filter(|s| !s.is_empty());
+    let deprecated = select("#main-content > .item-info .stab.deprecated").map(|stab| parse_deprecation(&text_of(stab)));
+
+    let mut feature_gates = Vec::new();
+    if let Ok(selector) = Selector::parse("#main-content > .item-info .stab.portability code") {
+        for code in document.select(&selector) {
+            let feature = text_of(code);
+            if !feature.is_empty() && !feature_gates.contains(&feature) {
+                feature_gates.push(feature);
+            }
+        }
+    }
+
+    ItemRecord {
+        kind,
+        path: path.to_string(),
+        signature,
+        docs,
+        since,
+        deprecated,
+        feature_gates,
+    }
+}
+
+/// Parse rustdoc's "Deprecated since 1.2.0: use `bar` instead" note.
+fn parse_deprecation(text: &str) -> Deprecation {
+    let text = text.trim_start_matches(|c: char| !c.is_alphanumeric()).trim();
+    let rest = text.strip_prefix("Deprecated").unwrap_or(text).trim();
+    let (head, note) = match rest.split_once(':') {
+        Some((head, note)) => (head.trim(), Some(note.trim().to_string()).filter(|n| !n.is_empty())),
+        None => (rest, None),
+    };
+    Deprecation {
+        since: head.strip_prefix("since").map(|since| since.trim().to_string()),
+        note,
+    }
+}
+
+#[derive(Default)]
+struct MarkdownWriter {
+    out: String,
+    /// Nesting depth of `ul`/`ol`, for list indentation.
+    list_depth: usize,
+    /// Inside an item declaration, where `<pre>` blocks are always Rust.
+    in_declaration: bool,
+}
+
+impl MarkdownWriter {
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let value = element.value();
+        let name = value.name();
+        if SKIPPED_TAGS.contains(&name)
+            || value.classes().any(|class| SKIPPED_CLASSES.contains(&class))
+            || value.id().is_some_and(|id| SKIPPED_IDS.contains(&id))
+        {
+            return;
+        }
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                self.block();
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+                self.children(element);
+                self.block();
+            }
+            "pre" => {
+                let language = value
+                    .classes()
+                    .find_map(|class| match class {
+                        "rust" => Some("rust"),
+                        _ => class.strip_prefix("language-"),
+                    })
+                    .or(self.in_declaration.then_some("rust"))
+                    .unwrap_or("");
+                let code: String = element.text().collect();
+                self.block();
+                self.out.push_str(&format!("```{}\n{}\n```", language, code.trim_end()));
+                self.block();
+            }
+            "code" => {
+                let code: String = element.text().collect();
+                if !code.is_empty() {
+                    self.space_if_needed();
+                    self.out.push_str(&format!("`{}`", code.trim()));
+                }
+            }
+            "strong" | "b" => self.wrapped(element, "**"),
+            "em" | "i" => self.wrapped(element, "*"),
+            "br" => self.out.push('\n'),
+            "ul" | "ol" => {
+                self.line();
+                self.list_depth += 1;
+                self.children(element);
+                self.list_depth -= 1;
+                self.block();
+            }
+            "li" | "dt" => {
+                self.line();
+                self.out.push_str(&"  ".repeat(self.list_depth.saturating_sub(1)));
+                self.out.push_str("- ");
+                self.children(element);
+            }
+            "dd" => {
+                self.out.push_str(": ");
+                self.children(element);
+            }
+            "tr" => {
+                self.line();
+                let cells: Vec<String> = element
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .map(|cell| collapse_whitespace(&cell.text().collect::<String>()))
+                    .collect();
+                self.out.push_str(&format!("| {} |", cells.join(" | ")));
+            }
+            _ => {
+                let is_block = matches!(
+                    name,
+                    "p" | "div" | "section" | "details" | "summary" | "dl" | "table" | "blockquote"
+                );
+                let is_declaration = value.classes().any(|class| class == "item-decl");
+                if is_block {
+                    self.block();
+                }
+                let outer = self.in_declaration;
+                self.in_declaration |= is_declaration;
+                self.children(element);
+                self.in_declaration = outer;
+                if is_block {
+                    self.block();
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let collapsed = collapse_whitespace(text);
+        if collapsed.is_empty() {
+            if text.chars().any(char::is_whitespace) && !self.out.ends_with([' ', '\n']) && !self.out.is_empty() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.space_if_needed();
+        }
+        self.out.push_str(&collapsed);
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn wrapped(&mut self, element: ElementRef, marker: &str) {
+        let text = collapse_whitespace(&element.text().collect::<String>());
+        if !text.is_empty() {
+            self.space_if_needed();
+            self.out.push_str(&format!("{}{}{}", marker, text, marker));
+        }
+    }
+
+    /// Start a new paragraph.
+    fn block(&mut self) {
+        let trimmed = self.out.trim_end_matches([' ', '\n']).len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+    }
+
+    /// Start a new line.
+    fn line(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn space_if_needed(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with([' ', '\n', '(', '[']) {
+            self.out.push(' ');
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Strip trailing spaces and collapse runs of blank lines outside code fences.
+fn tidy(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut blank_lines = 0;
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.starts_with("```") {
+            in_code = !in_code;
+        }
+        let line = if in_code { line } else { line.trim_end() };
+        if line.is_empty() && !in_code {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string() + "\n"
+}
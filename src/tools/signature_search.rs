@@ -0,0 +1,468 @@
+use crate::tools::api_diff::render;
+use crate::tools::item_list::{
+    download_and_cache_crate, hidden_impls, impl_type_name, module_exports, parse_crate_modules, public_type_paths,
+    release_spans,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::path::Path;
+use syn::{FnArg, GenericArgument, ImplItem, Item, PathArguments, ReturnType, TraitItem, Type, TypeParamBound};
+
+/// Default number of results returned by `search_by_signature`.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// Functions scoring below this similarity are not reported.
+const MIN_SCORE: f64 = 0.5;
+
+/// A type reduced to what matters for matching: references, lifetimes and
+/// module paths are dropped and borrowed/owned pairs are unified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeShape {
+    /// `_` in a query: matches anything.
+    Wildcard,
+    /// A type parameter, e.g. `T` or `Self` in a trait.
+    Generic(String),
+    /// A named type with its generic arguments, e.g. `Result<Url, ParseError>`.
+    Named(String, Vec<TypeShape>),
+    Tuple(Vec<TypeShape>),
+}
+
+/// A parsed signature query: argument types and return type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureQuery {
+    pub inputs: Vec<TypeShape>,
+    /// `None` when the query has no `->`, meaning any return type.
+    pub output: Option<TypeShape>,
+}
+
+/// A public function or method with its normalized signature.
+#[derive(Debug, Clone)]
+pub struct FnSignature {
+    pub path: String,
+    /// The signature as written, e.g. `fn parse(input: &str) -> Result<Url, ParseError>`.
+    pub rendered: String,
+    pub inputs: Vec<TypeShape>,
+    pub output: TypeShape,
+}
+
+/// Download a crate and return its functions whose signatures best match `query`.
+pub async fn search_by_signature(
+    crate_name: &str,
+    version: &str,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<String> {
+    let parsed = parse_query(query)?;
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let functions = collect_signatures(Path::new(&crate_path));
+    let matches = rank_signatures(&parsed, &functions, limit.unwrap_or(DEFAULT_LIMIT));
+
+    let mut output = format!("# Functions matching `{}` in {} {}\n\n", query, crate_name, version);
+    if matches.is_empty() {
+        output.push_str("No matching functions found.\n");
+        return Ok(output);
+    }
+    for (rank, (function, score)) in matches.iter().enumerate() {
+        output.push_str(&format!(
+            "{}. `{}` (match {:.0}%)\n   `{}`\n",
+            rank + 1,
+            function.path,
+            score * 100.0,
+            function.rendered
+        ));
+    }
+    Ok(output)
+}
+
+/// Parse `A, B -> C` into argument and return shapes. Single capital letters are generics.
+pub fn parse_query(query: &str) -> Result<SignatureQuery> {
+    let (inputs, output) = match last_top_level_arrow(query) {
+        Some(arrow) => (&query[..arrow], Some(&query[arrow + 2..])),
+        None => (query, None),
+    };
+    let mut inputs = inputs.trim();
+    if inputs.starts_with('(') && inputs.ends_with(')') && split_top_level(&inputs[1..inputs.len() - 1], ",").len() > 1 {
+        inputs = &inputs[1..inputs.len() - 1];
+    }
+
+    let parse = |text: &str| -> Result<TypeShape> {
+        let ty: Type = syn::parse_str(text.trim()).map_err(|e| anyhow!("cannot parse type `{}`: {}", text.trim(), e))?;
+        Ok(normalize(&ty, &QueryGenerics))
+    };
+    let inputs = split_top_level(inputs, ",")
+        .into_iter()
+        .filter(|part| !part.trim().is_empty() && part.trim() != "()")
+        .map(parse)
+        .collect::<Result<Vec<_>>>()?;
+    let output = output.map(parse).transpose()?;
+    if inputs.is_empty() && output.is_none() {
+        return Err(anyhow!("query must contain at least one type"));
+    }
+    Ok(SignatureQuery { inputs, output })
+}
+
+/// Gather the public functions and methods reachable from the crate root, also
+/// through `pub use` re-exports of private modules.
+pub fn collect_signatures(crate_dir: &Path) -> Vec<FnSignature> {
+    let mut functions = Vec::new();
+    let modules = parse_crate_modules(crate_dir);
+    let exports = module_exports(&modules);
+    for (module, module_exports) in modules.iter().zip(&exports) {
+        for export in module_exports {
+            let items = module.ast.items.iter().filter(|item| export.includes_item(item));
+            collect_items(items, &export.path, &mut functions);
+        }
+    }
+    // Methods of public types, wherever their impls are written
+    let type_paths = public_type_paths(&modules, &exports);
+    for block in hidden_impls(&modules, &exports) {
+        if let Some(type_path) = impl_type_name(block).and_then(|name| type_paths.get(&name)) {
+            collect_impl(block, type_path, &mut functions);
+        }
+    }
+    drop(modules);
+    release_spans();
+    functions
+}
+
+/// Score every function against `query` and return the best `limit`, best first.
+pub fn rank_signatures<'a>(
+    query: &SignatureQuery,
+    functions: &'a [FnSignature],
+    limit: usize,
+) -> Vec<(&'a FnSignature, f64)> {
+    let mut scored: Vec<(&FnSignature, f64)> = functions
+        .iter()
+        .map(|function| (function, signature_similarity(query, function)))
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .collect();
+    // Stable sort keeps declaration order among equal scores.
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+    scored
+}
+
+/// Similarity in `[0, 1]` between a query and a function signature.
+pub fn signature_similarity(query: &SignatureQuery, function: &FnSignature) -> f64 {
+    let inputs = inputs_similarity(&query.inputs, &function.inputs);
+    match &query.output {
+        Some(output) => (inputs + type_similarity(output, &function.output)) / 2.0,
+        None => inputs,
+    }
+}
+
+/// Match query arguments to function arguments in any order, greedily.
+fn inputs_similarity(query: &[TypeShape], candidate: &[TypeShape]) -> f64 {
+    if query.is_empty() && candidate.is_empty() {
+        return 1.0;
+    }
+    let mut used = vec![false; candidate.len()];
+    let mut total = 0.0;
+    for wanted in query {
+        let best = candidate
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !used[*i])
+            .map(|(i, ty)| (i, type_similarity(wanted, ty)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, score)) = best {
+            used[i] = true;
+            total += score;
+        }
+    }
+    total / query.len().max(candidate.len()) as f64
+}
+
+/// Similarity in `[0, 1]` between two normalized types.
+pub fn type_similarity(query: &TypeShape, candidate: &TypeShape) -> f64 {
+    match (query, candidate) {
+        (TypeShape::Wildcard, _) | (_, TypeShape::Wildcard) => 1.0,
+        (TypeShape::Generic(_), TypeShape::Generic(_)) => 1.0,
+        // A generic function accepts the concrete type, and vice versa, but less precisely.
+        (TypeShape::Generic(_), _) | (_, TypeShape::Generic(_)) => 0.4,
+        (TypeShape::Named(a, a_args), TypeShape::Named(b, b_args)) if a == b => {
+            0.5 + 0.5 * args_similarity(a_args, b_args)
+        }
+        (TypeShape::Tuple(a), TypeShape::Tuple(b)) if a.len() == b.len() => {
+            if a.is_empty() {
+                1.0
+            } else {
+                a.iter().zip(b).map(|(x, y)| type_similarity(x, y)).sum::<f64>() / a.len() as f64
+            }
+        }
+        _ => 0.0,
+    }
+}
+
+fn args_similarity(query: &[TypeShape], candidate: &[TypeShape]) -> f64 {
+    if query.is_empty() || candidate.is_empty() {
+        // `Vec` matches `Vec<u8>`; missing arguments are not held against either side.
+        return 1.0;
+    }
+    let total: f64 = query.iter().zip(candidate).map(|(a, b)| type_similarity(a, b)).sum();
+    total / query.len().max(candidate.len()) as f64
+}
+
+/// Decides which bare identifiers are type parameters.
+trait GenericScope {
+    fn is_generic(&self, name: &str) -> bool;
+    /// What `Self` stands for, if known.
+    fn self_type(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// In queries, single capital letters (`T`, `K`, `V`) are type parameters.
+struct QueryGenerics;
+
+impl GenericScope for QueryGenerics {
+    fn is_generic(&self, name: &str) -> bool {
+        name.len() == 1 && name.chars().all(|c| c.is_ascii_uppercase())
+    }
+}
+
+/// Type parameters declared on a function and its enclosing impl or trait.
+struct ItemGenerics {
+    params: HashSet<String>,
+    self_type: Option<String>,
+}
+
+impl GenericScope for ItemGenerics {
+    fn is_generic(&self, name: &str) -> bool {
+        self.params.contains(name)
+    }
+
+    fn self_type(&self) -> Option<&str> {
+        self.self_type.as_deref()
+    }
+}
+
+fn normalize(ty: &Type, scope: &dyn GenericScope) -> TypeShape {
+    match ty {
+        Type::Reference(r) => normalize(&r.elem, scope),
+        Type::Paren(p) => normalize(&p.elem, scope),
+        Type::Group(g) => normalize(&g.elem, scope),
+        Type::Ptr(p) => normalize(&p.elem, scope),
+        Type::Infer(_) => TypeShape::Wildcard,
+        Type::Never(_) => TypeShape::Named("!".to_string(), Vec::new()),
+        Type::Tuple(t) => TypeShape::Tuple(t.elems.iter().map(|e| normalize(e, scope)).collect()),
+        Type::Slice(s) => TypeShape::Named("Vec".to_string(), vec![normalize(&s.elem, scope)]),
+        Type::Array(a) => TypeShape::Named("Vec".to_string(), vec![normalize(&a.elem, scope)]),
+        Type::BareFn(f) => {
+            let mut args: Vec<TypeShape> = f.inputs.iter().map(|arg| normalize(&arg.ty, scope)).collect();
+            args.push(return_shape(&f.output, scope));
+            TypeShape::Named("Fn".to_string(), args)
+        }
+        Type::ImplTrait(t) => bounds_shape(t.bounds.iter(), scope),
+        Type::TraitObject(t) => bounds_shape(t.bounds.iter(), scope),
+        Type::Path(p) => {
+            if p.qself.is_some() {
+                // `<T as Trait>::Assoc` is as good as a type parameter for matching.
+                return TypeShape::Generic(render(ty));
+            }
+            let Some(last) = p.path.segments.last() else { return TypeShape::Wildcard };
+            let name = last.ident.to_string();
+            if p.path.segments.len() == 1 && last.arguments.is_none() {
+                if name == "Self" {
+                    return match scope.self_type() {
+                        Some(self_type) => TypeShape::Named(self_type.to_string(), Vec::new()),
+                        None => TypeShape::Generic(name),
+                    };
+                }
+                if scope.is_generic(&name) {
+                    return TypeShape::Generic(name);
+                }
+            }
+            if p.path.segments.len() == 2 && scope.is_generic(&p.path.segments[0].ident.to_string()) {
+                // `T::Item` and friends.
+                return TypeShape::Generic(render(ty));
+            }
+            TypeShape::Named(canonical_name(&name), path_args(&last.arguments, scope))
+        }
+        _ => TypeShape::Named(render(ty), Vec::new()),
+    }
+}
+
+/// Borrowed and owned forms of the same data match each other.
+fn canonical_name(name: &str) -> String {
+    match name {
+        "str" => "String",
+        "Path" => "PathBuf",
+        "OsStr" => "OsString",
+        "CStr" => "CString",
+        other => other,
+    }
+    .to_string()
+}
+
+fn path_args(arguments: &PathArguments, scope: &dyn GenericScope) -> Vec<TypeShape> {
+    match arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(normalize(ty, scope)),
+                // `Iterator<Item = T>` is matched on `T`.
+                GenericArgument::AssocType(assoc) => Some(normalize(&assoc.ty, scope)),
+                _ => None,
+            })
+            .collect(),
+        PathArguments::Parenthesized(args) => {
+            let mut shapes: Vec<TypeShape> = args.inputs.iter().map(|ty| normalize(ty, scope)).collect();
+            shapes.push(return_shape(&args.output, scope));
+            shapes
+        }
+        PathArguments::None => Vec::new(),
+    }
+}
+
+/// `impl Trait` and `dyn Trait` are matched on their first trait bound.
+/// Conversion traits stand for their target, so `impl Into<String>` matches `String`.
+fn bounds_shape<'a>(bounds: impl Iterator<Item = &'a TypeParamBound>, scope: &dyn GenericScope) -> TypeShape {
+    for bound in bounds {
+        let TypeParamBound::Trait(t) = bound else { continue };
+        let Some(last) = t.path.segments.last() else { continue };
+        let name = last.ident.to_string();
+        let mut args = path_args(&last.arguments, scope);
+        if matches!(name.as_str(), "Into" | "AsRef" | "Borrow") && args.len() == 1 {
+            return args.remove(0);
+        }
+        let name = match name.as_str() {
+            "FnOnce" | "FnMut" => "Fn".to_string(),
+            "IntoIterator" => "Iterator".to_string(),
+            _ => name,
+        };
+        return TypeShape::Named(name, args);
+    }
+    TypeShape::Wildcard
+}
+
+fn return_shape(output: &ReturnType, scope: &dyn GenericScope) -> TypeShape {
+    match output {
+        ReturnType::Default => TypeShape::Tuple(Vec::new()),
+        ReturnType::Type(_, ty) => normalize(ty, scope),
+    }
+}
+
+fn collect_items<'a>(items: impl IntoIterator<Item = &'a Item>, module_path: &[String], out: &mut Vec<FnSignature>) {
+    let is_pub = |vis: &syn::Visibility| matches!(vis, syn::Visibility::Public(_));
+    let path_of = |name: &dyn std::fmt::Display| {
+        let mut parts = module_path.to_vec();
+        parts.push(name.to_string());
+        parts.join("::")
+    };
+    for item in items {
+        match item {
+            Item::Fn(f) if is_pub(&f.vis) => {
+                let scope = ItemGenerics { params: generic_names(&[&f.sig.generics]), self_type: None };
+                out.push(signature(path_of(&f.sig.ident), &f.sig, &scope));
+            }
+            Item::Impl(block) => {
+                if let Some(type_name) = impl_type_name(block) {
+                    collect_impl(block, &path_of(&type_name), out);
+                }
+            }
+            Item::Trait(t) if is_pub(&t.vis) => {
+                let trait_path = path_of(&t.ident);
+                for trait_item in &t.items {
+                    let TraitItem::Fn(f) = trait_item else { continue };
+                    let mut params = generic_names(&[&t.generics, &f.sig.generics]);
+                    params.extend(t.items.iter().filter_map(|i| match i {
+                        TraitItem::Type(assoc) => Some(assoc.ident.to_string()),
+                        _ => None,
+                    }));
+                    let scope = ItemGenerics { params, self_type: None };
+                    out.push(signature(format!("{}::{}", trait_path, f.sig.ident), &f.sig, &scope));
+                }
+            }
+            Item::Mod(m) if is_pub(&m.vis) => {
+                if let Some((_, inline_items)) = &m.content {
+                    let mut child_path = module_path.to_vec();
+                    child_path.push(m.ident.to_string());
+                    collect_items(inline_items, &child_path, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The methods of impl block `block`, as members of the type at `type_path`.
+fn collect_impl(block: &syn::ItemImpl, type_path: &str, out: &mut Vec<FnSignature>) {
+    let Some(type_name) = impl_type_name(block) else { return };
+    for impl_item in &block.items {
+        let ImplItem::Fn(f) = impl_item else { continue };
+        if block.trait_.is_none() && !matches!(f.vis, syn::Visibility::Public(_)) {
+            continue;
+        }
+        let scope = ItemGenerics {
+            params: generic_names(&[&block.generics, &f.sig.generics]),
+            self_type: Some(type_name.clone()),
+        };
+        out.push(signature(format!("{}::{}", type_path, f.sig.ident), &f.sig, &scope));
+    }
+}
+
+fn generic_names(generics: &[&syn::Generics]) -> HashSet<String> {
+    generics
+        .iter()
+        .flat_map(|g| g.type_params())
+        .map(|param| param.ident.to_string())
+        .collect()
+}
+
+fn signature(path: String, sig: &syn::Signature, scope: &ItemGenerics) -> FnSignature {
+    let inputs = sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Receiver(_) => match scope.self_type() {
+                Some(self_type) => TypeShape::Named(self_type.to_string(), Vec::new()),
+                None => TypeShape::Generic("Self".to_string()),
+            },
+            FnArg::Typed(pat) => normalize(&pat.ty, scope),
+        })
+        .collect();
+    FnSignature {
+        path,
+        rendered: render(sig),
+        inputs,
+        output: return_shape(&sig.output, scope),
+    }
+}
+
+/// Split `text` on `separator` where it is not nested in `<>`, `()` or `[]`.
+fn split_top_level<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' | b'(' | b'[' => depth += 1,
+            // The `>` of an arrow closes nothing.
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' | b')' | b']' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 && text[i..].starts_with(separator) {
+            parts.push(&text[start..i]);
+            i += separator.len();
+            start = i;
+            continue;
+        }
+        i += 1;
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn last_top_level_arrow(text: &str) -> Option<usize> {
+    let parts = split_top_level(text, "->");
+    if parts.len() < 2 {
+        return None;
+    }
+    let before: usize = parts[..parts.len() - 1].iter().map(|p| p.len() + 2).sum();
+    Some(before - 2)
+}
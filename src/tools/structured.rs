@@ -0,0 +1,165 @@
+use mcp_core::{Content, ResourceContents};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Schema id of a single item (`lookup_item`).
+pub const ITEM_SCHEMA: &str = "cratedocs/item/v1";
+/// Schema id of an item listing (`list_crate_items`).
+pub const ITEM_LIST_SCHEMA: &str = "cratedocs/item-list/v1";
+/// Schema id of crates.io search results (`search_crates`).
+pub const SEARCH_SCHEMA: &str = "cratedocs/search/v1";
+/// Schema id of crate metadata (`lookup_crate`).
+pub const CRATE_SCHEMA: &str = "cratedocs/crate/v1";
+
+/// MIME type of embedded structured output.
+pub const MIME_TYPE: &str = "application/json";
+
+/// An item as documented by rustdoc or declared in source.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemRecord {
+    /// `struct`, `enum`, `union`, `trait`, `fn`, `method`, `macro`, `type`, `const`, `static` or `mod`.
+    pub kind: String,
+    /// Path including the crate name, e.g. `tokio::sync::mpsc::Sender`.
+    pub path: String,
+    /// The declaration without its body, when known.
+    pub signature: Option<String>,
+    /// The doc comment as markdown.
+    pub docs: Option<String>,
+    /// Version the item was stabilized or introduced in, when documented.
+    pub since: Option<String>,
+    pub deprecated: Option<Deprecation>,
+    /// Cargo features the item requires.
+    pub feature_gates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deprecation {
+    pub since: Option<String>,
+    pub note: Option<String>,
+}
+
+/// The items of one crate version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemList {
+    pub crate_name: String,
+    pub version: String,
+    pub items: Vec<ItemRecord>,
+}
+
+/// One crates.io search hit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateSummary {
+    pub name: String,
+    /// Newest stable version, or the newest version if none is stable.
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub downloads: u64,
+    pub documentation: Option<String>,
+    pub repository: Option<String>,
+}
+
+/// crates.io search results for a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub crates: Vec<CrateSummary>,
+}
+
+/// crates.io metadata of one crate version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateMetadata {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    pub downloads: u64,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// Feature name to the features and optional dependencies it enables.
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+/// `cratedocs://{name}/{version}`, the URI of a crate version (`latest` if unspecified).
+pub fn crate_uri(crate_name: &str, version: Option<&str>) -> String {
+    format!("cratedocs://{}/{}", crate_name, version.unwrap_or("latest"))
+}
+
+/// `cratedocs://search?q={query}`, the URI of a crates.io search.
+pub fn search_uri(query: &str) -> String {
+    reqwest::Url::parse_with_params("cratedocs://search", [("q", query)])
+        .map(String::from)
+        .unwrap_or_else(|_| "cratedocs://search".to_string())
+}
+
+/// Whether a tool call asked for structured output.
+pub fn requested(arguments: &Value) -> bool {
+    arguments.get("structured").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Wrap `value` as an embedded JSON resource tagged with its `schema` id.
+pub fn embed<T: Serialize>(uri: &str, schema: &str, value: &T) -> Content {
+    let mut json = serde_json::to_value(value).unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut json {
+        map.insert("schema".to_string(), Value::String(schema.to_string()));
+    }
+    Content::resource(ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some(MIME_TYPE.to_string()),
+        text: serde_json::to_string_pretty(&json).unwrap_or_default(),
+    })
+}
+
+/// Parse a crates.io `/api/v1/crates?q=` response into search hits.
+pub fn search_results(query: &str, body: &str) -> Result<SearchResults, serde_json::Error> {
+    let response: Value = serde_json::from_str(body)?;
+    let crates = response["crates"].as_array().cloned().unwrap_or_default();
+    let crates = crates
+        .iter()
+        .map(|info| CrateSummary {
+            name: string(&info["name"]).unwrap_or_default(),
+            version: string(&info["max_stable_version"]).or_else(|| string(&info["max_version"])),
+            description: string(&info["description"]).map(|d| d.trim().to_string()),
+            downloads: info["downloads"].as_u64().unwrap_or(0),
+            documentation: string(&info["documentation"]),
+            repository: string(&info["repository"]),
+        })
+        .collect();
+    Ok(SearchResults {
+        query: query.to_string(),
+        crates,
+    })
+}
+
+/// Combine crates.io's `/api/v1/crates/{name}` and `/api/v1/crates/{name}/{version}` responses.
+pub fn crate_metadata(crate_info: &Value, version_info: &Value) -> CrateMetadata {
+    let krate = &crate_info["crate"];
+    let version = &version_info["version"];
+    let strings = |value: &Value| -> Vec<String> {
+        value.as_array().map_or_else(Vec::new, |values| values.iter().filter_map(string).collect())
+    };
+    let features = version["features"]
+        .as_object()
+        .map(|features| features.iter().map(|(name, enables)| (name.clone(), strings(enables))).collect())
+        .unwrap_or_default();
+    CrateMetadata {
+        name: string(&krate["name"]).unwrap_or_default(),
+        version: string(&version["num"]).unwrap_or_default(),
+        description: string(&krate["description"]).map(|d| d.trim().to_string()),
+        license: string(&version["license"]),
+        repository: string(&krate["repository"]),
+        homepage: string(&krate["homepage"]),
+        documentation: string(&krate["documentation"]),
+        downloads: krate["downloads"].as_u64().unwrap_or(0),
+        keywords: strings(&krate["keywords"]),
+        categories: strings(&krate["categories"]),
+        features,
+    }
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
@@ -0,0 +1,703 @@
+use crate::tools::api_diff::{collect_public_api, diff_public_api};
+use crate::tools::crate_search::{tokenize, CrateIndex};
+use crate::tools::examples::{collect_examples, render_examples, rust_code_blocks};
+use crate::tools::impls::{collect_impls, filter_by_trait, filter_by_type, render_impls, ImplQuery};
+use crate::tools::item_list::{collect_item_records, crate_dir_name, render_item_list, ItemListFilters};
+use crate::tools::metrics::Metrics;
+use crate::tools::paginate::{page_from_cursor, render_page, split_pages};
+use crate::tools::pipeline::Pipeline;
+use crate::tools::prompts::{list_prompts, render_prompt};
+use crate::tools::rustdoc::{rustdoc_item_record, rustdoc_to_markdown};
+use crate::tools::signature_search::{collect_signatures, parse_query, rank_signatures, TypeShape};
+use crate::tools::structured::{crate_metadata, search_results, Deprecation, ItemList};
+use crate::tools::tokens::{heuristic_tokens, truncate_to_tokens, TokenizerSource};
+use crate::tools::view_source::{find_item_source, render_spans};
+use mcp_core::handler::PromptError;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Write a throwaway crate source tree and return its root directory.
+fn write_crate(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cratedocs-test-{}-{:x}", name, rand::random::<u64>()));
+    for (path, content) in files {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_collect_public_api_follows_pub_modules() {
+    let dir = write_crate(
+        "collect",
+        &[
+            ("src/lib.rs", "pub mod client;\nmod internal;\npub fn top(x: &str) -> usize { x.len() }\nfn hidden() {}"),
+            ("src/client.rs", "pub struct Client { pub timeout: u64, secret: u8 }\nimpl Client { pub fn new() -> Self { todo!() } fn private(&self) {} }"),
+            ("src/internal.rs", "pub fn not_reachable() {}"),
+        ],
+    );
+    let api = collect_public_api(&dir);
+    let paths: Vec<&str> = api.keys().map(|(path, _)| path.as_str()).collect();
+
+    assert!(paths.contains(&"top"));
+    assert!(paths.contains(&"client::Client"));
+    assert!(paths.contains(&"client::Client::timeout"));
+    assert!(paths.contains(&"client::Client::new"));
+    assert!(!paths.contains(&"hidden"));
+    assert!(!paths.contains(&"client::Client::secret"));
+    assert!(!paths.contains(&"client::Client::private"));
+    assert!(!paths.contains(&"internal::not_reachable"));
+
+    let top = &api[&("top".to_string(), "fn")];
+    assert_eq!(top.signature, "fn top(x: &str) -> usize");
+    fs::remove_dir_all(dir).ok();
+}
+
+/// A crate whose API lives in private modules, re-exported from the root, with impls
+/// in a module of their own.
+fn reexport_crate(name: &str) -> PathBuf {
+    write_crate(
+        name,
+        &[
+            ("src/lib.rs", "mod imp;\nmod util;\nmod methods;\npub use imp::*;\npub use self::util::{only, Gone};\n"),
+            ("src/imp.rs", "pub struct Client;\npub mod nested;\npub fn connect(url: &str) -> Client { todo!() }"),
+            ("src/imp/nested.rs", "pub fn deep() {}"),
+            ("src/util.rs", "pub fn only() {}\npub fn skipped() {}\npub struct Gone;"),
+            ("src/methods.rs", "use crate::Client;\nimpl Client {\n    /// Closes the connection.\n    pub fn close(&self) -> bool { true }\n}\nimpl std::fmt::Display for Client { fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) } }\nstruct Private;\nimpl Private { pub fn unreachable(&self) {} }\n#[cfg(test)]\nmod tests { impl crate::Client { pub fn test_only(&self) {} } }"),
+        ],
+    )
+}
+
+#[test]
+fn test_collect_public_api_resolves_reexports() {
+    let dir = reexport_crate("reexports");
+    let api = collect_public_api(&dir);
+    let paths: Vec<&str> = api.keys().map(|(path, _)| path.as_str()).collect();
+    assert!(paths.contains(&"Client"));
+    assert!(paths.contains(&"connect"));
+    assert!(paths.contains(&"nested::deep"));
+    assert!(paths.contains(&"only"));
+    assert!(paths.contains(&"Gone"));
+    assert!(paths.contains(&"Client::close"));
+    assert!(paths.contains(&"impl std::fmt::Display for Client"));
+    assert!(!paths.contains(&"skipped"));
+    assert!(!paths.contains(&"imp::Client"));
+    assert!(!paths.contains(&"Private::unreachable"));
+    assert!(!paths.contains(&"Client::test_only"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_diff_public_api_flags_breaking_changes() {
+    let old_dir = write_crate(
+        "diff-old",
+        &[(
+            "src/lib.rs",
+            "pub fn keep() {}\npub fn gone() {}\npub fn retyped(x: u32) {}\npub enum Mode { A, B }\n#[non_exhaustive]\npub enum Open { A }",
+        )],
+    );
+    let new_dir = write_crate(
+        "diff-new",
+        &[(
+            "src/lib.rs",
+            "pub fn keep() {}\npub fn retyped(x: u64) {}\npub fn fresh() {}\npub enum Mode { A, B, C }\n#[non_exhaustive]\npub enum Open { A, B }",
+        )],
+    );
+    let diff = diff_public_api(&collect_public_api(&old_dir), &collect_public_api(&new_dir));
+
+    let removed: Vec<&str> = diff.removed.iter().map(|i| i.path.as_str()).collect();
+    let added: Vec<&str> = diff.added.iter().map(|i| i.path.as_str()).collect();
+    let breaking_additions: Vec<&str> = diff.breaking_additions.iter().map(|i| i.path.as_str()).collect();
+
+    assert_eq!(removed, vec!["gone"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].1.signature, "fn retyped(x: u64)");
+    assert!(added.contains(&"fresh"));
+    assert!(added.contains(&"Mode::C"));
+    assert!(added.contains(&"Open::B"));
+    assert_eq!(breaking_additions, vec!["Mode::C"]);
+    assert_eq!(diff.breaking_count(), 3);
+    assert_eq!(diff.required_bump("1.2.0"), "major");
+
+    let markdown = diff.to_markdown("demo", "1.2.0", "2.0.0");
+    assert!(markdown.contains("## Removed"));
+    assert!(markdown.contains("old: `fn retyped(x: u32)`"));
+    fs::remove_dir_all(old_dir).ok();
+    fs::remove_dir_all(new_dir).ok();
+}
+
+#[test]
+fn test_rust_code_blocks_skips_other_languages_and_hidden_lines() {
+    let markdown = "Intro\n```rust\n# use demo::Client;\nlet c = Client::new();\n```\n```toml\ndemo = \"1\"\n```\n```no_run\nrun();\n```";
+    let blocks = rust_code_blocks(markdown);
+    assert_eq!(blocks, vec!["let c = Client::new();".to_string(), "run();".to_string()]);
+}
+
+#[test]
+fn test_collect_examples_ranks_attached_doctests_first() {
+    let dir = write_crate(
+        "examples",
+        &[
+            ("src/lib.rs", "//! ```\n//! demo::connect();\n//! ```\n\n/// Connects.\n///\n/// ```\n/// let c = demo::connect();\n/// ```\npub fn connect() {}"),
+            ("examples/basic.rs", "fn main() { demo::connect(); }"),
+            ("examples/other.rs", "fn main() {}"),
+            ("tests/it.rs", "fn connects_slowly() {}\n\n#[test]\nfn connects() {\n    demo::connect();\n}\n\n#[test]\nfn unrelated() {}"),
+            ("README.md", "# Demo\n```rust\ndemo::connect();\n```"),
+        ],
+    );
+    let examples = collect_examples(&dir, Some("connect"));
+    let titles: Vec<&str> = examples.iter().map(|e| e.title.as_str()).collect();
+
+    assert_eq!(titles[0], "doctest on `connect`");
+    assert!(titles.contains(&"example `examples/basic.rs`"));
+    assert!(!titles.contains(&"example `examples/other.rs`"));
+    assert!(titles.contains(&"test `connects` in `tests/it.rs`"));
+    assert!(!titles.iter().any(|t| t.contains("unrelated")));
+    assert!(titles.contains(&"README snippet"));
+
+    let test_example = examples.iter().find(|e| e.title.starts_with("test")).unwrap();
+    // Not confused with `connects_slowly`, whose name starts the same
+    assert_eq!(test_example.code, "#[test]\nfn connects() {\n    demo::connect();\n}");
+
+    let rendered = render_examples(&examples, 60);
+    assert!(rendered.contains("## 1. doctest on `connect`"));
+    assert!(rendered.contains("omitted"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_find_item_source_includes_impls_and_context() {
+    let dir = write_crate(
+        "source",
+        &[
+            ("src/lib.rs", "pub mod client;\n"),
+            (
+                "src/client.rs",
+                "use std::fmt;\n\n/// A client.\npub struct Client {\n    pub url: String,\n}\n\nimpl Client {\n    pub fn get(&self) -> &str {\n        &self.url\n    }\n}\n\nimpl fmt::Display for Client {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n        write!(f, \"{}\", self.url)\n    }\n}\n",
+            ),
+        ],
+    );
+    let spans = find_item_source(&dir, "Client");
+    let labels: Vec<&str> = spans.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, vec!["struct `client::Client`", "impl Client", "impl fmt::Display for Client"]);
+    assert_eq!((spans[0].start_line, spans[0].end_line), (3, 6));
+    assert_eq!((spans[1].start_line, spans[1].end_line), (8, 12));
+
+    let method = find_item_source(&dir, "Client::get");
+    assert_eq!(method.len(), 1);
+    let rendered = render_spans(&dir, &method, 1);
+    assert!(rendered.contains("## method `client::Client::get` (src/client.rs:9-11)"));
+    assert!(rendered.contains(" 8 | impl Client {"));
+    assert!(rendered.contains("12 | }"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_tokenize_splits_identifiers_and_stems() {
+    assert_eq!(tokenize("GracefulShutdown"), vec!["graceful", "shutdown"]);
+    assert_eq!(tokenize("max_retries of the pool"), vec!["max", "retri", "pool"]);
+    assert_eq!(tokenize("closing"), tokenize("closed"));
+    assert_eq!(tokenize("closes"), tokenize("close"));
+}
+
+#[test]
+fn test_crate_index_resolves_reexports() {
+    let dir = reexport_crate("index-reexports");
+    let index = CrateIndex::build(&dir);
+    let hits = index.search("closes connection", 10);
+    assert_eq!(hits[0].document.path, "Client::close");
+    let hits = index.search("skipped test_only unreachable", 10);
+    let paths: Vec<&str> = hits.iter().map(|h| h.document.path.as_str()).collect();
+    assert!(!paths.contains(&"skipped"));
+    assert!(!paths.contains(&"Client::test_only"));
+    assert!(!paths.contains(&"Private::unreachable"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_crate_index_ranks_concepts_over_names() {
+    let dir = write_crate(
+        "search",
+        &[
+            ("src/lib.rs", "//! Channels for tasks.\npub mod runtime;\nmod private;\n\n/// Bounded channel that applies backpressure when full.\npub fn channel() {}\n\n/// Unbounded channel.\npub fn unbounded_channel() {}\n\n/// Applies backpressure internally.\nfn hidden() {}"),
+            ("src/runtime.rs", "pub struct Runtime;\nimpl Runtime {\n    /// Waits for all tasks, then stops.\n    pub fn shutdown_graceful(self) {}\n    /// Not part of the API.\n    fn shutdown_now(self) {}\n}"),
+            ("src/private.rs", "/// Shutdown helper.\npub fn shutdown_helper() {}"),
+            ("README.md", "# Demo\nIntro.\n\n## Graceful shutdown\nCall `Runtime::shutdown_graceful` to stop.\n"),
+        ],
+    );
+    let index = CrateIndex::build(&dir);
+    assert!(!index.is_empty());
+
+    let hits = index.search("backpressure", 10);
+    let paths: Vec<&str> = hits.iter().map(|h| h.document.path.as_str()).collect();
+    assert_eq!(paths, vec!["channel"]);
+    assert_eq!(hits[0].snippet, "Bounded channel that applies backpressure when full.");
+
+    let hits = index.search("graceful shutdown", 10);
+    let paths: Vec<&str> = hits.iter().map(|h| h.document.path.as_str()).collect();
+    assert!(paths[..2].contains(&"runtime::Runtime::shutdown_graceful"));
+    assert!(paths[..2].contains(&"README: Graceful shutdown"));
+    assert!(!paths.contains(&"runtime::Runtime::shutdown_now"));
+    assert!(!paths.contains(&"private::shutdown_helper"));
+
+    assert!(index.search("nonexistent", 10).is_empty());
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_parse_query_normalizes_references_and_generics() {
+    let query = parse_query("&str, &mut Vec<T> -> Result<Url, _>").unwrap();
+    assert_eq!(
+        query.inputs,
+        vec![
+            TypeShape::Named("String".to_string(), vec![]),
+            TypeShape::Named("Vec".to_string(), vec![TypeShape::Generic("T".to_string())]),
+        ]
+    );
+    assert_eq!(
+        query.output,
+        Some(TypeShape::Named(
+            "Result".to_string(),
+            vec![TypeShape::Named("Url".to_string(), vec![]), TypeShape::Wildcard]
+        ))
+    );
+    assert!(parse_query("impl Iterator<Item = T> -> Vec<T>").unwrap().output.is_some());
+    assert!(parse_query("&str").unwrap().output.is_none());
+    assert!(parse_query("->").is_err());
+}
+
+#[test]
+fn test_collect_signatures_resolves_reexports() {
+    let dir = reexport_crate("signature-reexports");
+    let functions = collect_signatures(&dir);
+    let paths: Vec<&str> = functions.iter().map(|f| f.path.as_str()).collect();
+    assert!(paths.contains(&"connect"));
+    assert!(paths.contains(&"nested::deep"));
+    assert!(paths.contains(&"Client::close"));
+    assert!(!paths.contains(&"skipped"));
+    assert!(!paths.contains(&"imp::connect"));
+    assert!(!paths.contains(&"Private::unreachable"));
+    assert!(!paths.contains(&"Client::test_only"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_rank_signatures_prefers_closest_types() {
+    let dir = write_crate(
+        "signature",
+        &[(
+            "src/lib.rs",
+            "pub struct Url;\npub struct ParseError;\nimpl Url {\n    pub fn parse(input: &str) -> Result<Url, ParseError> { todo!() }\n    pub fn as_str(&self) -> &str { todo!() }\n    fn private(s: &str) -> Result<Url, ParseError> { todo!() }\n}\npub fn from_string(s: String) -> Option<Url> { todo!() }\npub fn identity<T>(value: T) -> T { value }\npub fn collect<T>(items: impl Iterator<Item = T>) -> Vec<T> { items.collect() }",
+        )],
+    );
+    let functions = collect_signatures(&dir);
+    let paths = |query: &str| -> Vec<String> {
+        rank_signatures(&parse_query(query).unwrap(), &functions, 10)
+            .into_iter()
+            .map(|(f, _)| f.path.clone())
+            .collect()
+    };
+
+    let parse = paths("&str -> Result<Url, _>");
+    assert_eq!(parse[0], "Url::parse");
+    assert!(parse.contains(&"from_string".to_string()));
+    assert!(!parse.contains(&"Url::private".to_string()));
+    assert!(!parse.contains(&"identity".to_string()));
+
+    assert_eq!(paths("Url -> String")[0], "Url::as_str");
+    assert_eq!(paths("impl Iterator<Item = T> -> Vec<T>")[0], "collect");
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_collect_impls_finds_derives_and_blanket_impls() {
+    let dir = write_crate(
+        "impls",
+        &[
+            ("src/lib.rs", "mod imp;\npub trait Service<R> { fn call(&mut self, req: R); }\n#[derive(Debug, Clone)]\npub struct Bytes;\nimpl Bytes { pub fn len(&self) -> usize { 0 } }\nimpl<T: Service<u8> + ?Sized> Service<u8> for &mut T { fn call(&mut self, req: u8) {} }"),
+            ("src/imp.rs", "use crate::{Bytes, Service};\nimpl Service<u8> for Bytes { fn call(&mut self, req: u8) {} }\nimpl<T> From<T> for Wrapper<T> { fn from(t: T) -> Self { Wrapper(t) } }\npub struct Wrapper<T>(T);\n#[cfg(test)]\nmod tests { impl Default for crate::Bytes { fn default() -> Self { crate::Bytes } } }"),
+        ],
+    );
+    let impls = collect_impls(&dir, "demo");
+
+    let services = filter_by_trait(&impls, "tower::Service");
+    let self_types: Vec<&str> = services.iter().map(|r| r.self_ty.as_str()).collect();
+    assert_eq!(self_types, vec!["Bytes", "&mut T"]);
+    assert_eq!(services[0].location, "src/imp.rs:2");
+    assert!(services[1].blanket);
+
+    let for_bytes = filter_by_type(&impls, "bytes::Bytes", false);
+    let traits: Vec<Option<&str>> = for_bytes.iter().map(|r| r.trait_path.as_deref()).collect();
+    assert_eq!(traits.len(), 4);
+    assert!(traits.contains(&Some("Debug")));
+    assert!(traits.contains(&Some("Clone")));
+    assert!(traits.contains(&Some("Service<u8>")));
+    assert!(traits.contains(&None));
+    assert!(!impls.iter().any(|r| r.trait_path.as_deref() == Some("Default")));
+
+    let for_bytes = filter_by_type(&impls, "bytes::Bytes", true);
+    assert_eq!(for_bytes.iter().filter(|r| r.blanket).count(), 1);
+    assert!(!for_bytes.iter().any(|r| r.trait_path.as_deref() == Some("From<T>")));
+
+    let rendered = render_impls(&for_bytes, ImplQuery::Type("Bytes"));
+    assert!(rendered.contains("## Derived (2)"));
+    assert!(rendered.contains("## Blanket implementations (1)"));
+    assert!(rendered.contains("- `Service<u8>`: `impl Service<u8> for Bytes` (demo src/imp.rs:2)"));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_rustdoc_to_markdown_keeps_docs_and_drops_chrome() {
+    let html = r##"<html><body>
+<nav class="sidebar"><a href="#">Sender</a><ul><li>Methods</li></ul></nav>
+<main><rustdoc-toolbar></rustdoc-toolbar>
+<div id="search"></div>
+<section id="main-content" class="content">
+<div class="main-heading"><h1>Struct <span class="struct">Sender</span><button id="copy-path">Copy item path</button></h1>
+<span class="out-of-band"><a class="src" href="../src/chan.rs.html">Source</a></span></div>
+<pre class="rust item-decl"><code>pub struct Sender&lt;T&gt; { <span class="comment">/* private fields */</span> }</code></pre>
+<details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+<div class="docblock"><p>Sends values to the associated <code>Receiver</code>.</p>
+<div class="example-wrap"><pre class="rust rust-example-rendered"><code>let (tx, rx) = channel();
+tx.send(1).await;</code></pre></div>
+<div class="example-wrap"><pre class="language-toml"><code>tokio = "1"</code></pre></div>
+<ul><li>fast</li><li>bounded</li></ul></div></details>
+<h2 id="implementations" class="section-header">Implementations<a href="#implementations" class="anchor">§</a></h2>
+<details class="toggle implementors-toggle" open><summary><section id="impl-Sender" class="impl"><a class="src rightside" href="#">Source</a><h3 class="code-header">impl&lt;T&gt; Sender&lt;T&gt;</h3></section></summary>
+<div class="impl-items"><section id="method.send" class="method"><span class="rightside"><span class="since">1.0</span></span><h4 class="code-header">pub async fn <a href="#method.send" class="fn">send</a>(&amp;self, value: T)</h4></section>
+<div class="docblock"><p>Sends a value, waiting until there is <em>capacity</em>.</p></div></div></details>
+</section></main>
+<footer>docs.rs footer</footer>
+<script>window.x = 1;</script>
+</body></html>"##;
+    let markdown = rustdoc_to_markdown(html);
+
+    assert!(markdown.starts_with("# Struct Sender\n"));
+    assert!(markdown.contains("```rust\npub struct Sender<T> { /* private fields */ }\n```"));
+    assert!(markdown.contains("Sends values to the associated `Receiver`."));
+    assert!(markdown.contains("```rust\nlet (tx, rx) = channel();\ntx.send(1).await;\n```"));
+    assert!(markdown.contains("```toml\ntokio = \"1\"\n```"));
+    assert!(markdown.contains("- fast\n- bounded"));
+    assert!(markdown.contains("## Implementations\n"));
+    assert!(markdown.contains("### impl<T> Sender<T>"));
+    assert!(markdown.contains("#### pub async fn send(&self, value: T)"));
+    assert!(markdown.contains("waiting until there is *capacity*."));
+    for chrome in ["Copy item path", "Source", "Expand description", "§", "Methods", "footer", "window.x", "1.0"] {
+        assert!(!markdown.contains(chrome), "{:?} leaked into:\n{}", chrome, markdown);
+    }
+}
+
+#[test]
+fn test_split_pages_breaks_at_sections_and_reopens_code_fences() {
+    let words = |text: &str| text.split_whitespace().count();
+    let markdown = "# Intro\none two three\n\n## Usage\nfour five six\n\n## Example\n```rust\nlet a = 1;\nlet b = 2;\nlet c = 3;\nlet d = 4;\n```\n";
+    let pages = split_pages(markdown, 10, &words);
+
+    assert_eq!(pages[0], "# Intro\none two three\n\n## Usage\nfour five six\n\n");
+    assert!(pages.iter().all(|page| words(page) <= 10));
+    for page in &pages[1..] {
+        assert_eq!(page.matches("```").count() % 2, 0, "unbalanced fence in {:?}", page);
+    }
+    assert_eq!(pages.concat().matches("let ").count(), 4);
+
+    let first = render_page(&pages, 1);
+    assert!(first.ends_with(&format!("Page 1 of {}. To read on, call this tool again with the same arguments and \"cursor\": \"page-2\".\n", pages.len())));
+    assert!(render_page(&pages, pages.len()).contains("(last page)"));
+    assert_eq!(page_from_cursor("page-2"), Some(2));
+    assert_eq!(page_from_cursor("page-0"), None);
+    assert_eq!(page_from_cursor("2"), None);
+
+    let short = split_pages("# Title\nshort", 100, &words);
+    assert_eq!(render_page(&short, 1), "# Title\nshort");
+
+    // A line longer than a page inside a code block is cut into fenced pieces
+    let long_line = (1..=12).map(|i| format!("w{}", i)).collect::<Vec<_>>().join(" ");
+    let markdown = format!("## Example\n```rust\nlet a = 1;\n{}\nlet b = 2;\n```\n", long_line);
+    let pages = split_pages(&markdown, 6, &words);
+    for page in &pages {
+        assert_eq!(page.matches("```").count() % 2, 0, "unbalanced fence in {:?}", page);
+        assert!(words(page) <= 6, "page too long: {:?}", page);
+        if page.split_whitespace().any(|word| word.starts_with('w')) {
+            assert!(page.starts_with("```rust\n") && page.ends_with("```\n"), "piece outside the fence: {:?}", page);
+        }
+    }
+    let code: String = pages.concat().split_whitespace().filter(|w| w.starts_with('w')).collect::<Vec<_>>().join(" ");
+    assert_eq!(code, long_line);
+    let order: Vec<usize> = ["let a", "w1 ", "w12", "let b"].iter().map(|s| pages.concat().find(s).unwrap()).collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", pages);
+}
+
+#[test]
+fn test_tokenizer_sources_and_binary_search_truncation() {
+    assert_eq!(TokenizerSource::parse("heuristic"), TokenizerSource::Heuristic);
+    assert_eq!(TokenizerSource::parse("  "), TokenizerSource::Heuristic);
+    assert_eq!(
+        TokenizerSource::parse("/opt/models/tokenizer.json"),
+        TokenizerSource::File(PathBuf::from("/opt/models/tokenizer.json"))
+    );
+    assert_eq!(
+        TokenizerSource::parse("bert-base-cased"),
+        TokenizerSource::Pretrained("bert-base-cased".to_string())
+    );
+
+    assert_eq!(heuristic_tokens(""), 0);
+    assert_eq!(heuristic_tokens("abcde"), 2);
+
+    let calls = std::cell::Cell::new(0);
+    let words = |text: &str| {
+        calls.set(calls.get() + 1);
+        text.split_whitespace().count()
+    };
+    let text = (1..=1000).map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+    let prefix = truncate_to_tokens(&text, 10, &words);
+    assert!(prefix.starts_with("1 2 3 4 5 6 7 8 9 10"));
+    assert_eq!(prefix.split_whitespace().count(), 10);
+    assert!(calls.get() < 30, "{} counts for a binary search", calls.get());
+
+    assert_eq!(truncate_to_tokens("short text", 10, &words), "short text");
+    assert_eq!(truncate_to_tokens("héllo wörld", 1, &heuristic_tokens), "hél");
+}
+
+#[test]
+fn test_output_pipeline_filters_and_presets() {
+    let markdown = "# mycrate\n\
+[![CI](https://github.com/o/r/workflows/ci/badge.svg)](https://github.com/o/r/actions) [![docs](https://docs.rs/mycrate/badge.svg)](https://docs.rs/mycrate)\n\
+![diagram](https://example.com/diagram.png)\n\
+<details><summary>More</summary>\n\
+See [the guide](https://example.com/guide) and [again](https://example.com/guide).\n\
+Uses `Vec<T>` and <kbd>Ctrl</kbd>.\n\
+```rust\n\
+# License\n\
+let x = \"<div>\";\n\
+```\n\
+</details>\n\
+## Trait Implementations\n\
+### impl<T> Clone for Sender<T>\n\
+#### fn clone(&self) -> Self\n\
+Returns a copy.\n\
+## Auto Trait Implementations\n\
+### impl<T> Send for Sender<T>\n\
+## Blanket Implementations\n\
+### impl<T> From<T> for T\n\
+## License\n\
+MIT\n\
+### Contributing\n\
+PRs welcome.\n\
+## Usage\n\
+Call it.\n";
+
+    let tldr = Pipeline::tldr().apply(markdown);
+    assert!(!tldr.contains("MIT") && !tldr.contains("PRs welcome"));
+    assert!(tldr.contains("## Usage\nCall it."));
+    assert!(tldr.contains("# License\nlet x = \"<div>\";"), "code blocks are left alone");
+    assert!(tldr.contains("Uses `Vec<T>` and Ctrl."));
+    assert!(!tldr.contains("<details>") && !tldr.contains("</details>"));
+    assert!(tldr.contains("![diagram]"));
+
+    let compact = Pipeline::compact().apply(markdown);
+    assert!(!compact.contains("badge.svg") && !compact.contains("diagram.png"));
+    assert!(compact.contains("See [the guide](https://example.com/guide) and again."));
+    assert!(compact.contains("## Trait Implementations\n- `impl<T> Clone for Sender<T>`\n## Usage"));
+    assert!(!compact.contains("Auto Trait") && !compact.contains("Blanket") && !compact.contains("Returns a copy."));
+
+    let badges = Pipeline::parse("drop_badges").unwrap().apply(markdown);
+    assert!(!badges.contains("badge.svg") && badges.contains("diagram.png"));
+
+    let custom = Pipeline::parse("strip_sections:^Usage$, remove_auto_trait_impls").unwrap().apply(markdown);
+    assert!(!custom.contains("Call it.") && !custom.contains("Auto Trait") && custom.contains("Blanket"));
+
+    assert!(Pipeline::parse("none").unwrap().is_empty());
+    assert!(Pipeline::parse("tldr,bogus").unwrap_err().contains("unknown filter 'bogus'"));
+    assert!(Pipeline::parse("strip_sections:(").is_err());
+}
+
+#[test]
+fn test_rustdoc_item_record_reads_declaration_docs_and_stability() {
+    let html = r#"<html><body><nav class="sidebar">Sidebar</nav>
+<section id="main-content" class="content">
+<div class="main-heading"><h1>Function <a href="index.html">old_send</a></h1><span class="out-of-band"><span class="since">1.2.0</span> · <a class="src" href="../src/lib.rs.html">Source</a></span></div>
+<pre class="rust item-decl"><code>pub fn old_send&lt;T&gt;(value: T) -&gt; Result&lt;(), T&gt;</code></pre>
+<span class="item-info"><div class="stab deprecated"><span class="emoji">👎</span><span>Deprecated since 0.3.0: use <code>send</code> instead</span></div><div class="stab portability">Available on <strong>crate features <code>sync</code> and <code>rt</code></strong> only.</div></span>
+<details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary><div class="docblock"><p>Sends a <em>value</em>.</p><div class="example-wrap"><pre class="rust rust-example-rendered"><code>old_send(1);</code></pre></div></div></details>
+</section></body></html>"#;
+
+    let record = rustdoc_item_record(html, "mycrate::old_send");
+    assert_eq!(record.kind, "fn");
+    assert_eq!(record.path, "mycrate::old_send");
+    assert_eq!(record.signature.as_deref(), Some("pub fn old_send<T>(value: T) -> Result<(), T>"));
+    assert_eq!(record.docs.as_deref(), Some("Sends a *value*.\n\n```rust\nold_send(1);\n```"));
+    assert_eq!(record.since.as_deref(), Some("1.2.0"));
+    assert_eq!(
+        record.deprecated,
+        Some(Deprecation { since: Some("0.3.0".to_string()), note: Some("use send instead".to_string()) })
+    );
+    assert_eq!(record.feature_gates, vec!["sync", "rt"]);
+}
+
+#[test]
+fn test_crate_dir_name_rejects_paths_out_of_the_cache() {
+    assert_eq!(crate_dir_name("serde_json", "1.0.0").unwrap(), "serde_json-1.0.0");
+    assert_eq!(crate_dir_name("tokio", "1.0.0-rc.1").unwrap(), "tokio-1.0.0-rc.1");
+    assert!(crate_dir_name("serde", "1.0.0/../../..").is_err());
+    assert!(crate_dir_name("serde", "latest").is_err());
+    assert!(crate_dir_name("../etc", "1.0.0").is_err());
+    assert!(crate_dir_name("", "1.0.0").is_err());
+}
+
+#[test]
+fn test_collect_item_records_from_source() {
+    let dir = write_crate(
+        "records",
+        &[
+            ("src/lib.rs", "pub mod net;\n/// Add two numbers.\npub fn add(a: u32, b: u32) -> u32 { a + b }\nfn helper() {}\n"),
+            (
+                "src/net.rs",
+                "/// A client.\n#[cfg(feature = \"client\")]\npub struct Client;\n#[deprecated(since = \"0.2.0\", note = \"use Client\")]\npub fn connect() {}\n",
+            ),
+        ],
+    );
+
+    let records = collect_item_records(&dir, "mycrate", None);
+    let paths: Vec<&str> = records.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, ["mycrate::add", "mycrate::helper", "mycrate::net", "mycrate::net::Client", "mycrate::net::connect"]);
+
+    let add = &records[0];
+    assert_eq!(add.kind, "fn");
+    assert_eq!(add.signature.as_deref(), Some("fn add(a: u32, b: u32) -> u32"));
+    assert_eq!(add.docs.as_deref(), Some("Add two numbers."));
+    assert_eq!(records[1].signature, None, "private items have no public signature");
+    assert_eq!(records[3].feature_gates, vec!["client"]);
+    assert_eq!(
+        records[4].deprecated,
+        Some(Deprecation { since: Some("0.2.0".to_string()), note: Some("use Client".to_string()) })
+    );
+
+    let filters = ItemListFilters {
+        item_type: Some("fn".to_string()),
+        visibility: Some("pub".to_string()),
+        module: Some("mycrate::net".to_string()),
+    };
+    let filtered = collect_item_records(&dir, "mycrate", Some(&filters));
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].path, "mycrate::net::connect");
+
+    // The markdown listing shows the same records as the structured one
+    let list = ItemList { crate_name: "mycrate".to_string(), version: "0.1.0".to_string(), items: records };
+    let markdown = render_item_list(&list);
+    assert!(markdown.starts_with("## Modules\n- `mycrate::net`\n"), "{}", markdown);
+    assert!(markdown.contains("## Functions\n- `mycrate::add`\n- `mycrate::helper`\n- `mycrate::net::connect` (deprecated)\n"));
+    let list = ItemList { items: filtered, ..list };
+    assert_eq!(render_item_list(&list), "## Functions\n- `mycrate::net::connect` (deprecated)\n\n");
+    let list = ItemList { items: Vec::new(), ..list };
+    assert_eq!(render_item_list(&list), "No items found in mycrate 0.1.0.\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_collect_item_records_resolves_reexports() {
+    let dir = reexport_crate("records-reexports");
+    let records = collect_item_records(&dir, "serde", None);
+    let record = |path: &str| records.iter().find(|r| r.path == path);
+    assert_eq!(record("serde::connect").unwrap().signature.as_deref(), Some("fn connect(url: &str) -> Client"));
+    assert_eq!(record("serde::Client::close").unwrap().signature.as_deref(), Some("fn close(&self) -> bool"));
+    assert!(record("serde::imp::connect").is_none());
+    assert!(record("serde::methods::Client::close").is_none());
+    assert_eq!(record("serde::util::skipped").unwrap().signature, None);
+    assert_eq!(record("serde::methods::Private").unwrap().signature, None);
+
+    let filtered = |module: &str| {
+        let filters = ItemListFilters { item_type: None, visibility: Some("pub".to_string()), module: Some(module.to_string()) };
+        let records = collect_item_records(&dir, "serde", Some(&filters));
+        records.into_iter().map(|r| r.path).collect::<Vec<_>>()
+    };
+    assert_eq!(filtered("serde::nested"), ["serde::nested::deep"]);
+    assert_eq!(filtered("nested"), ["serde::nested::deep"]);
+    assert!(filtered("serde_json").is_empty());
+    assert!(filtered("serde").contains(&"serde::connect".to_string()));
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_structured_crates_io_responses() {
+    let body = r#"{"crates":[{"name":"log","max_version":"0.5.0-beta","max_stable_version":"0.4.22","description":" A logging facade \n","downloads":42,"documentation":"https://docs.rs/log","repository":null}],"meta":{"total":1}}"#;
+    let results = search_results("logger", body).unwrap();
+    assert_eq!(results.query, "logger");
+    assert_eq!(results.crates[0].name, "log");
+    assert_eq!(results.crates[0].version.as_deref(), Some("0.4.22"));
+    assert_eq!(results.crates[0].description.as_deref(), Some("A logging facade"));
+    assert_eq!(results.crates[0].repository, None);
+    assert!(search_results("logger", "<html>").is_err());
+
+    let crate_info = serde_json::json!({"crate": {"name": "log", "description": "A logging facade", "downloads": 42, "keywords": ["logging"], "categories": [], "homepage": ""}});
+    let version_info = serde_json::json!({"version": {"num": "0.4.22", "license": "MIT OR Apache-2.0", "features": {"std": [], "kv": ["value-bag"]}}});
+    let metadata = crate_metadata(&crate_info, &version_info);
+    assert_eq!(metadata.version, "0.4.22");
+    assert_eq!(metadata.license.as_deref(), Some("MIT OR Apache-2.0"));
+    assert_eq!(metadata.homepage, None);
+    assert_eq!(metadata.keywords, vec!["logging"]);
+    assert_eq!(metadata.features["kv"], vec!["value-bag"]);
+}
+
+#[test]
+fn test_prompts_fill_arguments_and_defaults() {
+    let prompts = list_prompts();
+    let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["explain_crate", "how_to", "migrate_version", "compare_crates"]);
+    let explain = &prompts[0].arguments.as_ref().unwrap();
+    assert_eq!(explain[0].required, Some(true));
+    assert_eq!(explain[1].required, Some(false));
+
+    let args = json!({ "crate_name": "axum", "old_version": "0.6.20", "new_version": "0.7.5" });
+    let text = render_prompt("migrate_version", args.as_object()).unwrap();
+    assert!(text.contains("`old_version` \"0.6.20\" and `new_version` \"0.7.5\""));
+    assert!(!text.contains('{'));
+
+    // Optional arguments fall back to their defaults
+    let args = json!({ "crate_name": "tokio" });
+    let text = render_prompt("explain_crate", args.as_object()).unwrap();
+    assert!(text.starts_with("Explain the Rust crate `tokio` (the latest release)"));
+    assert!(!text.contains("{version}") && !text.contains("{focus}"));
+
+    // Values are inserted as given, even when they look like placeholders
+    let args = json!({ "crate_name": "{version}", "version": "1.0.0" });
+    let text = render_prompt("explain_crate", args.as_object()).unwrap();
+    assert!(text.starts_with("Explain the Rust crate `{version}` (1.0.0)"));
+
+    assert!(matches!(render_prompt("how_to", args.as_object()), Err(PromptError::InvalidParameters(e)) if e == "task is required"));
+    let args = json!({ "crate_name": "tokio", "colour": "blue" });
+    assert!(matches!(render_prompt("explain_crate", args.as_object()), Err(PromptError::InvalidParameters(_))));
+    assert!(matches!(render_prompt("write_my_code", None), Err(PromptError::NotFound(_))));
+}
+
+#[test]
+fn test_metrics_render() {
+    let metrics = Metrics::default();
+    metrics.record_tool_call("lookup_crate", Duration::from_millis(30), true, 1200);
+    metrics.record_tool_call("lookup_crate", Duration::from_secs(60), false, 0);
+    metrics.record_cache(true);
+    metrics.record_cache(false);
+    metrics.record_cache(true);
+    metrics.record_cache(true);
+    metrics.record_upstream("https://docs.rs/serde/latest/serde/", Some(200));
+    metrics.record_upstream("https://docs.rs/missing/latest/missing/", Some(404));
+    metrics.record_upstream("https://crates.io/api/v1/crates/serde", None);
+    assert_eq!(metrics.cache_hit_ratio(), 0.75);
+
+    let text = metrics.render();
+    assert!(text.contains("# TYPE cratedocs_tool_duration_seconds histogram\n"));
+    assert!(text.contains("cratedocs_tool_calls_total{tool=\"lookup_crate\",outcome=\"ok\"} 1\n"));
+    assert!(text.contains("cratedocs_tool_calls_total{tool=\"lookup_crate\",outcome=\"error\"} 1\n"));
+    // Buckets are cumulative; the slow call only shows in +Inf
+    assert!(text.contains("cratedocs_tool_duration_seconds_bucket{tool=\"lookup_crate\",le=\"0.01\"} 0\n"));
+    assert!(text.contains("cratedocs_tool_duration_seconds_bucket{tool=\"lookup_crate\",le=\"0.05\"} 1\n"));
+    assert!(text.contains("cratedocs_tool_duration_seconds_bucket{tool=\"lookup_crate\",le=\"30\"} 1\n"));
+    assert!(text.contains("cratedocs_tool_duration_seconds_bucket{tool=\"lookup_crate\",le=\"+Inf\"} 2\n"));
+    assert!(text.contains("cratedocs_tool_duration_seconds_count{tool=\"lookup_crate\"} 2\n"));
+    assert!(text.contains("cratedocs_tool_response_bytes_total{tool=\"lookup_crate\"} 1200\n"));
+    assert!(text.contains("cratedocs_cache_hit_ratio 0.75\n"));
+    assert!(text.contains("cratedocs_upstream_responses_total{host=\"docs.rs\",status=\"404\"} 1\n"));
+    assert!(text.contains("cratedocs_upstream_responses_total{host=\"crates.io\",status=\"error\"} 1\n"));
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokenizers::tokenizer::Tokenizer;
+
+/// Tokenizer used when none is configured.
+pub const DEFAULT_TOKENIZER: &str = "bert-base-cased";
+
+/// Environment variable read when no tokenizer is configured explicitly.
+pub const TOKENIZER_ENV: &str = "CRATEDOCS_TOKENIZER";
+
+/// Where token counts come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenizerSource {
+    /// A model name on the Hugging Face hub, downloaded on first use.
+    Pretrained(String),
+    /// A local `tokenizer.json`, for offline hosts.
+    File(PathBuf),
+    /// No tokenizer: estimate about four bytes per token.
+    Heuristic,
+}
+
+impl TokenizerSource {
+    /// Parse `heuristic`, a path to a `.json` file, or a hub model name.
+    pub fn parse(spec: &str) -> Self {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("heuristic") || spec.is_empty() {
+            Self::Heuristic
+        } else if spec.ends_with(".json") || PathBuf::from(spec).is_file() {
+            Self::File(PathBuf::from(spec))
+        } else {
+            Self::Pretrained(spec.to_string())
+        }
+    }
+}
+
+static SOURCE: OnceLock<TokenizerSource> = OnceLock::new();
+static TOKENIZER: OnceLock<Option<Tokenizer>> = OnceLock::new();
+
+/// Choose the tokenizer for this process. Must be called before the first token count;
+/// afterwards the choice is fixed and `source` is handed back as the error.
+pub fn configure_tokenizer(source: TokenizerSource) -> Result<(), TokenizerSource> {
+    SOURCE.set(source)
+}
+
+/// The configured source, falling back to `CRATEDOCS_TOKENIZER` and then `bert-base-cased`.
+pub fn tokenizer_source() -> &'static TokenizerSource {
+    SOURCE.get_or_init(|| match std::env::var(TOKENIZER_ENV) {
+        Ok(spec) => TokenizerSource::parse(&spec),
+        Err(_) => TokenizerSource::Pretrained(DEFAULT_TOKENIZER.to_string()),
+    })
+}
+
+/// The process-wide tokenizer, loaded on first use. `None` means the heuristic is used,
+/// either by choice or because loading failed.
+fn tokenizer() -> Option<&'static Tokenizer> {
+    TOKENIZER
+        .get_or_init(|| {
+            let loaded = match tokenizer_source() {
+                TokenizerSource::Pretrained(name) => Tokenizer::from_pretrained(name, None),
+                TokenizerSource::File(path) => Tokenizer::from_file(path),
+                TokenizerSource::Heuristic => return None,
+            };
+            match loaded {
+                Ok(tokenizer) => Some(tokenizer),
+                Err(e) => {
+                    tracing::warn!("Failed to load tokenizer {:?}, estimating token counts instead: {}", tokenizer_source(), e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Count the tokens in `text` with the configured tokenizer (special tokens excluded).
+pub fn count_tokens(text: &str) -> Result<usize, tokenizers::Error> {
+    match tokenizer() {
+        Some(tokenizer) => Ok(tokenizer.encode(text, false)?.get_ids().len()),
+        None => Ok(heuristic_tokens(text)),
+    }
+}
+
+/// Token count of `text`, falling back to the heuristic if the tokenizer fails.
+pub fn estimate_tokens(text: &str) -> usize {
+    count_tokens(text).unwrap_or_else(|_| heuristic_tokens(text))
+}
+
+/// Rough token count for English prose and code: about four bytes per token.
+pub fn heuristic_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// The longest prefix of `text` within `max_tokens`, found by binary search over
+/// character boundaries so that only `O(log n)` counts are needed.
+pub fn truncate_to_tokens<'a>(text: &'a str, max_tokens: usize, count: &dyn Fn(&str) -> usize) -> &'a str {
+    if count(text) <= max_tokens {
+        return text;
+    }
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    // Invariant: the prefix ending at boundaries[low] fits, the one at boundaries[high] does not.
+    let (mut low, mut high) = (0, boundaries.len());
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if count(&text[..boundaries[mid]]) <= max_tokens {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    &text[..boundaries[low]]
+}
@@ -0,0 +1,152 @@
+use crate::tools::api_diff::render;
+use crate::tools::item_list::{
+    download_and_cache_crate, line_range, parse_crate_modules, release_spans, visit_items,
+};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::Item;
+
+/// A span of source lines belonging to the requested item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// What the span contains, e.g. "struct `client::Client`" or "impl Display for Client".
+    pub label: String,
+    pub file: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Download a crate and return the source of `item_path`, with `context_lines` of surrounding code.
+pub async fn view_source(
+    crate_name: &str,
+    version: &str,
+    item_path: &str,
+    context_lines: Option<usize>,
+) -> Result<String> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let crate_dir = Path::new(&crate_path);
+    let item_path = item_path
+        .strip_prefix(&format!("{}::", crate_name.replace('-', "_")))
+        .unwrap_or(item_path);
+
+    let spans = find_item_source(crate_dir, item_path);
+    if spans.is_empty() {
+        bail!("item `{}` not found in {} {}", item_path, crate_name, version);
+    }
+
+    let mut output = format!("# Source of `{}` ({} {})\n\n", item_path, crate_name, version);
+    output.push_str(&render_spans(crate_dir, &spans, context_lines.unwrap_or(0)));
+    Ok(output)
+}
+
+/// Download a crate and return one of its files, given relative to the crate root.
+pub async fn read_source_file(crate_name: &str, version: &str, file: &str) -> Result<String> {
+    let crate_path = download_and_cache_crate(crate_name, version).await?;
+    let crate_dir = fs::canonicalize(&crate_path)?;
+    let path = match fs::canonicalize(crate_dir.join(file)) {
+        Ok(path) if path.starts_with(&crate_dir) && path.is_file() => path,
+        _ => bail!("file `{}` not found in {} {}", file, crate_name, version),
+    };
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Locate `item_path` in an unpacked crate. Types come with all their impl blocks.
+pub fn find_item_source(crate_dir: &Path, item_path: &str) -> Vec<SourceSpan> {
+    let modules = parse_crate_modules(crate_dir);
+    let mut spans = Vec::new();
+    let mut type_names = HashSet::new();
+
+    for module in &modules {
+        visit_items(&module.ast.items, &module.module_path, &mut |item| {
+            if item.path != item_path && !item.path.ends_with(&format!("::{}", item_path)) {
+                return;
+            }
+            if matches!(item.kind, "struct" | "enum" | "union") {
+                if let Some(name) = item.path.rsplit("::").next() {
+                    type_names.insert(name.to_string());
+                }
+            }
+            spans.push(SourceSpan {
+                label: format!("{} `{}`", item.kind, item.path),
+                file: module.file.clone(),
+                start_line: item.lines.0,
+                end_line: item.lines.1,
+            });
+        });
+    }
+
+    if !type_names.is_empty() {
+        for module in &modules {
+            visit_impls(&module.ast.items, &mut |block| {
+                let syn::Type::Path(self_ty) = &*block.self_ty else { return };
+                let Some(segment) = self_ty.path.segments.last() else { return };
+                if !type_names.contains(&segment.ident.to_string()) {
+                    return;
+                }
+                let label = match &block.trait_ {
+                    Some((negative, trait_path, _)) => format!(
+                        "impl {}{} for {}",
+                        if negative.is_some() { "!" } else { "" },
+                        render(trait_path),
+                        render(&block.self_ty)
+                    ),
+                    None => format!("impl {}", render(&block.self_ty)),
+                };
+                let (start_line, end_line) = line_range(block);
+                spans.push(SourceSpan {
+                    label,
+                    file: module.file.clone(),
+                    start_line,
+                    end_line,
+                });
+            });
+        }
+    }
+
+    drop(modules);
+    release_spans();
+    spans
+}
+
+/// Render spans as line-numbered code blocks, widened by `context` lines on each side.
+pub fn render_spans(crate_dir: &Path, spans: &[SourceSpan], context: usize) -> String {
+    let mut output = String::new();
+    for span in spans {
+        let Ok(content) = fs::read_to_string(&span.file) else { continue };
+        let lines: Vec<&str> = content.lines().collect();
+        let first = span.start_line.saturating_sub(context).max(1);
+        let last = (span.end_line + context).min(lines.len());
+        let relative = span.file.strip_prefix(crate_dir).unwrap_or(&span.file);
+
+        output.push_str(&format!(
+            "## {} ({}:{}-{})\n```rust\n",
+            span.label,
+            relative.display(),
+            span.start_line,
+            span.end_line
+        ));
+        let width = last.to_string().len();
+        for number in first..=last {
+            output.push_str(&format!("{:>width$} | {}\n", number, lines[number - 1], width = width));
+        }
+        output.push_str("```\n\n");
+    }
+    output
+}
+
+/// Visit every impl block, including those inside inline modules.
+fn visit_impls<'a, F: FnMut(&'a syn::ItemImpl)>(items: &'a [Item], cb: &mut F) {
+    for item in items {
+        match item {
+            Item::Impl(block) => cb(block),
+            Item::Mod(m) => {
+                if let Some((_, inline_items)) = &m.content {
+                    visit_impls(inline_items, cb);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Comma-separated bearer tokens allowed every tool, in addition to the auth file.
+pub const TOKENS_ENV: &str = "CRATEDOCS_TOKENS";
+/// OAuth 2.0 protected resource metadata (RFC 9728), as required by MCP's authorization spec.
+pub const METADATA_PATH: &str = "/.well-known/oauth-protected-resource";
+/// Rate limits count requests per window.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// How long an introspection result is trusted before asking the issuer again.
+const INTROSPECTION_TTL: Duration = Duration::from_secs(60);
+/// Introspection results kept at most; those expiring first are dropped to make room.
+const MAX_INTROSPECTED: usize = 10_000;
+
+/// Introspection results by token, until the instant they expire.
+type Introspected = HashMap<String, (Instant, Option<Arc<TokenPolicy>>)>;
+
+/// What a token may do.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TokenPolicy {
+    /// Shown in logs and the admin session list instead of the token itself.
+    pub name: String,
+    /// Identifies the token for rate limits and session ownership. Set by [`Auth`],
+    /// unlike `name`, which is only for display and need not be unique across issuers.
+    #[serde(skip)]
+    pub id: String,
+    /// Requests per minute; unlimited if absent.
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Tools the token may call, and read as resources; all tools if absent.
+    #[serde(default)]
+    pub tools: Option<BTreeSet<String>>,
+    /// Whether the token may use the `/admin` endpoints.
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenEntry {
+    pub token: String,
+    #[serde(flatten)]
+    pub policy: TokenPolicy,
+}
+
+/// Tokens issued by an authorization server, checked with its RFC 7662 introspection endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Introspection {
+    pub url: String,
+    /// Credentials of this server at the issuer, sent with HTTP Basic authentication.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Requests per minute for each introspected token.
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Tools allowed to introspected tokens; all tools if absent.
+    #[serde(default)]
+    pub tools: Option<BTreeSet<String>>,
+}
+
+/// The auth file, e.g.
+/// `{"tokens": [{"token": "...", "name": "ci", "rate_limit": 60, "tools": ["lookup_crate"]}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<TokenEntry>,
+    /// Canonical URI of the server, e.g. `https://docs.example.com/mcp`; derived from
+    /// the `Host` header if absent.
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// Issuers of the tokens, advertised in the protected resource metadata.
+    #[serde(default)]
+    pub authorization_servers: Vec<String>,
+    #[serde(default)]
+    pub introspection: Option<Introspection>,
+}
+
+impl AuthConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let config: Self = serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        config.validate().with_context(|| format!("checking {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Reject tokens listed twice, names shared by tokens, which would make logs ambiguous,
+    /// and introspection without a `resource` to check the tokens' audience against.
+    pub fn validate(&self) -> Result<()> {
+        if self.introspection.is_some() && self.resource.is_none() {
+            anyhow::bail!("\"introspection\" needs \"resource\", the audience introspected tokens must have");
+        }
+        let mut names = HashSet::new();
+        let mut tokens = HashSet::new();
+        for entry in &self.tokens {
+            if !names.insert(entry.policy.name.as_str()) {
+                anyhow::bail!("token name {:?} is used more than once", entry.policy.name);
+            }
+            if !tokens.insert(entry.token.as_str()) {
+                anyhow::bail!("token {:?} is listed more than once", entry.policy.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add unrestricted tokens from a comma-separated list, named `env-1`, `env-2`, ...
+    /// skipping names already taken.
+    pub fn with_token_list(mut self, list: &str) -> Self {
+        let tokens = list.split(',').map(str::trim).filter(|token| !token.is_empty());
+        let mut n = 0;
+        for token in tokens {
+            let name = loop {
+                n += 1;
+                let name = format!("env-{}", n);
+                if !self.tokens.iter().any(|entry| entry.policy.name == name) {
+                    break name;
+                }
+            };
+            self.tokens.push(TokenEntry {
+                token: token.to_string(),
+                policy: TokenPolicy {
+                    name,
+                    ..Default::default()
+                },
+            });
+        }
+        self
+    }
+
+    /// Add the tokens in `$CRATEDOCS_TOKENS`, if set.
+    pub fn with_env_tokens(self) -> Self {
+        match std::env::var(TOKENS_ENV) {
+            Ok(list) => self.with_token_list(&list),
+            Err(_) => self,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty() || self.introspection.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(default)]
+    sub: Option<String>,
+    #[serde(default)]
+    client_id: Option<String>,
+    #[serde(default)]
+    aud: Option<Value>,
+    #[serde(default)]
+    exp: Option<u64>,
+}
+
+/// Bearer-token authentication for the HTTP transports. Disabled, letting every
+/// request through, unless tokens or an introspection endpoint are configured.
+#[derive(Clone, Default)]
+pub struct Auth {
+    config: Arc<AuthConfig>,
+    tokens: Arc<HashMap<String, Arc<TokenPolicy>>>,
+    client: Client,
+    introspected: Arc<Mutex<Introspected>>,
+    /// Start and request count of the current rate window, by token id.
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+    /// Derives token ids from tokens, with keys unknown outside the process.
+    hasher: RandomState,
+}
+
+impl Auth {
+    pub fn new(config: AuthConfig) -> Self {
+        let hasher = RandomState::new();
+        let tokens = config
+            .tokens
+            .iter()
+            .map(|entry| {
+                let policy = TokenPolicy {
+                    id: format!("token:{:016x}", hasher.hash_one(&entry.token)),
+                    ..entry.policy.clone()
+                };
+                (entry.token.clone(), Arc::new(policy))
+            })
+            .collect();
+        Self {
+            config: Arc::new(config),
+            tokens: Arc::new(tokens),
+            hasher,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// The policy of a valid token, or `None` for an invalid one. `Err` if the issuer
+    /// could not be asked; that is not remembered, unlike the issuer's answers.
+    pub async fn authenticate(&self, token: &str) -> Result<Option<Arc<TokenPolicy>>> {
+        if let Some(policy) = self.tokens.get(token) {
+            return Ok(Some(policy.clone()));
+        }
+        let Some(introspection) = self.config.introspection.as_ref() else { return Ok(None) };
+        {
+            let introspected = self.introspected.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((expires, policy)) = introspected.get(token) {
+                if *expires > Instant::now() {
+                    return Ok(policy.clone());
+                }
+            }
+        }
+        let (expires, policy) = self.introspect(introspection, token).await?;
+        let mut introspected = self.introspected.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        introspected.retain(|_, (expires, _)| *expires > now);
+        while introspected.len() >= MAX_INTROSPECTED {
+            let first = introspected.iter().min_by_key(|(_, (expires, _))| *expires).map(|(token, _)| token.clone());
+            match first {
+                Some(first) => introspected.remove(&first),
+                None => break,
+            };
+        }
+        introspected.insert(token.to_string(), (expires, policy.clone()));
+        Ok(policy)
+    }
+
+    async fn introspect(&self, introspection: &Introspection, token: &str) -> Result<(Instant, Option<Arc<TokenPolicy>>)> {
+        let mut request = self
+            .client
+            .post(&introspection.url)
+            .form(&[("token", token), ("token_type_hint", "access_token")]);
+        if let Some(client_id) = &introspection.client_id {
+            request = request.basic_auth(client_id, introspection.client_secret.as_ref());
+        }
+        let response: IntrospectionResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let mut ttl = INTROSPECTION_TTL;
+        if let Some(exp) = response.exp {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            ttl = ttl.min(Duration::from_secs(exp.saturating_sub(now)));
+        }
+        let expires = Instant::now() + ttl;
+        if !response.active || !self.audience_matches(response.aud.as_ref()) {
+            return Ok((expires, None));
+        }
+        // The subject and client are the identity the issuer vouches for
+        let id = match (&response.sub, &response.client_id) {
+            (None, None) => format!("introspected:{:016x}", self.hasher.hash_one(token)),
+            (sub, client_id) => format!("introspected:{:?}:{:?}", sub, client_id),
+        };
+        let name = response.sub.or(response.client_id).unwrap_or_else(|| "introspected".to_string());
+        let policy = TokenPolicy {
+            name,
+            id,
+            rate_limit: introspection.rate_limit,
+            tools: introspection.tools.clone(),
+            admin: false,
+        };
+        Ok((expires, Some(Arc::new(policy))))
+    }
+
+    /// Only tokens issued for this server's `resource` are accepted.
+    fn audience_matches(&self, aud: Option<&Value>) -> bool {
+        let (Some(resource), Some(aud)) = (&self.config.resource, aud) else { return false };
+        match aud {
+            Value::String(aud) => aud == resource,
+            Value::Array(auds) => auds.iter().any(|aud| aud.as_str() == Some(resource.as_str())),
+            _ => false,
+        }
+    }
+
+    /// Count a request against the token's rate limit; `Err` holds the time until the window resets.
+    pub fn check_rate(&self, policy: &TokenPolicy) -> Result<(), Duration> {
+        let Some(limit) = policy.rate_limit else { return Ok(()) };
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let (start, count) = windows.entry(policy.id.clone()).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(*start)));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// The canonical URI of the server, as seen by a client sending `headers`.
+    fn resource(&self, headers: &HeaderMap) -> String {
+        match &self.config.resource {
+            Some(resource) => resource.clone(),
+            None => {
+                let host = headers.get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or("localhost");
+                format!("http://{}/mcp", host)
+            }
+        }
+    }
+
+    fn metadata_url(&self, headers: &HeaderMap) -> String {
+        let resource = self.resource(headers);
+        match Url::parse(&resource) {
+            Ok(url) => format!("{}{}", url.origin().ascii_serialization(), METADATA_PATH),
+            Err(_) => METADATA_PATH.to_string(),
+        }
+    }
+
+    /// The protected resource metadata document.
+    pub fn metadata(&self, headers: &HeaderMap) -> Value {
+        json!({
+            "resource": self.resource(headers),
+            "authorization_servers": self.config.authorization_servers,
+            "bearer_methods_supported": ["header"],
+            "resource_name": "CrateDocs MCP",
+        })
+    }
+
+    /// Routes serving the metadata, which must be reachable without a token.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route(METADATA_PATH, get(metadata_handler))
+            .route(&format!("{}/mcp", METADATA_PATH), get(metadata_handler))
+            .with_state(self.clone())
+    }
+
+    fn unauthorized(&self, headers: &HeaderMap, error: Option<&str>) -> Response {
+        let mut challenge = format!("Bearer resource_metadata=\"{}\"", self.metadata_url(headers));
+        if let Some(error) = error {
+            challenge.push_str(&format!(", error=\"{}\"", error));
+        }
+        let mut response = (StatusCode::UNAUTHORIZED, "Bearer token required").into_response();
+        if let Ok(challenge) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
+async fn metadata_handler(State(auth): State<Auth>, headers: HeaderMap) -> Json<Value> {
+    Json(auth.metadata(&headers))
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Middleware rejecting requests without a valid token, or over its rate limit. The
+/// token's policy is added to the request's extensions for the handlers.
+pub async fn require_token(State(auth): State<Auth>, mut request: Request, next: Next) -> Response {
+    if !auth.is_enabled() {
+        return next.run(request).await;
+    }
+    let headers = request.headers();
+    let Some(token) = bearer_token(headers) else {
+        return auth.unauthorized(headers, None);
+    };
+    let policy = match auth.authenticate(token).await {
+        Ok(Some(policy)) => policy,
+        Ok(None) => {
+            tracing::warn!("rejected request with an invalid token");
+            return auth.unauthorized(headers, Some("invalid_token"));
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "token introspection failed");
+            return (StatusCode::SERVICE_UNAVAILABLE, "Token introspection unavailable").into_response();
+        }
+    };
+    if let Err(retry_after) = auth.check_rate(&policy) {
+        tracing::warn!(token = %policy.name, "rate limit exceeded");
+        let retry_after = retry_after.as_secs().max(1).to_string();
+        return (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after)], "Rate limit exceeded")
+            .into_response();
+    }
+    request.extensions_mut().insert(policy);
+    next.run(request).await
+}
+
+/// Middleware for `/admin` routes: only admin tokens. Requests without a token's policy,
+/// as when authentication is disabled, are refused too.
+pub async fn require_admin(policy: Option<Extension<Arc<TokenPolicy>>>, request: Request, next: Next) -> Response {
+    match policy {
+        Some(Extension(policy)) if policy.admin => next.run(request).await,
+        _ => (StatusCode::FORBIDDEN, "Admin token required").into_response(),
+    }
+}
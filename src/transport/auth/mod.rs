@@ -0,0 +1,5 @@
+mod auth;
+pub use auth::*;
+
+#[cfg(test)]
+mod tests;
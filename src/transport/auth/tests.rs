@@ -0,0 +1,282 @@
+use crate::transport::auth::{require_admin, Auth, AuthConfig, Introspection, TokenPolicy, METADATA_PATH};
+use crate::transport::http_sse_server::App;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Request, Response, StatusCode};
+use axum::{middleware, routing::{get, post}, Form, Json, Router};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn config() -> AuthConfig {
+    serde_json::from_value(json!({
+        "tokens": [
+            { "token": "admin-token", "name": "admin", "admin": true },
+            { "token": "docs-token", "name": "docs", "rate_limit": 2, "tools": ["lookup_crate"] }
+        ],
+        "resource": "https://docs.example.com/mcp",
+        "authorization_servers": ["https://auth.example.com"]
+    }))
+    .unwrap()
+}
+
+fn request(method: &str, uri: &str, token: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("accept", "application/json")
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    builder.body(body.map_or_else(Body::empty, |body| Body::from(body.to_string()))).unwrap()
+}
+
+async fn send(app: &App, request: Request<Body>) -> Response<Body> {
+    app.router().oneshot(request).await.unwrap()
+}
+
+async fn json_body(response: Response<Body>) -> Value {
+    let body = response.into_body().into_data_stream().next().await.unwrap().unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn initialize() -> Value {
+    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })
+}
+
+#[test]
+fn test_config_parsing() {
+    let config = config().with_token_list("one, ,two");
+    assert!(config.is_enabled());
+    assert_eq!(config.tokens.len(), 4);
+    assert_eq!(config.tokens[1].policy.rate_limit, Some(2));
+    assert_eq!(config.tokens[1].policy.tools, Some(BTreeSet::from(["lookup_crate".to_string()])));
+    assert_eq!(config.tokens[3].token, "two");
+    assert_eq!(config.tokens[3].policy, TokenPolicy { name: "env-2".to_string(), ..Default::default() });
+    assert!(!AuthConfig::default().with_token_list("").is_enabled());
+}
+
+#[test]
+fn test_config_validation() {
+    assert!(config().validate().is_ok());
+    let introspection = Introspection {
+        url: "https://auth.example.com/introspect".to_string(),
+        client_id: None,
+        client_secret: None,
+        rate_limit: None,
+        tools: None,
+    };
+    let unchecked_audience = AuthConfig { introspection: Some(introspection), ..Default::default() };
+    assert!(unchecked_audience.validate().is_err());
+    let mut duplicate = config();
+    duplicate.tokens[1].policy.name = "admin".to_string();
+    assert!(duplicate.validate().is_err());
+    let mut duplicate = config();
+    duplicate.tokens[1].token = "admin-token".to_string();
+    assert!(duplicate.validate().is_err());
+
+    // Names of environment tokens do not collide with the file's
+    let mut config = config();
+    config.tokens[1].policy.name = "env-1".to_string();
+    let config = config.with_token_list("one");
+    assert_eq!(config.tokens[2].policy.name, "env-2");
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_rate_limit() {
+    let auth = Auth::new(config());
+    let policy = TokenPolicy { name: "docs".to_string(), id: "a".to_string(), rate_limit: Some(2), ..Default::default() };
+    assert!(auth.check_rate(&policy).is_ok());
+    assert!(auth.check_rate(&policy).is_ok());
+    let retry_after = auth.check_rate(&policy).unwrap_err();
+    assert!(retry_after.as_secs() <= 60);
+    // Other tokens have their own window, even with the same name
+    let other = TokenPolicy { name: "docs".to_string(), id: "b".to_string(), rate_limit: Some(1), ..Default::default() };
+    assert!(auth.check_rate(&other).is_ok());
+}
+
+#[tokio::test]
+async fn test_disabled_auth_lets_requests_through() {
+    let app = App::new();
+    let response = send(&app, request("POST", "/mcp", None, Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_requests_need_a_valid_token() {
+    let app = App::new().with_auth(Auth::new(config()));
+
+    let response = send(&app, request("POST", "/mcp", None, Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        "Bearer resource_metadata=\"https://docs.example.com/.well-known/oauth-protected-resource\""
+    );
+
+    let response = send(&app, request("GET", "/sse", Some("wrong"), None)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenge = response.headers()[header::WWW_AUTHENTICATE].to_str().unwrap();
+    assert!(challenge.ends_with("error=\"invalid_token\""));
+
+    let response = send(&app, request("POST", "/mcp", Some("admin-token"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_metadata_is_public() {
+    let app = App::new().with_auth(Auth::new(config()));
+    for path in [METADATA_PATH.to_string(), format!("{}/mcp", METADATA_PATH)] {
+        let response = send(&app, request("GET", &path, None, None)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let metadata = json_body(response).await;
+        assert_eq!(metadata["resource"], "https://docs.example.com/mcp");
+        assert_eq!(metadata["authorization_servers"], json!(["https://auth.example.com"]));
+        assert_eq!(metadata["bearer_methods_supported"], json!(["header"]));
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_requests_get_429() {
+    let app = App::new().with_auth(Auth::new(config()));
+    for _ in 0..2 {
+        let response = send(&app, request("GET", METADATA_PATH, Some("docs-token"), None)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(&app, request("POST", "/mcp", Some("docs-token"), Some(initialize()))).await;
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let response = send(&app, request("POST", "/mcp", Some("docs-token"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+    // The limit is per token
+    let response = send(&app, request("POST", "/mcp", Some("admin-token"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_need_admin_token() {
+    let app = App::new().with_auth(Auth::new(config()));
+    let response = send(&app, request("GET", "/admin/sessions", Some("docs-token"), None)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = send(&app, request("GET", "/admin/sessions", Some("admin-token"), None)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Requests that skipped authentication have no policy and are refused
+    let admin = Router::new()
+        .route("/admin", get(|| async { "ok" }))
+        .route_layer(middleware::from_fn(require_admin));
+    let response = admin.oneshot(request("GET", "/admin", None, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_sessions_belong_to_their_token() {
+    let app = App::new().with_auth(Auth::new(config()));
+    let response = send(&app, request("POST", "/mcp", Some("docs-token"), Some(initialize()))).await;
+    let session = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+
+    let call = json!({
+        "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+        "params": { "name": "view_source", "arguments": { "crate_name": "serde" } }
+    });
+    let mut other = request("POST", "/mcp", Some("admin-token"), Some(call.clone()));
+    other.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+    assert_eq!(send(&app, other).await.status(), StatusCode::NOT_FOUND);
+
+    // The session's own token is held to its tool allow-list
+    let mut own = request("POST", "/mcp", Some("docs-token"), Some(call));
+    own.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+    let response = json_body(send(&app, own).await).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("view_source is not allowed"));
+
+    let sessions = app.registry.list();
+    assert_eq!(sessions[0].owner.as_deref(), Some("docs"));
+}
+
+/// A stand-in for an authorization server's introspection endpoint, counting its requests.
+async fn spawn_issuer(requests: Arc<AtomicUsize>) -> String {
+    async fn introspect(
+        State(requests): State<Arc<AtomicUsize>>,
+        headers: HeaderMap,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        requests.fetch_add(1, Ordering::Relaxed);
+        // "cratedocs:secret"
+        if headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) != Some("Basic Y3JhdGVkb2NzOnNlY3JldA==") {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Ok(Json(match form.get("token").map(String::as_str) {
+            Some("issued") => json!({ "active": true, "sub": "alice", "aud": "https://docs.example.com/mcp" }),
+            Some("other-audience") => json!({ "active": true, "sub": "bob", "aud": "https://elsewhere.example.com" }),
+            Some("anonymous-1" | "anonymous-2") => json!({ "active": true, "aud": "https://docs.example.com/mcp" }),
+            Some("no-audience") => json!({ "active": true, "sub": "carol" }),
+            Some("unavailable") => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+            _ => json!({ "active": false }),
+        }))
+    }
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let issuer = Router::new().route("/introspect", post(introspect)).with_state(requests);
+        axum::serve(listener, issuer).await.unwrap();
+    });
+    format!("http://{}/introspect", address)
+}
+
+#[tokio::test]
+async fn test_introspected_tokens() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let config = AuthConfig {
+        resource: Some("https://docs.example.com/mcp".to_string()),
+        introspection: Some(Introspection {
+            url: spawn_issuer(requests.clone()).await,
+            client_id: Some("cratedocs".to_string()),
+            client_secret: Some("secret".to_string()),
+            rate_limit: Some(10),
+            tools: None,
+        }),
+        ..Default::default()
+    };
+    assert!(config.validate().is_ok());
+    let auth = Auth::new(config);
+
+    let policy = auth.authenticate("issued").await.unwrap().unwrap();
+    assert_eq!(policy.name, "alice");
+    assert_eq!(policy.rate_limit, Some(10));
+    assert!(!policy.admin);
+    // Cached after the first lookup
+    assert_eq!(auth.authenticate("issued").await.unwrap(), Some(policy));
+
+    // Tokens the issuer names nobody for are told apart by the token
+    let first = auth.authenticate("anonymous-1").await.unwrap().unwrap();
+    let second = auth.authenticate("anonymous-2").await.unwrap().unwrap();
+    assert_eq!(first.name, second.name);
+    assert_ne!(first.id, second.id);
+
+    assert_eq!(auth.authenticate("other-audience").await.unwrap(), None);
+    assert_eq!(auth.authenticate("no-audience").await.unwrap(), None);
+
+    // Inactive tokens are remembered like active ones
+    let before = requests.load(Ordering::Relaxed);
+    assert_eq!(auth.authenticate("revoked").await.unwrap(), None);
+    assert_eq!(auth.authenticate("revoked").await.unwrap(), None);
+    assert_eq!(requests.load(Ordering::Relaxed), before + 1);
+
+    // Failed introspections are not, so a valid token is not locked out by an outage
+    let before = requests.load(Ordering::Relaxed);
+    assert!(auth.authenticate("unavailable").await.is_err());
+    assert!(auth.authenticate("unavailable").await.is_err());
+    assert_eq!(requests.load(Ordering::Relaxed), before + 2);
+
+    let app = App::new().with_auth(auth);
+    let response = send(&app, request("POST", "/mcp", Some("issued"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&app, request("POST", "/mcp", Some("unavailable"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = send(&app, request("POST", "/mcp", Some("revoked"), Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
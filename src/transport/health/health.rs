@@ -0,0 +1,130 @@
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use reqwest::Client;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::tools::item_list::CACHE_DIR;
+
+/// Fetched by `/readyz` to check that crates.io can be reached.
+pub const DEFAULT_UPSTREAM: &str = "https://index.crates.io/config.json";
+/// How long a readiness result is reused, so frequent probes do not each reach crates.io.
+const READINESS_TTL: Duration = Duration::from_secs(10);
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of one readiness check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn from_result<E: std::fmt::Display>(result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => Self { ok: true, error: None },
+            Err(e) => Self {
+                ok: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Crate sources can be downloaded into the cache directory.
+    pub cache_dir: Check,
+    /// crates.io answers.
+    pub upstream: Check,
+}
+
+/// Liveness and readiness probes for running the server as a shared service.
+#[derive(Clone)]
+pub struct Health {
+    cache_dir: PathBuf,
+    upstream: String,
+    client: Client,
+    last: Arc<Mutex<Option<(Instant, Readiness)>>>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new(CACHE_DIR, DEFAULT_UPSTREAM)
+    }
+}
+
+impl Health {
+    pub fn new(cache_dir: impl Into<PathBuf>, upstream: impl Into<String>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            upstream: upstream.into(),
+            client: Client::new(),
+            last: Default::default(),
+        }
+    }
+
+    /// Create the cache directory if needed and write a file into it.
+    pub fn check_cache_dir(&self) -> Check {
+        let probe = self.cache_dir.join(format!(".readyz-{}", std::process::id()));
+        let result = std::fs::create_dir_all(&self.cache_dir)
+            .and_then(|_| std::fs::write(&probe, b"ok"))
+            .and_then(|_| std::fs::remove_file(&probe));
+        Check::from_result(result.map_err(|e| format!("{}: {}", self.cache_dir.display(), e)))
+    }
+
+    pub async fn check_upstream(&self) -> Check {
+        let response = self
+            .client
+            .get(&self.upstream)
+            .header("User-Agent", "CrateDocs/0.1.0 (https://github.com/d6e/cratedocs-mcp)")
+            .timeout(UPSTREAM_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        Check::from_result(response.map(|_| ()))
+    }
+
+    /// Run both checks, or return the result of a recent run.
+    pub async fn readiness(&self) -> Readiness {
+        if let Some((checked, readiness)) = &*self.last.lock().unwrap_or_else(|e| e.into_inner()) {
+            if checked.elapsed() < READINESS_TTL {
+                return readiness.clone();
+            }
+        }
+        let cache_dir = self.check_cache_dir();
+        let upstream = self.check_upstream().await;
+        let readiness = Readiness {
+            ready: cache_dir.ok && upstream.ok,
+            cache_dir,
+            upstream,
+        };
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), readiness.clone()));
+        readiness
+    }
+
+    /// `/healthz` and `/readyz`, which must be reachable without a token.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/healthz", get(healthz_handler))
+            .route("/readyz", get(readyz_handler))
+            .with_state(self.clone())
+    }
+}
+
+/// The process is up and serving requests.
+async fn healthz_handler() -> &'static str {
+    "ok"
+}
+
+async fn readyz_handler(State(health): State<Health>) -> (StatusCode, Json<Readiness>) {
+    let readiness = health.readiness().await;
+    let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    if !readiness.ready {
+        tracing::warn!(?readiness, "not ready");
+    }
+    (status, Json(readiness))
+}
@@ -0,0 +1,5 @@
+mod health;
+pub use health::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,98 @@
+use crate::transport::auth::{Auth, AuthConfig};
+use crate::transport::health::Health;
+use crate::transport::http_sse_server::App;
+use axum::body::Body;
+use axum::http::{header, Request, Response, StatusCode};
+use axum::{routing::get, Router};
+use futures::StreamExt;
+use serde_json::Value;
+use std::path::PathBuf;
+use tower::ServiceExt;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cratedocs-health-{}-{}", name, std::process::id()))
+}
+
+/// A stand-in for crates.io.
+async fn spawn_upstream() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/config.json", get(|| async { "{}" }))).await.unwrap();
+    });
+    format!("http://{}/config.json", address)
+}
+
+async fn get_path(app: &App, path: &str) -> Response<Body> {
+    let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+    app.router().oneshot(request).await.unwrap()
+}
+
+async fn body_text(response: Response<Body>) -> String {
+    let mut body = String::new();
+    let mut stream = response.into_body().into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        body.push_str(std::str::from_utf8(&chunk.unwrap()).unwrap());
+    }
+    body
+}
+
+#[tokio::test]
+async fn test_healthz() {
+    let response = get_path(&App::new(), "/healthz").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, "ok");
+}
+
+#[tokio::test]
+async fn test_readyz() {
+    let dir = scratch_dir("ready");
+    let app = App::new().with_health(Health::new(&dir, spawn_upstream().await));
+    let response = get_path(&app, "/readyz").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let readiness: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(readiness["ready"], true);
+    assert_eq!(readiness["cache_dir"]["ok"], true);
+    assert!(dir.is_dir());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_readyz_reports_failed_checks() {
+    // A file where the cache directory should be
+    let file = scratch_dir("not-a-dir");
+    std::fs::write(&file, b"").unwrap();
+    let health = Health::new(file.join("cache"), "http://127.0.0.1:1/config.json");
+
+    assert!(!health.check_cache_dir().ok);
+    let readiness = health.readiness().await;
+    assert!(!readiness.ready);
+    assert!(!readiness.upstream.ok);
+    assert!(readiness.upstream.error.is_some());
+
+    let app = App::new().with_health(health);
+    assert_eq!(get_path(&app, "/readyz").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let _ = std::fs::remove_file(file);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    assert_eq!(get_path(&App::new(), "/metrics").await.status(), StatusCode::NOT_FOUND);
+    let app = App::new().with_admin_open(true);
+    let response = get_path(&app, "/metrics").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    let text = body_text(response).await;
+    assert!(text.contains("# TYPE cratedocs_cache_hit_ratio gauge"));
+    assert!(text.contains("cratedocs_active_sessions{transport=\"sse\"} 0"));
+    assert!(text.contains("cratedocs_active_sessions{transport=\"streamable-http\"} 0"));
+    assert!(text.contains("cratedocs_active_sessions{transport=\"websocket\"} 0"));
+}
+
+#[tokio::test]
+async fn test_probes_skip_authentication() {
+    let app = App::new().with_auth(Auth::new(AuthConfig::default().with_token_list("secret")));
+    assert_eq!(get_path(&app, "/healthz").await.status(), StatusCode::OK);
+    // Metrics are for operators
+    assert_eq!(get_path(&app, "/metrics").await.status(), StatusCode::UNAUTHORIZED);
+}
@@ -1,10 +1,14 @@
 use axum::{
     body::Body,
-    extract::{Query, State},
-    http::StatusCode,
-    response::sse::{Event, Sse},
-    routing::get,
-    Router,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse,
+    },
+    routing::{delete, get},
+    Extension, Json, Router,
 };
 use futures::{Stream, StreamExt, TryStreamExt};
 use mcp_server::{ByteTransport, Server};
@@ -15,40 +19,137 @@ use tokio_util::codec::FramedRead;
 // Tests in ../tests.rs
 
 use anyhow::Result;
-use mcp_server::router::RouterService;
-use crate::{transport::jsonrpc_frame_codec::JsonRpcFrameCodec, tools::DocRouter};
+use crate::{transport::jsonrpc_frame_codec::JsonRpcFrameCodec, tools::{DocRouter, DocService}};
+use crate::tools::docs::subscriptions::DEFAULT_POLL_INTERVAL;
+use crate::tools::metrics;
+use crate::transport::health::Health;
+use crate::transport::auth::{require_admin, require_token, Auth, TokenPolicy};
+use crate::transport::notifications::with_notifications;
+use crate::transport::origin::{validate_origin, OriginPolicy};
+use crate::transport::sessions::{new_session_id, SessionId, SessionInfo, SessionLimits, SessionRegistry};
+use crate::transport::streamable_http::StreamableHttp;
+use crate::transport::websocket::WebSocketTransport;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::{
     io::{self, AsyncWriteExt},
     sync::Mutex,
 };
 
 type C2SWriter = Arc<Mutex<io::WriteHalf<io::SimplexStream>>>;
-type SessionId = Arc<str>;
-
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct App {
     pub txs: Arc<tokio::sync::RwLock<HashMap<SessionId, C2SWriter>>>,
+    /// Configured router whose cache and HTTP client every session shares.
+    pub doc_router: DocRouter,
+    /// How often each session checks its subscribed crates for new releases.
+    pub poll_interval: Duration,
+    /// Sessions of the Streamable HTTP endpoint, served next to the SSE routes.
+    pub streamable: StreamableHttp,
+    /// Sessions of the WebSocket endpoint, one per connection.
+    pub websocket: WebSocketTransport,
+    /// Limits and activity of the sessions of every transport.
+    pub registry: SessionRegistry,
+    /// Bearer-token authentication of every route but the protected resource metadata.
+    pub auth: Auth,
+    /// Browser origins allowed to call the server, with CORS headers for them.
+    pub origins: OriginPolicy,
+    /// Liveness and readiness probes, served without authentication.
+    pub health: Health,
+    /// Serve `/admin` and `/metrics` even though authentication is disabled, e.g. on a
+    /// loopback address nobody else can reach. Off by default, as session ids listed by
+    /// `/admin/sessions` let their holder use the sessions.
+    pub admin_open: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        let registry = SessionRegistry::default();
         Self {
             txs: Default::default(),
+            doc_router: DocRouter::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            streamable: StreamableHttp::new().with_registry(registry.clone()),
+            websocket: WebSocketTransport::new().with_registry(registry.clone()),
+            registry,
+            auth: Auth::default(),
+            origins: OriginPolicy::default(),
+            health: Health::default(),
+            admin_open: false,
         }
     }
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.streamable = self.streamable.with_poll_interval(poll_interval);
+        self.websocket = self.websocket.with_poll_interval(poll_interval);
+        self
+    }
+    /// Serve every session of every transport from `router`, e.g. one configured with `--tldr`.
+    pub fn with_doc_router(mut self, router: DocRouter) -> Self {
+        self.streamable = self.streamable.with_router(router.clone());
+        self.websocket = self.websocket.with_router(router.clone());
+        self.doc_router = router;
+        self
+    }
+    /// Replace the session limits shared by every transport.
+    pub fn with_session_limits(mut self, limits: SessionLimits) -> Self {
+        self.registry = SessionRegistry::new(limits);
+        self.streamable = self.streamable.with_registry(self.registry.clone());
+        self.websocket = self.websocket.with_registry(self.registry.clone());
+        self
+    }
+    pub fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.websocket = self.websocket.with_ping_interval(ping_interval);
+        self
+    }
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+    pub fn with_origins(mut self, origins: OriginPolicy) -> Self {
+        self.origins = origins;
+        self
+    }
+    pub fn with_health(mut self, health: Health) -> Self {
+        self.health = health;
+        self
+    }
+    pub fn with_admin_open(mut self, admin_open: bool) -> Self {
+        self.admin_open = admin_open;
+        self
+    }
+    /// Whether `/admin` and `/metrics` are served: only to admin tokens, or to anyone if opened explicitly.
+    pub fn serves_admin(&self) -> bool {
+        self.auth.is_enabled() || self.admin_open
+    }
     pub fn router(&self) -> Router {
-        Router::new()
-            .route("/sse", get(sse_handler).post(post_event_handler))
+        let admin = Router::new()
+            .route("/metrics", get(metrics_handler))
+            .route("/admin/sessions", get(list_sessions_handler))
+            .route("/admin/sessions/{session_id}", delete(close_session_handler));
+        // Opened with --admin-open if authentication is disabled, as there are no admin tokens then
+        let admin = if self.auth.is_enabled() { admin.route_layer(middleware::from_fn(require_admin)) } else { admin };
+        let routes = Router::new().route("/sse", get(sse_handler).post(post_event_handler).delete(delete_session_handler));
+        let routes = if self.serves_admin() { routes.merge(admin) } else { routes };
+        routes
             .with_state(self.clone())
+            .merge(self.streamable.router())
+            .merge(self.websocket.router())
+            .layer(middleware::from_fn_with_state(self.auth.clone(), require_token))
+            .merge(self.auth.router())
+            .merge(self.health.router())
+            // CORS answers preflight requests before they reach authentication
+            .layer(self.origins.cors_layer())
+            .layer(middleware::from_fn_with_state(self.origins.clone(), validate_origin))
     }
 }
 
-fn session_id() -> SessionId {
-    let id = format!("{:016x}", rand::random::<u128>());
-    Arc::from(id)
-}
-
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PostEventQuery {
@@ -58,12 +159,12 @@ pub struct PostEventQuery {
 
 async fn post_event_handler(
     State(app): State<App>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
     Query(query_params): Query<PostEventQuery>,
     body: Body,
 ) -> Result<StatusCode, StatusCode> {
     tracing::debug!(?query_params, "Received POST request");
     const BODY_BYTES_LIMIT: usize = 1 << 22;
-    const BUFFER_SIZE: usize = 1 << 12; // For new sessions
 
     let (session_id_arc, c2s_writer_for_body): (SessionId, C2SWriter) =
         match query_params.session_id {
@@ -72,52 +173,24 @@ async fn post_event_handler(
                 // Convert String to Arc<str> for map lookup
                 let session_arc: SessionId = Arc::from(id_str.as_str());
                 let rg = app.txs.read().await;
+                let owner = policy.as_deref().map(Arc::as_ref);
                 match rg.get(&session_arc) {
-                    Some(writer) => {
+                    // Sessions opened with another token look like unknown ones
+                    Some(writer) if app.registry.is_owner(&session_arc, owner) => {
                         tracing::debug!(session_id = %session_arc, "Found existing session writer");
+                        app.registry.touch(&session_arc);
                         (session_arc, writer.clone())
                     }
-                    None => {
+                    _ => {
                         tracing::warn!(session_id = %session_arc, "sessionId provided but not found in active sessions");
                         return Err(StatusCode::NOT_FOUND);
                     }
                 }
             }
             None => {
-                tracing::info!("sessionId not provided, creating new session for POST request");
-                let new_session_id_arc = session_id(); // fn session_id() -> Arc<str>
-                tracing::info!(new_session_id = %new_session_id_arc, "Generated new session ID");
-
-                let (c2s_read, c2s_write_half) = tokio::io::simplex(BUFFER_SIZE);
-                // s2c_read/write are also needed for the ByteTransport and Server::run
-                // _s2c_read is not directly used by this POST handler but needed for the spawned server task.
-                let (_s2c_read, s2c_write_half) = tokio::io::simplex(BUFFER_SIZE);
-
-                let new_c2s_writer_for_map = Arc::new(Mutex::new(c2s_write_half));
-                app.txs
-                    .write()
-                    .await
-                    .insert(new_session_id_arc.clone(), new_c2s_writer_for_map.clone());
-                tracing::info!(session_id = %new_session_id_arc, "Inserted new session writer into app.txs");
-
-                // Spawn the server task for the new session
-                let app_clone = app.clone();
-                let task_session_id = new_session_id_arc.clone();
-                tokio::spawn(async move {
-                    let router = RouterService(DocRouter::new());
-                    let server = Server::new(router);
-                    let bytes_transport = ByteTransport::new(c2s_read, s2c_write_half);
-                    tracing::info!(session_id = %task_session_id, "Spawning server task for new POST session");
-                    let _result = server
-                        .run(bytes_transport)
-                        .await
-                        .inspect_err(|e| {
-                            tracing::error!(?e, session_id = %task_session_id, "Server run error for new POST session")
-                        });
-                    app_clone.txs.write().await.remove(&task_session_id);
-                    tracing::info!(session_id = %task_session_id, "Cleaned up new POST session from app.txs after server task completion");
-                });
-                (new_session_id_arc, new_c2s_writer_for_map)
+                // Responses are only delivered over an SSE stream, so a session must exist first
+                tracing::warn!("POST without sessionId; open /sse first");
+                return Err(StatusCode::BAD_REQUEST);
             }
         };
 
@@ -161,10 +234,19 @@ async fn post_event_handler(
     Ok(StatusCode::ACCEPTED)
 }
 
-async fn sse_handler(State(app): State<App>) -> Sse<impl Stream<Item = Result<Event, io::Error>>> {
+async fn sse_handler(
+    State(app): State<App>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, io::Error>>>, StatusCode> {
     // it's 4KB
     const BUFFER_SIZE: usize = 1 << 12;
-    let session = session_id();
+    let session = new_session_id();
+    let owner = policy.as_deref().cloned();
+    let allowed_tools = policy.as_ref().and_then(|policy| policy.tools.clone()).map(Arc::new);
+    let closed = app.registry.open_as(session.clone(), "sse", owner).map_err(|e| {
+        tracing::warn!(%session, %e, "rejecting sse connection");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
     tracing::info!(%session, "sse connection");
     let (c2s_read, c2s_write) = tokio::io::simplex(BUFFER_SIZE);
     let (s2c_read, s2c_write) = tokio::io::simplex(BUFFER_SIZE);
@@ -175,18 +257,26 @@ async fn sse_handler(State(app): State<App>) -> Sse<impl Stream<Item = Result<Ev
     {
         let app_clone = app.clone();
         let session = session.clone();
+        let closed = closed.clone();
         tokio::spawn(async move {
-            let router = RouterService(DocRouter::new());
-            let server = Server::new(router);
-            let bytes_transport = ByteTransport::new(c2s_read, s2c_write);
-            let _result = server
-                .run(bytes_transport)
-                .await
-                .inspect_err(|e| tracing::error!(?e, "server run error"));
+            let (router, notifications) =
+                DocService::with_subscriptions(app_clone.doc_router.for_session(), app_clone.poll_interval);
+            let server = Server::new(router.with_allowed_tools(allowed_tools));
+            let bytes_transport = ByteTransport::new(c2s_read, with_notifications(s2c_write, notifications));
+            tokio::select! {
+                result = server.run(bytes_transport) => {
+                    let _ = result.inspect_err(|e| tracing::error!(?e, "server run error"));
+                }
+                _ = closed.cancelled() => {}
+            }
             app_clone.txs.write().await.remove(&session);
+            app_clone.registry.close(&session);
+            tracing::info!(%session, "sse session closed");
         });
     }
 
+    // Dropped when the client disconnects, which closes the session
+    let disconnect_guard = closed.clone().drop_guard();
     let stream = futures::stream::once(futures::future::ok(
         Event::default()
             .event("endpoint")
@@ -199,6 +289,57 @@ async fn sse_handler(State(app): State<App>) -> Sse<impl Stream<Item = Result<Ev
                 Ok(message) => futures::future::ok(Event::default().event("message").data(message)),
                 Err(e) => futures::future::err(io::Error::new(io::ErrorKind::InvalidData, e)),
             }),
-    );
-    Sse::new(stream)
-}
\ No newline at end of file
+    )
+    .take_until(closed.cancelled_owned())
+    .map(move |event| {
+        let _ = &disconnect_guard;
+        event
+    });
+    Ok(Sse::new(stream))
+}
+
+/// End a session explicitly: `DELETE /sse?sessionId=...`.
+async fn delete_session_handler(
+    State(app): State<App>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+    Query(query_params): Query<PostEventQuery>,
+) -> StatusCode {
+    let Some(session_id) = query_params.session_id else {
+        return StatusCode::BAD_REQUEST;
+    };
+    // Sessions opened with another token look like unknown ones
+    let owner = policy.as_deref().map(Arc::as_ref);
+    if !app.registry.is_owner(&session_id, owner) {
+        return StatusCode::NOT_FOUND;
+    }
+    if app.registry.close(&session_id) || app.txs.write().await.remove(session_id.as_str()).is_some() {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Prometheus metrics: tool calls, cache, upstream responses and open sessions.
+async fn metrics_handler(State(app): State<App>) -> impl IntoResponse {
+    let mut body = metrics::metrics().render();
+    metrics::header(&mut body, "cratedocs_active_sessions", "gauge", "Open sessions by transport.");
+    let sessions = app.registry.list();
+    for transport in ["sse", "streamable-http", "websocket"] {
+        let count = sessions.iter().filter(|session| session.transport == transport).count();
+        body.push_str(&format!("cratedocs_active_sessions{{transport=\"{}\"}} {}\n", transport, count));
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// Active sessions of every transport.
+async fn list_sessions_handler(State(app): State<App>) -> Json<Vec<SessionInfo>> {
+    Json(app.registry.list())
+}
+
+async fn close_session_handler(State(app): State<App>, Path(session_id): Path<String>) -> StatusCode {
+    if app.registry.close(&session_id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
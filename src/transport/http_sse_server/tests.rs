@@ -50,4 +50,91 @@ async fn test_session_management() {
         assert_eq!(txs.len(), 1);
         assert!(txs.contains_key(&test_id));
     }
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_post_without_session_is_rejected() {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    let app = App::new();
+    let request = Request::builder()
+        .method("POST")
+        .uri("/sse")
+        .body(Body::from(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#))
+        .unwrap();
+    let response = app.router().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(app.txs.read().await.is_empty());
+    assert!(app.registry.is_empty());
+}
+
+#[tokio::test]
+async fn test_admin_sessions_list_and_close() {
+    use crate::transport::sessions::SessionLimits;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use futures::StreamExt;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    // Without authentication the admin routes are only served when opened explicitly
+    let list = Request::builder().uri("/admin/sessions").body(Body::empty()).unwrap();
+    assert_eq!(App::new().router().oneshot(list).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    let app = App::new()
+        .with_session_limits(SessionLimits {
+            max_sessions: 1,
+            idle_timeout: Duration::from_secs(60),
+        })
+        .with_admin_open(true);
+    let initialize = |session: Option<&str>| {
+        let mut builder = Request::builder().method("POST").uri("/mcp").header("accept", "application/json");
+        if let Some(session) = session {
+            builder = builder.header("mcp-session-id", session);
+        }
+        builder
+            .body(Body::from(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#))
+            .unwrap()
+    };
+
+    let response = app.router().oneshot(initialize(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let session = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+
+    // The limit is shared by both transports
+    let response = app.router().oneshot(initialize(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let sse = Request::builder().uri("/sse").body(Body::empty()).unwrap();
+    assert_eq!(app.router().oneshot(sse).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let list = Request::builder().uri("/admin/sessions").body(Body::empty()).unwrap();
+    let body = app.router().oneshot(list).await.unwrap().into_body().into_data_stream().next().await.unwrap().unwrap();
+    let sessions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(sessions[0]["id"], session.as_str());
+    assert_eq!(sessions[0]["transport"], "streamable-http");
+
+    let close = Request::builder()
+        .method("DELETE")
+        .uri(format!("/admin/sessions/{}", session))
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.router().oneshot(close).await.unwrap().status(), StatusCode::NO_CONTENT);
+    assert!(app.registry.is_empty());
+
+    // The transport forgets the closed session
+    for _ in 0..50 {
+        if app.streamable.session_count().await == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(app.streamable.session_count().await, 0);
+    let ping = Request::builder()
+        .method("POST")
+        .uri("/mcp")
+        .header("mcp-session-id", session.as_str())
+        .body(Body::from(r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#))
+        .unwrap();
+    assert_eq!(app.router().oneshot(ping).await.unwrap().status(), StatusCode::NOT_FOUND);
+}
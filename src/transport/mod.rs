@@ -1,2 +1,12 @@
+pub mod auth;
+pub mod health;
 pub mod http_sse_server;
-pub mod jsonrpc_frame_codec;
\ No newline at end of file
+pub mod jsonrpc_frame_codec;
+pub mod notifications;
+pub mod origin;
+pub mod sessions;
+pub mod streamable_http;
+pub mod tls;
+#[cfg(unix)]
+pub mod unix_socket;
+pub mod websocket;
\ No newline at end of file
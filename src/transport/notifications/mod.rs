@@ -0,0 +1,5 @@
+mod notifications;
+pub use notifications::with_notifications;
+
+#[cfg(test)]
+mod tests;
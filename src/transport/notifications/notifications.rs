@@ -0,0 +1,43 @@
+use tokio::io::{self, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+const BUFFER_SIZE: usize = 1 << 12;
+
+/// Interleave server-initiated notifications with the server's responses.
+///
+/// `Server::run` owns its writer, so hand it the returned writer instead: whole
+/// lines written to it, and each notification, are forwarded to `writer` as
+/// newline-delimited JSON-RPC messages.
+pub fn with_notifications<W>(
+    writer: W,
+    mut notifications: mpsc::UnboundedReceiver<String>,
+) -> io::DuplexStream
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    // Unlike a simplex pipe, a duplex stream reports EOF once the server drops its end
+    let (responses, server_writer) = io::duplex(BUFFER_SIZE);
+    tokio::spawn(async move {
+        let mut writer = writer;
+        let mut responses = BufReader::new(responses).lines();
+        loop {
+            let message = tokio::select! {
+                line = responses.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                Some(notification) = notifications.recv() => notification,
+            };
+            let written = async {
+                writer.write_all(message.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await
+            };
+            if let Err(e) = written.await {
+                tracing::error!(?e, "failed to write to the client");
+                break;
+            }
+        }
+    });
+    server_writer
+}
@@ -0,0 +1,29 @@
+use crate::transport::notifications::with_notifications;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_notifications_interleave_with_responses() {
+    let (client, server) = tokio::io::duplex(1 << 12);
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut server_writer = with_notifications(server, rx);
+    let mut lines = BufReader::new(client).lines();
+
+    server_writer.write_all(b"{\"id\":1}\n").await.unwrap();
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "{\"id\":1}");
+
+    tx.send("{\"method\":\"notifications/resources/updated\"}".to_string()).unwrap();
+    assert_eq!(
+        lines.next_line().await.unwrap().unwrap(),
+        "{\"method\":\"notifications/resources/updated\"}"
+    );
+
+    // A response written in pieces still arrives as one line
+    server_writer.write_all(b"{\"id\":").await.unwrap();
+    server_writer.write_all(b"2}\n").await.unwrap();
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "{\"id\":2}");
+
+    // Closing the server side ends the stream
+    drop(server_writer);
+    assert_eq!(lines.next_line().await.unwrap(), None);
+}
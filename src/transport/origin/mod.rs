@@ -0,0 +1,5 @@
+mod origin;
+pub use origin::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use reqwest::Url;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::transport::streamable_http::SESSION_HEADER;
+
+/// Which browser origins may use the server. Loopback origins are always allowed;
+/// others must be listed, so a page on another site cannot reach a locally bound
+/// server through DNS rebinding. As same-origin requests carry no `Origin`, a server
+/// bound to loopback also checks the `Host` the request was sent to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginPolicy {
+    allowed: Arc<Vec<String>>,
+    allowed_hosts: Arc<Vec<String>>,
+    validate_host: bool,
+}
+
+impl OriginPolicy {
+    /// Allow `origins` (e.g. `https://app.example.com`) besides loopback ones; `*` allows any.
+    pub fn new<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let allowed = origins
+            .into_iter()
+            .map(|origin| origin.as_ref().trim().trim_end_matches('/').to_ascii_lowercase())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        Self {
+            allowed: Arc::new(allowed),
+            ..Self::default()
+        }
+    }
+
+    /// Allow requests addressed to `hosts` (e.g. `mcp.internal`, without port) besides
+    /// loopback names and the hosts of the allowed origins; `*` allows any.
+    pub fn with_allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let hosts = hosts
+            .into_iter()
+            .map(|host| host.as_ref().trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        self.allowed_hosts = Arc::new(hosts);
+        self
+    }
+
+    /// Reject requests whose `Host` is not allowed, as a server bound to loopback should.
+    pub fn with_host_validation(mut self, validate_host: bool) -> Self {
+        self.validate_host = validate_host;
+        self
+    }
+
+    pub fn allows_any(&self) -> bool {
+        self.allowed.iter().any(|origin| origin == "*")
+    }
+
+    pub fn is_allowed(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/').to_ascii_lowercase();
+        self.allows_any() || is_loopback_origin(&origin) || self.allowed.contains(&origin)
+    }
+
+    /// Whether a request addressed to `host` (a `Host` header value, with or without port) is served.
+    pub fn is_allowed_host(&self, host: &str) -> bool {
+        if !self.validate_host || self.allowed_hosts.iter().any(|allowed| allowed == "*") {
+            return true;
+        }
+        let Some(host) = host_name(&format!("http://{}", host.trim())) else { return false };
+        is_loopback_host(&host)
+            || self.allowed_hosts.contains(&host)
+            || self.allowed.iter().filter_map(|origin| host_name(origin)).any(|origin| origin == host)
+    }
+
+    /// CORS headers for the allowed origins, exposing the headers MCP clients read.
+    pub fn cors_layer(&self) -> CorsLayer {
+        let policy = self.clone();
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin.to_str().is_ok_and(|origin| policy.is_allowed(origin))
+            }))
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers([
+                header::ACCEPT,
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                HeaderName::from_static(SESSION_HEADER),
+                HeaderName::from_static("last-event-id"),
+                HeaderName::from_static("mcp-protocol-version"),
+            ])
+            .expose_headers([HeaderName::from_static(SESSION_HEADER), header::WWW_AUTHENTICATE])
+    }
+}
+
+fn host_name(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(str::to_ascii_lowercase)
+}
+
+fn is_loopback_origin(origin: &str) -> bool {
+    host_name(origin).is_some_and(|host| is_loopback_host(&host))
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Middleware rejecting browser requests from origins the policy does not allow.
+/// Requests without an `Origin` header, from non-browser clients, pass. With host
+/// validation, requests addressed to other hosts are rejected too.
+pub async fn validate_origin(State(policy): State<OriginPolicy>, request: Request, next: Next) -> Response {
+    // HTTP/2 requests carry the host in the URI instead of a header
+    let host = match request.headers().get(header::HOST) {
+        Some(host) => host.to_str().ok().map(str::to_string),
+        None => request.uri().authority().map(|authority| authority.to_string()),
+    };
+    if policy.validate_host && !host.as_deref().is_some_and(|host| policy.is_allowed_host(host)) {
+        tracing::warn!(?host, "rejected request addressed to a disallowed host");
+        return (StatusCode::FORBIDDEN, "Host not allowed").into_response();
+    }
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let allowed = origin.to_str().is_ok_and(|origin| policy.is_allowed(origin));
+        if !allowed {
+            tracing::warn!(?origin, "rejected request from a disallowed origin");
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    next.run(request).await
+}
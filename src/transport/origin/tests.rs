@@ -0,0 +1,115 @@
+use crate::transport::auth::{Auth, AuthConfig};
+use crate::transport::http_sse_server::App;
+use crate::transport::origin::OriginPolicy;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use tower::ServiceExt;
+
+#[test]
+fn test_origin_policy() {
+    let policy = OriginPolicy::new(["https://App.example.com/", " "]);
+    assert!(policy.is_allowed("https://app.example.com"));
+    assert!(policy.is_allowed("http://localhost:5173"));
+    assert!(policy.is_allowed("http://127.0.0.1:8080"));
+    assert!(policy.is_allowed("http://[::1]:8080"));
+    assert!(policy.is_allowed("http://dev.localhost"));
+    assert!(!policy.is_allowed("http://app.example.com"));
+    assert!(!policy.is_allowed("http://localhost.evil.com"));
+    assert!(!policy.is_allowed("null"));
+    assert!(!policy.allows_any());
+
+    let any = OriginPolicy::new(["*"]);
+    assert!(any.allows_any());
+    assert!(any.is_allowed("https://anything.example.net"));
+}
+
+fn ping(origin: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri("/mcp")
+        .header("accept", "application/json")
+        .header("content-type", "application/json");
+    if let Some(origin) = origin {
+        builder = builder.header(header::ORIGIN, origin);
+    }
+    builder
+        .body(Body::from(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_disallowed_origins_are_rejected() {
+    let app = App::new().with_origins(OriginPolicy::new(["https://app.example.com"]));
+
+    // DNS rebinding: a foreign page resolving to the local server
+    let response = app.router().oneshot(ping(Some("http://attacker.example:8080"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.router().oneshot(ping(Some("https://app.example.com"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+    let exposed = response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS].to_str().unwrap();
+    assert!(exposed.contains("mcp-session-id"));
+
+    // Clients other than browsers send no Origin
+    let response = app.router().oneshot(ping(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_preflight_skips_authentication() {
+    let app = App::new()
+        .with_origins(OriginPolicy::new(["https://app.example.com"]))
+        .with_auth(Auth::new(AuthConfig::default().with_token_list("secret")));
+    let preflight = Request::builder()
+        .method("OPTIONS")
+        .uri("/sse")
+        .header(header::ORIGIN, "https://app.example.com")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.router().oneshot(preflight).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+    let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+    assert!(allowed.contains("authorization"));
+}
+
+#[test]
+fn test_host_policy() {
+    let policy = OriginPolicy::new(["https://app.example.com"]).with_allowed_hosts(["MCP.internal"]);
+    // Hosts are only checked when bound to loopback
+    assert!(policy.is_allowed_host("attacker.example"));
+
+    let policy = policy.with_host_validation(true);
+    assert!(policy.is_allowed_host("localhost:8080"));
+    assert!(policy.is_allowed_host("127.0.0.1:8080"));
+    assert!(policy.is_allowed_host("[::1]:8080"));
+    assert!(policy.is_allowed_host("app.example.com"));
+    assert!(policy.is_allowed_host("mcp.internal:8080"));
+    assert!(!policy.is_allowed_host("attacker.example:8080"));
+    assert!(!policy.is_allowed_host("localhost.attacker.example"));
+    assert!(!policy.is_allowed_host(""));
+}
+
+#[tokio::test]
+async fn test_rebound_hosts_are_rejected() {
+    let app = App::new().with_origins(OriginPolicy::default().with_host_validation(true));
+    let with_host = |host: Option<&str>| {
+        let mut request = ping(None);
+        if let Some(host) = host {
+            request.headers_mut().insert(header::HOST, host.parse().unwrap());
+        }
+        request
+    };
+
+    // A same-origin request from a rebound page carries its own host, but no Origin
+    let response = app.router().oneshot(with_host(Some("attacker.example:8080"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.router().oneshot(with_host(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.router().oneshot(with_host(Some("localhost:8080"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
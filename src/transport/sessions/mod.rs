@@ -0,0 +1,5 @@
+mod sessions;
+pub use sessions::*;
+
+#[cfg(test)]
+mod tests;
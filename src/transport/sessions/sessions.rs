@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+
+use crate::transport::auth::TokenPolicy;
+
+/// Concurrent sessions allowed across all network transports, unless configured.
+pub const DEFAULT_MAX_SESSIONS: usize = 100;
+/// Sessions without a request for this long are closed, unless configured.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+pub type SessionId = Arc<str>;
+
+/// A new random session id, hard enough to guess to serve as the credential of a session.
+pub fn new_session_id() -> SessionId {
+    Arc::from(format!("{:032x}", rand::random::<u128>()))
+}
+
+/// Limits applied to HTTP sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    pub max_sessions: usize,
+    pub idle_timeout: Duration,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+/// An active session, as shown by the admin endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    /// `sse`, `streamable-http`, `websocket` or `unix`.
+    pub transport: &'static str,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub idle_seconds: u64,
+    pub requests: u64,
+    /// Name of the token that opened the session, when authentication is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+struct Entry {
+    transport: &'static str,
+    created_at: SystemTime,
+    last_active: Instant,
+    requests: u64,
+    owner: Option<Arc<TokenPolicy>>,
+    closed: CancellationToken,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooManySessions(pub usize);
+
+impl std::fmt::Display for TooManySessions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "too many sessions (limit {})", self.0)
+    }
+}
+
+impl std::error::Error for TooManySessions {}
+
+/// Sessions of every network transport, with their activity. Closing a session
+/// cancels its token; the transport owning it then ends the session's tasks.
+#[derive(Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<HashMap<SessionId, Entry>>>,
+    pub limits: SessionLimits,
+}
+
+impl SessionRegistry {
+    pub fn new(limits: SessionLimits) -> Self {
+        Self {
+            sessions: Default::default(),
+            limits,
+        }
+    }
+
+    /// Register a new session, unless the limit is reached. The token is cancelled when it closes.
+    pub fn open(&self, id: SessionId, transport: &'static str) -> Result<CancellationToken, TooManySessions> {
+        self.open_as(id, transport, None)
+    }
+
+    /// Register a new session that only `owner` may use.
+    pub fn open_as(
+        &self,
+        id: SessionId,
+        transport: &'static str,
+        owner: Option<Arc<TokenPolicy>>,
+    ) -> Result<CancellationToken, TooManySessions> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if sessions.len() >= self.limits.max_sessions {
+            return Err(TooManySessions(self.limits.max_sessions));
+        }
+        let closed = CancellationToken::new();
+        sessions.insert(
+            id,
+            Entry {
+                transport,
+                created_at: SystemTime::now(),
+                last_active: Instant::now(),
+                requests: 0,
+                owner,
+                closed: closed.clone(),
+            },
+        );
+        Ok(closed)
+    }
+
+    /// Record a request on a session. Returns false if the session is unknown.
+    pub fn touch(&self, id: &str) -> bool {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get_mut(id) {
+            Some(entry) => {
+                entry.last_active = Instant::now();
+                entry.requests += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether `owner` may use an open session: its own, or one opened without authentication.
+    pub fn is_owner(&self, id: &str, owner: Option<&TokenPolicy>) -> bool {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.get(id).is_some_and(|entry| {
+            entry
+                .owner
+                .as_deref()
+                .is_none_or(|entry_owner| owner.is_some_and(|owner| owner.id == entry_owner.id))
+        })
+    }
+
+    /// Close a session. Returns false if it was not open.
+    pub fn close(&self, id: &str) -> bool {
+        let entry = self.sessions.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        match entry {
+            Some(entry) => {
+                entry.closed.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close sessions idle for longer than the idle timeout; returns how many were closed.
+    pub fn close_idle(&self) -> usize {
+        let timeout = self.limits.idle_timeout;
+        let idle: Vec<SessionId> = {
+            let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+            sessions
+                .iter()
+                .filter(|(_, entry)| entry.last_active.elapsed() > timeout)
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+        for id in &idle {
+            tracing::info!(session_id = %id, "closing idle session");
+            self.close(id);
+        }
+        idle.len()
+    }
+
+    /// Close idle sessions periodically, for as long as the runtime lives.
+    pub fn spawn_reaper(&self) {
+        let registry = self.clone();
+        let period = (self.limits.idle_timeout / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                registry.close_idle();
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Active sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<SessionInfo> = sessions
+            .iter()
+            .map(|(id, entry)| SessionInfo {
+                id: id.to_string(),
+                transport: entry.transport,
+                created_at: entry.created_at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+                idle_seconds: entry.last_active.elapsed().as_secs(),
+                requests: entry.requests,
+                owner: entry.owner.as_ref().map(|owner| owner.name.clone()),
+            })
+            .collect();
+        list.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        list
+    }
+}
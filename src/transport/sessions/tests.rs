@@ -0,0 +1,46 @@
+use crate::transport::sessions::{SessionLimits, SessionRegistry, TooManySessions};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_registry_enforces_session_limit() {
+    let registry = SessionRegistry::new(SessionLimits {
+        max_sessions: 2,
+        idle_timeout: Duration::from_secs(60),
+    });
+    let first = registry.open(Arc::from("a"), "sse").unwrap();
+    registry.open(Arc::from("b"), "streamable-http").unwrap();
+    assert_eq!(registry.open(Arc::from("c"), "sse"), Err(TooManySessions(2)));
+
+    // Closing frees a slot and cancels the session's token
+    assert!(registry.close("a"));
+    assert!(first.is_cancelled());
+    assert!(!registry.close("a"));
+    assert!(registry.open(Arc::from("c"), "sse").is_ok());
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn test_registry_tracks_activity_and_closes_idle_sessions() {
+    let registry = SessionRegistry::new(SessionLimits {
+        max_sessions: 10,
+        idle_timeout: Duration::from_millis(50),
+    });
+    let idle = registry.open(Arc::from("idle"), "sse").unwrap();
+    let busy = registry.open(Arc::from("busy"), "streamable-http").unwrap();
+    assert!(registry.touch("busy"));
+    assert!(!registry.touch("unknown"));
+
+    let sessions = registry.list();
+    assert_eq!(sessions.len(), 2);
+    let busy_info = sessions.iter().find(|s| s.id == "busy").unwrap();
+    assert_eq!(busy_info.transport, "streamable-http");
+    assert_eq!(busy_info.requests, 1);
+
+    std::thread::sleep(Duration::from_millis(80));
+    registry.touch("busy");
+    assert_eq!(registry.close_idle(), 1);
+    assert!(idle.is_cancelled());
+    assert!(!busy.is_cancelled());
+    assert_eq!(registry.list().iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), ["busy"]);
+}
@@ -0,0 +1,6 @@
+mod streamable_http;
+
+pub use streamable_http::*;
+
+#[cfg(test)]
+mod tests;
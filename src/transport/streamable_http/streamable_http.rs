@@ -0,0 +1,404 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::post,
+    Extension, Json, Router,
+};
+use futures::{stream, stream::BoxStream, StreamExt};
+use mcp_core::protocol::{ErrorData, JsonRpcRequest, JsonRpcResponse, INTERNAL_ERROR, INVALID_REQUEST, PARSE_ERROR};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use tower_service::Service;
+
+use crate::tools::docs::subscriptions::DEFAULT_POLL_INTERVAL;
+use crate::tools::{DocRouter, DocService};
+use crate::transport::auth::TokenPolicy;
+use crate::transport::sessions::{new_session_id, SessionId, SessionRegistry};
+
+/// Header carrying the session id assigned in the `initialize` response.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+/// Server messages kept per session for clients resuming the GET stream.
+const EVENT_LOG_SIZE: usize = 256;
+/// POST response streams kept per session for clients resuming one of them.
+const RESPONSE_STREAMS_KEPT: usize = 64;
+/// Stream number of server-initiated messages; POST response streams are numbered from 1.
+const GET_STREAM: u64 = 0;
+
+/// An SSE event id, `<stream>-<index>`, so resuming replays only the stream the event came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct EventId {
+    stream: u64,
+    index: u64,
+}
+
+impl EventId {
+    fn parse(value: &str) -> Option<Self> {
+        let (stream, index) = value.trim().split_once('-')?;
+        Some(Self {
+            stream: stream.parse().ok()?,
+            index: index.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.stream, self.index)
+    }
+}
+
+#[derive(Default)]
+struct EventLog {
+    next_index: u64,
+    events: VecDeque<(u64, String)>,
+    last_stream: u64,
+    responses: VecDeque<(u64, Vec<String>)>,
+}
+
+/// Numbers every message a session sends over SSE, so a client can resume with `Last-Event-ID`.
+#[derive(Clone)]
+struct EventStream {
+    log: Arc<Mutex<EventLog>>,
+    live: broadcast::Sender<(EventId, String)>,
+}
+
+impl EventStream {
+    fn new() -> Self {
+        let (live, _) = broadcast::channel(EVENT_LOG_SIZE);
+        Self {
+            log: Default::default(),
+            live,
+        }
+    }
+
+    /// Record a server-initiated message and send it to open GET streams.
+    fn publish(&self, message: String) {
+        let id = {
+            let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+            let index = log.next_index;
+            log.next_index += 1;
+            log.events.push_back((index, message.clone()));
+            if log.events.len() > EVENT_LOG_SIZE {
+                log.events.pop_front();
+            }
+            EventId {
+                stream: GET_STREAM,
+                index,
+            }
+        };
+        let _ = self.live.send((id, message));
+    }
+
+    /// Number the responses to one POST request as a stream of their own and keep them for resumption.
+    fn record_responses(&self, messages: Vec<String>) -> Vec<(EventId, String)> {
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        log.last_stream += 1;
+        let stream = log.last_stream;
+        log.responses.push_back((stream, messages.clone()));
+        if log.responses.len() > RESPONSE_STREAMS_KEPT {
+            log.responses.pop_front();
+        }
+        (0..).map(|index| EventId { stream, index }).zip(messages).collect()
+    }
+
+    /// The messages of the stream `last_event_id` came from that follow it. Resuming the GET
+    /// stream (or opening it without an id) continues with live messages; a POST response
+    /// stream ends after its remaining responses.
+    fn resume(&self, last_event_id: Option<EventId>) -> BoxStream<'static, (EventId, String)> {
+        if let Some(last) = last_event_id.filter(|id| id.stream != GET_STREAM) {
+            let log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+            let replay: Vec<(EventId, String)> = log
+                .responses
+                .iter()
+                .find(|(stream, _)| *stream == last.stream)
+                .map(|(stream, messages)| {
+                    (0..)
+                        .map(|index| EventId { stream: *stream, index })
+                        .zip(messages.iter().cloned())
+                        .filter(|(id, _)| id.index > last.index)
+                        .collect()
+                })
+                .unwrap_or_default();
+            return stream::iter(replay).boxed();
+        }
+
+        // Subscribe before reading the log so no message falls in between
+        let receiver = self.live.subscribe();
+        let replay: Vec<(EventId, String)> = match last_event_id {
+            Some(last) => {
+                let log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+                log.events
+                    .iter()
+                    .filter(|(index, _)| *index > last.index)
+                    .map(|(index, message)| {
+                        let id = EventId {
+                            stream: GET_STREAM,
+                            index: *index,
+                        };
+                        (id, message.clone())
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let seen = replay.last().map(|(id, _)| id.index).or(last_event_id.map(|id| id.index));
+        let live = stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |(id, _)| futures::future::ready(seen.is_none_or(|seen| id.index > seen)));
+        stream::iter(replay).chain(live).boxed()
+    }
+}
+
+/// A client session: its service and the messages sent to it over SSE.
+struct Session {
+    service: DocService,
+    events: EventStream,
+    forwarder: tokio::task::JoinHandle<()>,
+}
+
+impl Session {
+    fn new(router: DocRouter, poll_interval: Duration, allowed_tools: Option<Arc<BTreeSet<String>>>) -> Self {
+        let (service, mut notifications) = DocService::with_subscriptions(router, poll_interval);
+        let service = service.with_allowed_tools(allowed_tools);
+        let events = EventStream::new();
+        let forwarder = {
+            let events = events.clone();
+            tokio::spawn(async move {
+                while let Some(notification) = notifications.recv().await {
+                    events.publish(notification);
+                }
+            })
+        };
+        Self {
+            service,
+            events,
+            forwarder,
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // Ends the session's GET streams and stops its release polling
+        self.forwarder.abort();
+    }
+}
+
+/// The Streamable HTTP transport: a single `/mcp` endpoint taking JSON-RPC
+/// messages by POST, server-initiated messages by GET and session ends by DELETE.
+#[derive(Clone)]
+pub struct StreamableHttp {
+    sessions: Arc<RwLock<HashMap<SessionId, Arc<Session>>>>,
+    /// Configured router whose cache and HTTP client every session shares.
+    pub router: DocRouter,
+    /// How often each session checks its subscribed crates for new releases.
+    pub poll_interval: Duration,
+    /// Session limits and activity, shared with the other transports.
+    pub registry: SessionRegistry,
+}
+
+impl Default for StreamableHttp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamableHttp {
+    pub fn new() -> Self {
+        Self {
+            sessions: Default::default(),
+            router: DocRouter::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            registry: SessionRegistry::default(),
+        }
+    }
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+    pub fn with_router(mut self, router: DocRouter) -> Self {
+        self.router = router;
+        self
+    }
+    pub fn with_registry(mut self, registry: SessionRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/mcp", post(post_handler).get(get_handler).delete(delete_handler))
+            .with_state(self.clone())
+    }
+    pub async fn session_count(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+
+    async fn session(&self, headers: &HeaderMap, owner: Option<&TokenPolicy>) -> Result<(SessionId, Arc<Session>), Response> {
+        let Some(id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+            return Err((StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response());
+        };
+        match self.sessions.read().await.get_key_value(id) {
+            // Sessions opened with another token look like unknown ones
+            Some((id, session)) if self.registry.is_owner(id, owner) => {
+                self.registry.touch(id);
+                Ok((id.clone(), session.clone()))
+            }
+            _ => Err((StatusCode::NOT_FOUND, "Session not found").into_response()),
+        }
+    }
+}
+
+fn error_response(id: Option<u64>, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(ErrorData { code, message, data: None }),
+    }
+}
+
+fn accepts(headers: &HeaderMap, mime_type: &str) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|accept| accept.contains(mime_type) || accept.contains("*/*"))
+}
+
+fn sse_event((id, message): (EventId, String)) -> Result<Event, Infallible> {
+    Ok(Event::default().id(id.to_string()).event("message").data(message))
+}
+
+async fn post_handler(
+    State(app): State<StreamableHttp>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let (messages, batch) = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(messages)) => (messages, true),
+        Ok(message) => (vec![message], false),
+        Err(e) => {
+            let error = error_response(None, PARSE_ERROR, format!("Parse error: {}", e));
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let initialize = messages.iter().any(|message| message["method"] == "initialize");
+    let owner = policy.as_deref().map(Arc::as_ref);
+    let (session_id, session) = match app.session(&headers, owner).await {
+        Ok(session) => session,
+        Err(_) if initialize && !headers.contains_key(SESSION_HEADER) => {
+            let id = new_session_id();
+            let closed = match app.registry.open_as(id.clone(), "streamable-http", policy.as_deref().cloned()) {
+                Ok(closed) => closed,
+                Err(e) => {
+                    let error = error_response(None, INTERNAL_ERROR, e.to_string());
+                    return (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response();
+                }
+            };
+            let allowed_tools = policy.as_ref().and_then(|policy| policy.tools.clone()).map(Arc::new);
+            let session = Arc::new(Session::new(app.router.for_session(), app.poll_interval, allowed_tools));
+            app.sessions.write().await.insert(id.clone(), session.clone());
+            // Forget the session once it is closed by DELETE, the idle timeout or an admin
+            let sessions = app.sessions.clone();
+            let closed_id = id.clone();
+            tokio::spawn(async move {
+                closed.cancelled().await;
+                sessions.write().await.remove(&closed_id);
+            });
+            tracing::info!(session_id = %id, "new streamable HTTP session");
+            (id, session)
+        }
+        Err(response) => return response,
+    };
+
+    let mut responses = Vec::new();
+    for message in messages {
+        // Notifications and responses to the server's requests need no reply
+        if message.get("method").is_none() || message.get("id").is_none_or(Value::is_null) {
+            continue;
+        }
+        let response = match serde_json::from_value::<JsonRpcRequest>(message) {
+            Ok(request) => {
+                let id = request.id;
+                let mut service = session.service.clone();
+                match service.call(request).await {
+                    Ok(response) => response,
+                    Err(e) => error_response(id, INTERNAL_ERROR, e.to_string()),
+                }
+            }
+            Err(e) => error_response(None, INVALID_REQUEST, format!("Invalid request: {}", e)),
+        };
+        responses.push(serde_json::to_value(response).unwrap_or(Value::Null));
+    }
+
+    let session_header = HeaderValue::from_str(&session_id).expect("session ids are hex");
+    if responses.is_empty() {
+        return (StatusCode::ACCEPTED, [(SESSION_HEADER, session_header)]).into_response();
+    }
+    if !accepts(&headers, "application/json") && accepts(&headers, "text/event-stream") {
+        // Numbered as a stream of their own, so a dropped response can be replayed
+        let messages = responses.iter().map(Value::to_string).collect();
+        let stream = stream::iter(session.events.record_responses(messages)).map(sse_event);
+        return ([(SESSION_HEADER, session_header)], Sse::new(stream)).into_response();
+    }
+    let body = if batch { Value::Array(responses) } else { responses.remove(0) };
+    ([(SESSION_HEADER, session_header)], Json(body)).into_response()
+}
+
+async fn get_handler(
+    State(app): State<StreamableHttp>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+    headers: HeaderMap,
+) -> Response {
+    if !accepts(&headers, "text/event-stream") {
+        return (StatusCode::NOT_ACCEPTABLE, "GET requires Accept: text/event-stream").into_response();
+    }
+    let owner = policy.as_deref().map(Arc::as_ref);
+    let (session_id, session) = match app.session(&headers, owner).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+    let last_event_id = headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(EventId::parse);
+    tracing::debug!(%session_id, ?last_event_id, "opening server-initiated stream");
+    let stream = session.events.resume(last_event_id).map(sse_event);
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+async fn delete_handler(
+    State(app): State<StreamableHttp>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+    headers: HeaderMap,
+) -> Response {
+    let owner = policy.as_deref().map(Arc::as_ref);
+    let (session_id, _) = match app.session(&headers, owner).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+    app.sessions.write().await.remove(&session_id);
+    app.registry.close(&session_id);
+    tracing::info!(%session_id, "streamable HTTP session ended");
+    StatusCode::NO_CONTENT.into_response()
+}
@@ -0,0 +1,115 @@
+use crate::transport::streamable_http::{StreamableHttp, SESSION_HEADER};
+use axum::body::Body;
+use axum::http::{Request, Response, StatusCode};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tower::ServiceExt;
+
+fn request(method: &str, session: Option<&str>, accept: &str, body: Option<Value>) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri("/mcp").header("accept", accept);
+    if let Some(session) = session {
+        builder = builder.header(SESSION_HEADER, session);
+    }
+    match body {
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+fn initialize() -> Value {
+    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })
+}
+
+async fn send(app: &StreamableHttp, request: Request<Body>) -> Response<Body> {
+    app.router().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_initialize_creates_session() {
+    let app = StreamableHttp::new();
+    let response = send(&app, request("POST", None, "application/json, text/event-stream", Some(initialize()))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(SESSION_HEADER));
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(app.session_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_requests_need_a_known_session() {
+    let app = StreamableHttp::new();
+    let ping = json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" });
+
+    let response = send(&app, request("POST", None, "application/json", Some(ping.clone()))).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = send(&app, request("POST", Some("no-such-session"), "application/json", Some(ping))).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = send(&app, request("POST", None, "application/json", None)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_notifications_are_accepted_and_delete_ends_session() {
+    let app = StreamableHttp::new();
+    let response = send(&app, request("POST", None, "application/json", Some(initialize()))).await;
+    let session = response.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    let response = send(&app, request("POST", Some(&session), "application/json", Some(initialized))).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    let response = send(&app, request("DELETE", Some(&session), "application/json", None)).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(app.session_count().await, 0);
+
+    let response = send(&app, request("DELETE", Some(&session), "application/json", None)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// The whole body of an SSE stream that ends.
+async fn full_body(body: Body) -> String {
+    let bytes = tokio::time::timeout(Duration::from_secs(5), axum::body::to_bytes(body, usize::MAX))
+        .await
+        .expect("stream did not end within 5s")
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_sse_responses_can_be_resumed() {
+    let app = StreamableHttp::new();
+    let response = send(&app, request("POST", None, "text/event-stream", Some(initialize()))).await;
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let session = response.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+    let first = full_body(response.into_body()).await;
+    assert!(first.contains("id: 1-0"), "{}", first);
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 2, "method": "ping" },
+        { "jsonrpc": "2.0", "id": 3, "method": "tools/list" },
+    ]);
+    let response = send(&app, request("POST", Some(&session), "text/event-stream", Some(batch))).await;
+    let second = full_body(response.into_body()).await;
+    assert!(second.contains("id: 2-0") && second.contains("id: 2-1"), "{}", second);
+
+    // A client that lost the last response of a stream picks up only that stream again
+    let mut resume = request("GET", Some(&session), "text/event-stream", None);
+    resume.headers_mut().insert("last-event-id", "2-0".parse().unwrap());
+    let response = send(&app, resume).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let replayed = full_body(response.into_body()).await;
+    assert!(replayed.contains("id: 2-1") && replayed.contains("\"id\":3"), "{}", replayed);
+    assert!(!replayed.contains("\"id\":2") && !replayed.contains("\"id\":1"), "{}", replayed);
+
+    // Responses on other streams are never replayed
+    let mut resume = request("GET", Some(&session), "text/event-stream", None);
+    resume.headers_mut().insert("last-event-id", "1-0".parse().unwrap());
+    assert_eq!(full_body(send(&app, resume).await.into_body()).await, "");
+
+    let response = send(&app, request("GET", Some(&session), "application/json", None)).await;
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+}
@@ -0,0 +1,5 @@
+mod tls;
+pub use tls::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,85 @@
+use crate::transport::tls::Tls;
+use axum::{routing::get, Router};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_rustls::rustls::{
+    crypto::ring,
+    pki_types::{pem::PemObject, CertificateDer, ServerName},
+    ClientConfig, RootCertStore,
+};
+use tokio_rustls::TlsConnector;
+
+fn testdata(file: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/transport/tls/testdata").join(file)
+}
+
+fn certificate(file: &str) -> CertificateDer<'static> {
+    CertificateDer::from_pem_file(testdata(file)).unwrap()
+}
+
+/// A scratch directory holding copies of the first certificate and key.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cratedocs-tls-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(testdata("cert1.pem"), dir.join("cert.pem")).unwrap();
+    std::fs::copy(testdata("key1.pem"), dir.join("key.pem")).unwrap();
+    dir
+}
+
+#[test]
+fn test_load_rejects_bad_files() {
+    assert!(Tls::load(testdata("cert1.pem"), testdata("key1.pem")).is_ok());
+    let error = Tls::load(testdata("cert1.pem"), testdata("key2.pem")).err().unwrap();
+    assert!(error.to_string().contains("do not match"));
+    assert!(Tls::load(testdata("missing.pem"), testdata("key1.pem")).is_err());
+    assert!(Tls::load(testdata("key1.pem"), testdata("key1.pem")).is_err());
+}
+
+#[test]
+fn test_reload() {
+    let dir = scratch_dir("reload");
+    let tls = Tls::load(dir.join("cert.pem"), dir.join("key.pem")).unwrap();
+    assert_eq!(tls.certificates(), vec![certificate("cert1.pem")]);
+
+    // A half-written renewal keeps the current certificate
+    std::fs::copy(testdata("cert2.pem"), dir.join("cert.pem")).unwrap();
+    assert!(tls.reload().is_err());
+    assert_eq!(tls.certificates(), vec![certificate("cert1.pem")]);
+
+    std::fs::copy(testdata("key2.pem"), dir.join("key.pem")).unwrap();
+    tls.reload().unwrap();
+    assert_eq!(tls.certificates(), vec![certificate("cert2.pem")]);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_serves_https() {
+    let tls = Tls::load(testdata("cert1.pem"), testdata("key1.pem")).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let router = Router::new().route("/", get(|| async { "hello over tls" }));
+    let listener = tls.bind(listener).unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let mut roots = RootCertStore::empty();
+    roots.add(certificate("cert1.pem")).unwrap();
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let tcp = tokio::net::TcpStream::connect(address).await.unwrap();
+    let mut stream = TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from("localhost").unwrap(), tcp)
+        .await
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("hello over tls"));
+}
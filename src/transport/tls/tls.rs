@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+/// How often the certificate and key files are checked for changes.
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+/// Connections that have not finished the handshake by then are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Handshaken connections waiting for the server to accept them.
+const ACCEPT_BACKLOG: usize = 128;
+
+/// Load a PEM certificate chain and private key.
+pub fn load_certified_key(cert: &Path, key: &Path, provider: &CryptoProvider) -> Result<CertifiedKey> {
+    let cert_pem = std::fs::read(cert).with_context(|| format!("reading {}", cert.display()))?;
+    let chain = CertificateDer::pem_slice_iter(&cert_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("parsing certificates in {}", cert.display()))?;
+    if chain.is_empty() {
+        anyhow::bail!("no certificate in {}", cert.display());
+    }
+    let key_pem = std::fs::read(key).with_context(|| format!("reading {}", key.display()))?;
+    let key_der =
+        PrivateKeyDer::from_pem_slice(&key_pem).with_context(|| format!("parsing private key in {}", key.display()))?;
+    CertifiedKey::from_der(chain, key_der, provider).context("certificate and private key do not match")
+}
+
+/// Hands out the most recently loaded certificate.
+#[derive(Debug)]
+struct ReloadingCert {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for ReloadingCert {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+}
+
+/// TLS termination with a certificate and key read from PEM files, picked up
+/// again when either file changes.
+#[derive(Clone)]
+pub struct Tls {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    provider: Arc<CryptoProvider>,
+    cert: Arc<ReloadingCert>,
+    acceptor: TlsAcceptor,
+}
+
+impl Tls {
+    pub fn load(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Result<Self> {
+        let (cert_path, key_path) = (cert_path.into(), key_path.into());
+        let provider = Arc::new(ring::default_provider());
+        let cert = Arc::new(ReloadingCert {
+            current: RwLock::new(Arc::new(load_certified_key(&cert_path, &key_path, &provider)?)),
+        });
+        let mut config = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(cert.clone());
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Self {
+            cert_path,
+            key_path,
+            provider,
+            cert,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Read the files again; on error the current certificate stays in use.
+    pub fn reload(&self) -> Result<()> {
+        let key = load_certified_key(&self.cert_path, &self.key_path, &self.provider)?;
+        *self.cert.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(key);
+        Ok(())
+    }
+
+    /// The certificate chain currently served.
+    pub fn certificates(&self) -> Vec<CertificateDer<'static>> {
+        self.cert.current.read().unwrap_or_else(|e| e.into_inner()).cert.clone()
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Some((modified(&self.cert_path)?, modified(&self.key_path)?))
+    }
+
+    /// Reload whenever the files' modification times change, for as long as the runtime lives.
+    pub fn spawn_reloader(&self, interval: Duration) {
+        let tls = self.clone();
+        tokio::spawn(async move {
+            let mut seen = tls.modified();
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let modified = tls.modified();
+                if modified.is_none() || modified == seen {
+                    continue;
+                }
+                // A renewal may replace the two files one after the other; retry until they match
+                match tls.reload() {
+                    Ok(()) => {
+                        tracing::info!(cert = %tls.cert_path.display(), "reloaded TLS certificate");
+                        seen = modified;
+                    }
+                    Err(e) => tracing::warn!(error = %e, "failed to reload TLS certificate"),
+                }
+            }
+        });
+    }
+
+    /// Accept TLS connections on `listener`, doing handshakes concurrently.
+    pub fn bind(&self, listener: TcpListener) -> Result<TlsListener> {
+        let local_addr = listener.local_addr()?;
+        let (connections, incoming) = mpsc::channel(ACCEPT_BACKLOG);
+        let acceptor = self.acceptor.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "failed to accept connection");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                if connections.is_closed() {
+                    break;
+                }
+                let acceptor = acceptor.clone();
+                let handshaken = connections.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = handshaken.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!(%addr, error = %e, "TLS handshake failed"),
+                        Err(_) => tracing::debug!(%addr, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(TlsListener { incoming, local_addr })
+    }
+}
+
+/// A listener of TLS connections for `axum::serve`.
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(connection) => connection,
+            // The accept loop only stops once this listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
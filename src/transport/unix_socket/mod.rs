@@ -0,0 +1,6 @@
+mod unix_socket;
+
+pub use unix_socket::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,130 @@
+use crate::transport::sessions::SessionRegistry;
+use crate::transport::unix_socket::{bridge, current_uid, SocketListener, UnixSocketServer};
+use serde_json::{json, Value};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cratedocs-unix-{}-{}", name, std::process::id()))
+}
+
+fn initialize() -> String {
+    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }).to_string() + "\n"
+}
+
+/// Serve `app` on a socket in `dir`, returning the socket's path.
+async fn spawn(app: UnixSocketServer, dir: &Path) -> PathBuf {
+    let listener = SocketListener::bind(dir.join("cratedocs.sock")).await.unwrap();
+    let path = listener.path().to_path_buf();
+    tokio::spawn(async move { app.serve(&listener).await });
+    path
+}
+
+/// Wait until the registry holds `count` sessions, as sessions close asynchronously.
+async fn wait_for_sessions(registry: &SessionRegistry, count: usize) {
+    for _ in 0..100 {
+        if registry.list().len() == count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {} sessions, found {:?}", count, registry.list());
+}
+
+#[tokio::test]
+async fn test_bind_restricts_permissions() {
+    let dir = scratch_dir("bind");
+    let path = dir.join("nested").join("cratedocs.sock");
+    let listener = SocketListener::bind(&path).await.unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let dir_mode = std::fs::metadata(path.parent().unwrap()).unwrap().permissions().mode();
+    assert_eq!(dir_mode & 0o777, 0o700);
+    assert_eq!(std::fs::metadata(&path).unwrap().uid(), current_uid());
+    // Only the socket is left where it was bound
+    let entries: Vec<_> = std::fs::read_dir(path.parent().unwrap()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+
+    // The socket is still in use
+    assert!(SocketListener::bind(&path).await.is_err());
+    drop(listener);
+    assert!(!path.exists());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_bind_replaces_stale_socket() {
+    let dir = scratch_dir("stale");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("cratedocs.sock");
+    // A socket file nobody listens on, as left by a crashed daemon
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+    let listener = SocketListener::bind(&path).await.unwrap();
+    assert!(UnixStream::connect(listener.path()).await.is_ok());
+
+    let file = dir.join("not-a-socket");
+    std::fs::write(&file, b"").unwrap();
+    assert!(SocketListener::bind(&file).await.is_err());
+    assert!(file.exists());
+    drop(listener);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_concurrent_sessions() {
+    let dir = scratch_dir("sessions");
+    let app = UnixSocketServer::new();
+    let path = spawn(app.clone(), &dir).await;
+
+    let first = UnixStream::connect(&path).await.unwrap();
+    let (read, mut write) = UnixStream::connect(&path).await.unwrap().into_split();
+    wait_for_sessions(&app.registry, 2).await;
+    assert!(app.registry.list().iter().all(|session| session.transport == "unix"));
+
+    write.write_all(initialize().as_bytes()).await.unwrap();
+    let mut lines = BufReader::new(read).lines();
+    let reply = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("no reply within 5s")
+        .unwrap()
+        .unwrap();
+    let reply: Value = serde_json::from_str(&reply).unwrap();
+    assert_eq!(reply["id"], 1);
+
+    drop(first);
+    wait_for_sessions(&app.registry, 1).await;
+    drop((lines, write));
+    wait_for_sessions(&app.registry, 0).await;
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_bridge() {
+    let dir = scratch_dir("bridge");
+    let path = spawn(UnixSocketServer::new(), &dir).await;
+
+    // Requests end with the input, as when the MCP client closes stdin
+    let input = initialize();
+    let mut output = Vec::new();
+    let bridged = bridge(&path, input.as_bytes(), &mut output);
+    tokio::time::timeout(Duration::from_secs(5), bridged).await.expect("bridge did not finish").unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let reply: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+    assert_eq!(reply["id"], 1);
+
+    assert!(bridge(&dir.join("missing.sock"), &b""[..], Vec::new()).await.is_err());
+
+    // A socket planted by another user is not trusted; only root can plant one in a test
+    if current_uid() == 0 {
+        let planted = dir.join("planted.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&planted).unwrap();
+        std::os::unix::fs::chown(&planted, Some(65534), None).unwrap();
+        let error = bridge(&planted, &b""[..], Vec::new()).await.unwrap_err();
+        assert!(error.to_string().contains("another user"));
+    }
+    let _ = std::fs::remove_dir_all(dir);
+}
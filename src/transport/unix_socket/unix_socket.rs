@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use mcp_server::{ByteTransport, Server};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{self, AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio_util::codec::FramedRead;
+
+use crate::tools::docs::subscriptions::DEFAULT_POLL_INTERVAL;
+use crate::tools::{DocRouter, DocService};
+use crate::transport::jsonrpc_frame_codec::JsonRpcFrameCodec;
+use crate::transport::notifications::with_notifications;
+use crate::transport::sessions::{new_session_id, SessionRegistry};
+
+/// File name of the socket in the default location.
+pub const SOCKET_NAME: &str = "cratedocs.sock";
+/// Only the user running the daemon may connect.
+const SOCKET_MODE: u32 = 0o600;
+/// Mode of socket directories the daemon creates.
+const DIR_MODE: u32 = 0o700;
+const BUFFER_SIZE: usize = 1 << 12;
+
+/// `$XDG_RUNTIME_DIR/cratedocs.sock`, or a per-user name in the temporary directory.
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join(SOCKET_NAME),
+        _ => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+            std::env::temp_dir().join(format!("cratedocs-{}.sock", user))
+        }
+    }
+}
+
+/// A listening socket whose file is removed when it is dropped.
+#[derive(Debug)]
+pub struct SocketListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl SocketListener {
+    /// Listen at `path` with the socket file readable and writable by its owner only.
+    ///
+    /// A socket file left behind by a daemon that is no longer running is replaced;
+    /// a socket that still accepts connections, or any other file, is an error.
+    pub async fn bind(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(DIR_MODE)
+            .create(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("{} exists and is not a socket", path.display());
+            }
+            if UnixStream::connect(&path).await.is_ok() {
+                anyhow::bail!("another server is listening on {}", path.display());
+            }
+        }
+        // Bound in a new private directory and moved into place once its mode is
+        // restricted, so that nobody else can connect in between
+        let staging = parent.join(format!(".cratedocs-{:016x}", rand::random::<u64>()));
+        std::fs::DirBuilder::new()
+            .mode(DIR_MODE)
+            .create(&staging)
+            .with_context(|| format!("creating {}", staging.display()))?;
+        let staged = staging.join(SOCKET_NAME);
+        let listener = UnixListener::bind(&staged)
+            .with_context(|| format!("binding {}", staged.display()))
+            .and_then(|listener| {
+                std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(SOCKET_MODE))
+                    .with_context(|| format!("restricting permissions of {}", staged.display()))?;
+                // Replaces a stale socket atomically
+                std::fs::rename(&staged, &path).with_context(|| format!("moving the socket to {}", path.display()))?;
+                Ok(listener)
+            });
+        let _ = std::fs::remove_dir_all(&staging);
+        Ok(Self { listener: listener?, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SocketListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Serves a line-delimited JSON-RPC session to every client of a Unix domain socket,
+/// so one daemon and its cache can be shared by all of a user's MCP clients.
+#[derive(Clone)]
+pub struct UnixSocketServer {
+    /// Configured router whose cache and HTTP client every session shares.
+    pub router: DocRouter,
+    /// How often each session checks its subscribed crates for new releases.
+    pub poll_interval: Duration,
+    /// Session limits and activity.
+    pub registry: SessionRegistry,
+}
+
+impl Default for UnixSocketServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnixSocketServer {
+    pub fn new() -> Self {
+        Self {
+            router: DocRouter::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            registry: SessionRegistry::default(),
+        }
+    }
+    pub fn with_router(mut self, router: DocRouter) -> Self {
+        self.router = router;
+        self
+    }
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+    pub fn with_registry(mut self, registry: SessionRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Accept clients until the future is dropped, each in its own session.
+    pub async fn serve(&self, listener: &SocketListener) {
+        loop {
+            match listener.listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(self.clone().serve_connection(stream));
+                }
+                Err(e) => {
+                    // e.g. out of file descriptors; retrying at once would spin
+                    tracing::error!(error = %e, "failed to accept a connection");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+
+    async fn serve_connection(self, stream: UnixStream) {
+        // The socket's mode keeps other users out, unless they are root or were let in by a changed mode
+        let peer = stream.peer_cred().map(|cred| cred.uid());
+        if peer.as_ref().ok() != Some(&current_uid()) {
+            tracing::warn!(?peer, "rejecting unix socket connection from another user");
+            return;
+        }
+        let session = new_session_id();
+        let closed = match self.registry.open(session.clone(), "unix") {
+            Ok(closed) => closed,
+            Err(e) => {
+                tracing::warn!(%session, %e, "rejecting unix socket connection");
+                return;
+            }
+        };
+        tracing::info!(%session, "unix socket connection");
+        let (socket_read, socket_write) = stream.into_split();
+        // Unlike a simplex pipe, a duplex stream reports EOF once the reader below drops its end
+        let (mut c2s_write, c2s_read) = io::duplex(BUFFER_SIZE);
+        let (service, notifications) = DocService::with_subscriptions(self.router.for_session(), self.poll_interval);
+        let server = Server::new(service);
+        let transport = ByteTransport::new(c2s_read, with_notifications(socket_write, notifications));
+
+        let reader = async {
+            let mut frames = FramedRead::new(socket_read, JsonRpcFrameCodec);
+            while let Some(Ok(frame)) = frames.next().await {
+                self.registry.touch(&session);
+                if c2s_write.write_all(&frame).await.is_err() || c2s_write.write_u8(b'\n').await.is_err() {
+                    break;
+                }
+            }
+            // Lets the server answer what it has read before it stops
+            drop(c2s_write);
+        };
+
+        tokio::select! {
+            (result, ()) = futures::future::join(server.run(transport), reader) => {
+                let _ = result.inspect_err(|e| tracing::error!(?e, "server run error"));
+            }
+            _ = closed.cancelled() => {}
+        }
+        self.registry.close(&session);
+        tracing::info!(%session, "unix socket session closed");
+    }
+}
+
+/// The effective user id of this process.
+pub fn current_uid() -> u32 {
+    rustix::process::geteuid().as_raw()
+}
+
+/// Connect `input` and `output`, usually stdin and stdout, to the daemon at `path`.
+///
+/// Returns when the daemon closes the connection. The end of `input` is passed on,
+/// so the daemon still answers the requests it has received.
+pub async fn bridge<R, W>(path: &Path, mut input: R, mut output: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    // Another user could have created the socket first, e.g. in a shared temporary directory
+    let owner = std::fs::symlink_metadata(path)
+        .with_context(|| format!("connecting to {}; is `cratedocs unix` running?", path.display()))?
+        .uid();
+    if owner != current_uid() {
+        anyhow::bail!("{} belongs to another user (uid {})", path.display(), owner);
+    }
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("connecting to {}; is `cratedocs unix` running?", path.display()))?;
+    // The file may have been replaced in between; the listening process cannot be
+    let server = stream.peer_cred().context("reading the server's credentials")?.uid();
+    if server != current_uid() {
+        anyhow::bail!("the server on {} runs as another user (uid {})", path.display(), server);
+    }
+    let (mut socket_read, mut socket_write) = stream.into_split();
+    let upstream = async {
+        // A failed write means the daemon has gone, which ends the downstream copy too
+        if io::copy(&mut input, &mut socket_write).await.is_ok() {
+            let _ = socket_write.shutdown().await;
+        }
+        // Keep the connection until the daemon has answered
+        std::future::pending::<()>().await
+    };
+    let downstream = async {
+        io::copy(&mut socket_read, &mut output).await?;
+        output.flush().await
+    };
+    tokio::select! {
+        () = upstream => {}
+        result = downstream => result?,
+    }
+    Ok(())
+}
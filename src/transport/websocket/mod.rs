@@ -0,0 +1,6 @@
+mod websocket;
+
+pub use websocket::*;
+
+#[cfg(test)]
+mod tests;
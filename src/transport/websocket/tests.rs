@@ -0,0 +1,116 @@
+use crate::transport::http_sse_server::App;
+use crate::transport::sessions::SessionRegistry;
+use crate::transport::websocket::WebSocketTransport;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn spawn(app: WebSocketTransport) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.router()).await.unwrap();
+    });
+    format!("ws://{}/ws", address)
+}
+
+/// Wait until the registry holds `count` sessions, as sessions close asynchronously.
+async fn wait_for_sessions(registry: &SessionRegistry, count: usize) {
+    for _ in 0..100 {
+        if registry.list().len() == count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {} sessions, found {:?}", count, registry.list());
+}
+
+/// The next text frame from the server, as JSON.
+async fn next_reply(socket: &mut WsStream) -> Value {
+    loop {
+        match tokio::time::timeout(Duration::from_secs(5), socket.next()).await.expect("no reply within 5s") {
+            Some(Ok(Message::Text(text))) => return serde_json::from_str(&text).unwrap(),
+            Some(Ok(_)) => continue,
+            other => panic!("connection ended: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_session() {
+    let app = WebSocketTransport::new();
+    let (mut socket, _) = tokio_tungstenite::connect_async(spawn(app.clone()).await).await.unwrap();
+
+    let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} });
+    socket.send(Message::text(initialize.to_string())).await.unwrap();
+    assert_eq!(next_reply(&mut socket).await["id"], 1);
+
+    let sessions = app.registry.list();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].transport, "websocket");
+    assert_eq!(sessions[0].requests, 1);
+
+    socket.close(None).await.unwrap();
+    wait_for_sessions(&app.registry, 0).await;
+}
+
+#[tokio::test]
+async fn test_frames_are_single_messages() {
+    let (mut socket, _) = tokio_tungstenite::connect_async(spawn(WebSocketTransport::new()).await).await.unwrap();
+
+    // Newlines inside a frame do not split the message
+    let ping = json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" });
+    socket.send(Message::text(serde_json::to_string_pretty(&ping).unwrap())).await.unwrap();
+    assert_eq!(next_reply(&mut socket).await["id"], 7);
+
+    socket.send(Message::text("{ not json")).await.unwrap();
+    let reply = next_reply(&mut socket).await;
+    assert_eq!(reply["error"]["code"], -32700);
+    assert_eq!(reply["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_unresponsive_client_is_dropped() {
+    let app = WebSocketTransport::new().with_ping_interval(Duration::from_millis(50));
+    // A client that never reads, and so never answers pings
+    let (_socket, _) = tokio_tungstenite::connect_async(spawn(app.clone()).await).await.unwrap();
+    wait_for_sessions(&app.registry, 1).await;
+    wait_for_sessions(&app.registry, 0).await;
+}
+
+#[tokio::test]
+async fn test_zero_ping_interval_is_raised() {
+    let app = WebSocketTransport::new().with_ping_interval(Duration::ZERO);
+    let (mut socket, _) = tokio_tungstenite::connect_async(spawn(app.clone()).await).await.unwrap();
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+    socket.send(Message::text(ping.to_string())).await.unwrap();
+    assert_eq!(next_reply(&mut socket).await["id"], 1);
+}
+
+#[tokio::test]
+async fn test_closing_the_session_disconnects() {
+    let app = WebSocketTransport::new();
+    let (mut socket, _) = tokio_tungstenite::connect_async(spawn(app.clone()).await).await.unwrap();
+    wait_for_sessions(&app.registry, 1).await;
+    let id = app.registry.list()[0].id.clone();
+    assert!(app.registry.close(&id));
+    let end = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(Ok(message)) = socket.next().await {
+            if message.is_close() {
+                break;
+            }
+        }
+    });
+    end.await.expect("connection still open");
+}
+
+#[test]
+fn test_app_counts_websocket_sessions() {
+    let app = App::new();
+    // The WebSocket transport shares the app's session limits
+    assert!(app.websocket.registry.open("ws-1".into(), "websocket").is_ok());
+    assert_eq!(app.registry.list().len(), 1);
+}
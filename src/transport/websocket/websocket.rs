@@ -0,0 +1,230 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::StatusCode,
+    response::Response,
+    routing::get,
+    Extension, Router,
+};
+use futures::{SinkExt, StreamExt};
+use mcp_core::protocol::PARSE_ERROR;
+use mcp_server::{ByteTransport, Server};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{self, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_util::codec::FramedRead;
+use tokio_util::sync::CancellationToken;
+
+use crate::tools::docs::subscriptions::DEFAULT_POLL_INTERVAL;
+use crate::tools::{DocRouter, DocService};
+use crate::transport::auth::TokenPolicy;
+use crate::transport::jsonrpc_frame_codec::JsonRpcFrameCodec;
+use crate::transport::notifications::with_notifications;
+use crate::transport::sessions::{new_session_id, SessionId, SessionRegistry};
+
+/// How often the server pings a client that has not closed its connection.
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+/// Shorter ping intervals are raised to this; a zero interval cannot be timed.
+const MIN_PING_INTERVAL: Duration = Duration::from_millis(10);
+/// Largest message accepted from a client, as for POST bodies of the other transports.
+const MAX_MESSAGE_BYTES: usize = 1 << 22;
+/// Bytes buffered between the socket and the session's server in each direction. When
+/// either side falls behind, the other stops reading, so a client that floods the
+/// server or stops reading is held back by TCP flow control rather than by memory.
+const BUFFER_SIZE: usize = 1 << 16;
+
+/// The WebSocket transport: `/ws` carries one JSON-RPC message per text frame, in
+/// both directions, for a session that lasts as long as the connection.
+#[derive(Clone)]
+pub struct WebSocketTransport {
+    /// Configured router whose cache and HTTP client every session shares.
+    pub router: DocRouter,
+    /// How often each session checks its subscribed crates for new releases.
+    pub poll_interval: Duration,
+    /// How often the client is pinged; it is dropped if nothing arrives between two pings.
+    pub ping_interval: Duration,
+    /// Session limits and activity, shared with the other transports.
+    pub registry: SessionRegistry,
+}
+
+impl Default for WebSocketTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebSocketTransport {
+    pub fn new() -> Self {
+        Self {
+            router: DocRouter::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            registry: SessionRegistry::default(),
+        }
+    }
+    pub fn with_router(mut self, router: DocRouter) -> Self {
+        self.router = router;
+        self
+    }
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+    pub fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+    pub fn with_registry(mut self, registry: SessionRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+    pub fn router(&self) -> Router {
+        Router::new().route("/ws", get(ws_handler)).with_state(self.clone())
+    }
+}
+
+async fn ws_handler(
+    State(app): State<WebSocketTransport>,
+    policy: Option<Extension<Arc<TokenPolicy>>>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let session = new_session_id();
+    let owner = policy.as_deref().cloned();
+    let allowed_tools = policy.as_ref().and_then(|policy| policy.tools.clone()).map(Arc::new);
+    let closed = app.registry.open_as(session.clone(), "websocket", owner).map_err(|e| {
+        tracing::warn!(%session, %e, "rejecting websocket connection");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+    tracing::info!(%session, "websocket connection");
+    let registry = app.registry.clone();
+    let failed_session = session.clone();
+    Ok(upgrade
+        .protocols(["mcp"])
+        .max_message_size(MAX_MESSAGE_BYTES)
+        .on_failed_upgrade(move |e| {
+            tracing::warn!(session = %failed_session, error = %e, "websocket upgrade failed");
+            registry.close(&failed_session);
+        })
+        .on_upgrade(move |socket| serve_socket(app, socket, session, closed, allowed_tools)))
+}
+
+async fn serve_socket(
+    app: WebSocketTransport,
+    socket: WebSocket,
+    session: SessionId,
+    closed: CancellationToken,
+    allowed_tools: Option<Arc<BTreeSet<String>>>,
+) {
+    let (c2s_read, mut c2s_write) = io::simplex(BUFFER_SIZE);
+    let (s2c_read, s2c_write) = io::simplex(BUFFER_SIZE);
+    let (service, notifications) = DocService::with_subscriptions(app.router.for_session(), app.poll_interval);
+    let server = Server::new(service.with_allowed_tools(allowed_tools));
+    let transport = ByteTransport::new(c2s_read, with_notifications(s2c_write, notifications));
+    let (mut sink, mut stream) = socket.split();
+    // Set by every message from the client, cleared by every ping
+    let alive = Arc::new(AtomicBool::new(true));
+    // Answers to frames that never reach the server
+    let (errors, mut error_replies) = mpsc::channel::<String>(16);
+
+    let reader = {
+        let alive = alive.clone();
+        let registry = app.registry.clone();
+        let session = session.clone();
+        async move {
+            while let Some(message) = stream.next().await {
+                alive.store(true, Ordering::Relaxed);
+                let message = match message {
+                    Ok(message) => message,
+                    Err(e) => {
+                        tracing::debug!(%session, error = %e, "websocket read error");
+                        break;
+                    }
+                };
+                let parsed = match message {
+                    Message::Text(text) => serde_json::from_str::<Value>(&text),
+                    Message::Binary(bytes) => serde_json::from_slice::<Value>(&bytes),
+                    // Pings are answered by the socket itself
+                    Message::Ping(_) | Message::Pong(_) => continue,
+                    Message::Close(_) => break,
+                };
+                registry.touch(&session);
+                // Written on one line, as the server reads one message per line
+                let line = match parsed {
+                    Ok(message) => message.to_string(),
+                    Err(e) => {
+                        let error = json!({
+                            "jsonrpc": "2.0",
+                            "id": null,
+                            "error": { "code": PARSE_ERROR, "message": format!("Parse error: {}", e) },
+                        });
+                        if errors.send(error.to_string()).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                // Waits while the server is busy, which stops reading from the socket
+                if c2s_write.write_all(line.as_bytes()).await.is_err() || c2s_write.write_u8(b'\n').await.is_err() {
+                    break;
+                }
+            }
+        }
+    };
+
+    let writer = async {
+        let mut frames = FramedRead::new(s2c_read, JsonRpcFrameCodec);
+        let mut ticker = tokio::time::interval(app.ping_interval.max(MIN_PING_INTERVAL));
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                frame = frames.next() => {
+                    let text = match frame {
+                        Some(Ok(bytes)) => match String::from_utf8(bytes.to_vec()) {
+                            Ok(text) => text,
+                            Err(e) => {
+                                tracing::error!(%session, error = %e, "server sent invalid UTF-8");
+                                break;
+                            }
+                        },
+                        _ => break,
+                    };
+                    // Waits while the client is slow to read, which stops the server writing
+                    if sink.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(error) = error_replies.recv() => {
+                    if sink.send(Message::Text(error.into())).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ticker.tick() => {
+                    if !alive.swap(false, Ordering::Relaxed) {
+                        tracing::info!(%session, "websocket client stopped answering pings");
+                        break;
+                    }
+                    if sink.send(Message::Ping(Default::default())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    };
+
+    tokio::select! {
+        result = server.run(transport) => {
+            let _ = result.inspect_err(|e| tracing::error!(?e, "server run error"));
+        }
+        _ = reader => {}
+        _ = writer => {}
+        _ = closed.cancelled() => {}
+    }
+    app.registry.close(&session);
+    tracing::info!(%session, "websocket session closed");
+}